@@ -0,0 +1,129 @@
+use crate::error::{BiaoError, Result};
+use serde::Serialize;
+use std::process::Command;
+
+/// Authentication state reported by `gh auth status`
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct AuthStatus {
+    pub authenticated: bool,
+    pub login: Option<String>,
+    pub host: Option<String>,
+    pub scopes: Vec<String>,
+}
+
+/// Run `gh auth status` and parse its report
+pub fn auth_status() -> Result<AuthStatus> {
+    let output = Command::new("gh")
+        .args(["auth", "status"])
+        .output()
+        .map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                BiaoError::GhNotFound {
+                    message: "github.com/cli/cli".to_string(),
+                }
+            } else {
+                BiaoError::Io(e)
+            }
+        })?;
+
+    // Depending on the version, gh writes the report to stdout or stderr
+    let text = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let mut status = parse_auth_status(&text);
+    status.authenticated &= output.status.success();
+    Ok(status)
+}
+
+/// Parse the human-readable output of `gh auth status`.
+///
+/// Handles both the current format
+/// (`✓ Logged in to github.com account octocat (keyring)`) and the older one
+/// (`✓ Logged in to github.com as octocat (oauth_token)`).
+pub fn parse_auth_status(text: &str) -> AuthStatus {
+    let mut status = AuthStatus::default();
+
+    for line in text.lines() {
+        let line = line.trim();
+
+        if let Some(rest) = line.split("Logged in to ").nth(1) {
+            if status.authenticated {
+                continue;
+            }
+            let mut words = rest.split_whitespace();
+            status.host = words.next().map(|h| h.to_string());
+            status.login = match words.next() {
+                Some("account") | Some("as") => words.next().map(|l| l.to_string()),
+                _ => None,
+            };
+            status.authenticated = true;
+        } else if let Some(scopes) = line.split("Token scopes:").nth(1) {
+            if !status.scopes.is_empty() {
+                continue;
+            }
+            status.scopes = scopes
+                .split(',')
+                .map(|s| s.trim().trim_matches('\'').trim_matches('"').to_string())
+                .filter(|s| !s.is_empty() && s != "none")
+                .collect();
+        }
+    }
+
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_logged_in() {
+        let text = "github.com
+  ✓ Logged in to github.com account octocat (keyring)
+  - Active account: true
+  - Git operations protocol: https
+  - Token: gho_************************************
+  - Token scopes: 'gist', 'read:org', 'repo', 'workflow'
+";
+        let status = parse_auth_status(text);
+        assert!(status.authenticated);
+        assert_eq!(status.login.as_deref(), Some("octocat"));
+        assert_eq!(status.host.as_deref(), Some("github.com"));
+        assert_eq!(status.scopes, vec!["gist", "read:org", "repo", "workflow"]);
+
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["authenticated"], true);
+        assert_eq!(json["login"], "octocat");
+        assert_eq!(json["host"], "github.com");
+        assert_eq!(json["scopes"][2], "repo");
+    }
+
+    #[test]
+    fn test_parse_legacy_format() {
+        let text = "github.acme.com
+  ✓ Logged in to github.acme.com as hubot (oauth_token)
+  ✓ Token scopes: repo, read:org
+";
+        let status = parse_auth_status(text);
+        assert!(status.authenticated);
+        assert_eq!(status.login.as_deref(), Some("hubot"));
+        assert_eq!(status.host.as_deref(), Some("github.acme.com"));
+        assert_eq!(status.scopes, vec!["repo", "read:org"]);
+    }
+
+    #[test]
+    fn test_parse_not_logged_in() {
+        let status = parse_auth_status(
+            "You are not logged into any GitHub hosts. To log in, run: gh auth login\n",
+        );
+        assert_eq!(status, AuthStatus::default());
+
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["authenticated"], false);
+        assert!(json["login"].is_null());
+        assert_eq!(json["scopes"], serde_json::json!([]));
+    }
+}
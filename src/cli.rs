@@ -1,17 +1,80 @@
-use crate::client::GithubClient;
+use crate::client::{GithubClient, RetryPolicy};
 use crate::error::Result;
 use crate::models::{CreateLabelRequest, UpdateLabelRequest};
-use clap::{Parser, Subcommand};
+use crate::output::{ColorChoice, OutputFormat};
+use clap::{Parser, Subcommand, ValueEnum};
 use colored::Colorize;
 
+/// `println!` for progress and success messages, which `--quiet` silences
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if !crate::output::is_quiet() {
+            println!($($arg)*);
+        }
+    };
+}
+
 #[derive(Parser)]
 #[command(name = "biao")]
 #[command(about = "GitHub label management CLI", long_about = None)]
+#[command(after_help = "Exit codes:
+  0  Success, including dry runs
+  1  Some label operations failed, or --exit-code found differences
+  2  Invalid arguments or config
+  3  GitHub, authentication, network or file error")]
 pub struct Args {
+    /// Output format
+    #[arg(long, alias = "format", global = true, value_enum, default_value_t = OutputFormat::Human)]
+    pub output: OutputFormat,
+
+    /// Shorthand for `--output json`: print only JSON, for piping into `jq`
+    #[arg(long, global = true, conflicts_with = "output")]
+    pub json: bool,
+
+    /// Don't print the "Repository: owner/repo" header
+    #[arg(long, global = true)]
+    pub no_banner: bool,
+
+    /// Only print requested data and errors: no header, progress or success messages
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Print each `gh api` call and its raw response on stderr, tokens redacted;
+    /// with `version`, also report the installed gh and git versions
+    #[arg(short, long, global = true)]
+    pub verbose: bool,
+
+    /// When to use colors: auto (terminal only), always or never
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+
+    /// HTTP statuses to retry, comma-separated (default: any 5xx)
+    #[arg(long, global = true, value_delimiter = ',', value_name = "CODES")]
+    pub retry_on: Vec<u16>,
+
+    /// How many times to retry a request failing with a --retry-on status or no response [default: 3]
+    #[arg(long, global = true, value_name = "N")]
+    pub max_retries: Option<u32>,
+
+    /// Only retry rate-limited requests, never server errors; overrides --retry-on
+    #[arg(long, global = true)]
+    pub retry_on_secondary_only: bool,
+
+    /// Fail on GitHub rate limits instead of waiting for them to reset
+    #[arg(long, global = true)]
+    pub no_wait: bool,
+
+    /// Repository (owner/name) to operate on instead of detecting it from git;
+    /// `apply` takes it repeatedly to apply to several repositories
+    #[arg(long, global = true, value_name = "OWNER/NAME")]
+    pub repo: Vec<String>,
+
     #[command(subcommand)]
     pub command: Commands,
 }
 
+// Parsed once per run; `Apply` carrying most of the flags is fine
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 pub enum Commands {
     /// Authenticate with GitHub (wrapper around `gh auth`)
@@ -21,22 +84,87 @@ pub enum Commands {
     },
 
     /// List all labels
-    List,
+    List {
+        /// Group labels by scheme prefix (`A-`, `P-`, `type/`, ...)
+        #[arg(long)]
+        group_by_prefix: bool,
+
+        /// Instead of listing, show how the labels differ from a template
+        #[arg(long, value_name = "TEMPLATE", conflicts_with = "group_by_prefix")]
+        diff_against_template: Option<String>,
+
+        /// With --diff-against-template, exit with an error when there are differences
+        #[arg(long, requires = "diff_against_template")]
+        exit_code: bool,
+
+        /// With --output json, only include name, color and description
+        #[arg(long)]
+        slim: bool,
+
+        /// Only list labels whose name or description contains this text (case-insensitive),
+        /// or whose name matches it as a glob (e.g. "A-*"); repeatable
+        #[arg(long, value_name = "TEXT", conflicts_with = "diff_against_template")]
+        filter: Vec<String>,
+
+        /// Order labels by name, by color, or default labels first
+        #[arg(long, value_enum)]
+        sort: Option<LabelSort>,
+
+        /// Show how many issues and pull requests (open or closed) use each label;
+        /// one extra request per label
+        #[arg(long, conflicts_with_all = ["group_by_prefix", "slim"])]
+        with_counts: bool,
+    },
+
+    /// Check a config for problems without GitHub or a git repository
+    Validate {
+        /// Path to the config file
+        file: String,
+
+        /// Parse the config as this format instead of going by its extension
+        #[arg(long, value_enum, value_name = "FORMAT")]
+        input_format: Option<crate::config::ConfigFormat>,
+    },
+
+    /// Summarize the repository's labels: totals, descriptions, GitHub defaults
+    /// and the most common colors
+    Stats {
+        /// Also count each label's open issues and pull requests (one request per label)
+        #[arg(long)]
+        with_counts: bool,
+    },
 
     /// Get a specific label
-    Get { name: String },
+    Get {
+        name: String,
+
+        /// Open the issues carrying this label in the browser
+        #[arg(long)]
+        web: bool,
+    },
+
+    /// Check that gh, git, authentication and the repository are set up correctly
+    Doctor,
+
+    /// Print the version of biao; with --verbose, also the installed gh and git
+    /// versions, for bug reports
+    Version,
 
     /// Create a new label
     Create {
         /// Label name
         name: String,
 
-        /// Label color (hex without #, e.g., "ff0000")
-        color: String,
+        /// Label color (hex without #, e.g., "ff0000"); defaults to the repo's default color
+        color: Option<String>,
 
         /// Optional description
         #[arg(short, long)]
         description: Option<String>,
+
+        /// Fail instead of warning when the label's text would be hard to read on its color
+        #[arg(long)]
+        strict_contrast: bool,
     },
 
     /// Update an existing label
@@ -63,15 +191,49 @@ pub enum Commands {
         name: String,
 
         /// Skip confirmation
-        #[arg(short)]
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Delete every label of the repository
+    Clear {
+        /// Skip typing the repository name to confirm
+        #[arg(short, long)]
+        force: bool,
+
+        /// Dry run - show what would be done without making changes
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+
+        /// Keep labels matching this glob; repeatable
+        #[arg(long, value_name = "GLOB")]
+        protect: Vec<String>,
+    },
+
+    /// Delete labels the config file doesn't define
+    Prune {
+        /// Path to TOML config file (default: labels.toml at the repository
+        /// root, else in the current directory)
+        file: Option<String>,
+
+        /// Delete without asking about each label
+        #[arg(short, long)]
         force: bool,
+
+        /// Dry run - show what would be deleted without making changes
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+
+        /// Keep the labels GitHub creates in every new repository
+        #[arg(long)]
+        keep_default: bool,
     },
 
     /// Apply label changes from a TOML config file
     Apply {
-        /// Path to TOML config file (default: labels.toml)
-        #[arg(default_value = "labels.toml")]
-        file: String,
+        /// Path to TOML config file, or `-` to read it from stdin (default:
+        /// labels.toml at the repository root, else in the current directory)
+        file: Option<String>,
 
         /// Dry run - show what would be done without making changes
         #[arg(short = 'n', long)]
@@ -80,6 +242,272 @@ pub enum Commands {
         /// Skip labels that already exist instead of failing
         #[arg(short = 's', long)]
         skip_existing: bool,
+
+        /// Normalize label names before applying
+        #[arg(long, value_enum, default_value_t = LabelNameCase::Preserve)]
+        label_name_case: LabelNameCase,
+
+        /// TOML file remapping colors (old hex → new hex) before applying
+        #[arg(long)]
+        color_theme: Option<String>,
+
+        /// Refuse to run if the config file has uncommitted changes
+        #[arg(long)]
+        require_clean: bool,
+
+        /// List labels that already match the config and count them in the summary
+        #[arg(long)]
+        report_unchanged: bool,
+
+        /// Write the computed plan to this file for a later `execute-plan`
+        #[arg(long, requires = "dry_run")]
+        plan_file: Option<String>,
+
+        /// Repositories to apply to, from the global --repo when given more than once
+        #[arg(skip)]
+        repos: Vec<String>,
+
+        /// Also apply to the repositories listed in this file, one owner/name per line
+        /// (blank lines and `#` comments are skipped)
+        #[arg(long, value_name = "PATH", conflicts_with = "repos_from_org")]
+        repos_file: Option<std::path::PathBuf>,
+
+        /// Apply to every repository of this organization
+        #[arg(long, value_name = "ORG", conflicts_with = "plan_file")]
+        repos_from_org: Option<String>,
+
+        /// With --repos-from-org, include archived repositories
+        #[arg(long, requires = "repos_from_org")]
+        include_archived: bool,
+
+        /// With --repos-from-org, skip repositories whose name matches this glob; repeatable
+        #[arg(long = "exclude-repo", value_name = "GLOB", requires = "repos_from_org")]
+        exclude_repos: Vec<String>,
+
+        /// Number of concurrent gh requests: label changes within a repository
+        /// (default 4), or repositories with --repo or --repos-from-org (default 1)
+        #[arg(long, value_name = "N")]
+        jobs: Option<usize>,
+
+        /// Only process labels matching this glob (e.g. "A-*"); repeatable
+        #[arg(long, value_name = "GLOB")]
+        include: Vec<String>,
+
+        /// Skip labels matching this glob; repeatable, applied after --include
+        #[arg(long, value_name = "GLOB")]
+        exclude: Vec<String>,
+
+        /// Fail without changing anything unless the repository already matches the config
+        #[arg(long, alias = "frozen")]
+        locked: bool,
+
+        /// Don't count differences in this field as drift; repeatable
+        #[arg(long, value_enum)]
+        ignore: Vec<crate::plan::DiffField>,
+
+        /// Warn when the repository would end up with more labels than this
+        #[arg(long, default_value_t = 200)]
+        max_labels: usize,
+
+        /// Fail instead of warning when --max-labels would be exceeded
+        #[arg(long)]
+        strict: bool,
+
+        /// Fail instead of warning when a label's text would be hard to read on its color
+        #[arg(long)]
+        strict_contrast: bool,
+
+        /// Time every gh call and print where the time went
+        #[arg(long, conflicts_with = "repos_from_org")]
+        profile: bool,
+
+        /// Where to save the snapshot of the labels taken before changing them
+        /// [default: $XDG_CACHE_HOME/biao or ~/.cache/biao]
+        #[arg(long, value_name = "DIR")]
+        snapshot_dir: Option<std::path::PathBuf>,
+
+        /// Don't save a snapshot before changing labels
+        #[arg(long, conflicts_with = "snapshot_dir")]
+        no_snapshot: bool,
+
+        /// Only create labels missing from the repository; never update, rename or delete
+        #[arg(long)]
+        only_new: bool,
+
+        /// Cut names longer than GitHub's 50-character limit instead of failing
+        #[arg(long)]
+        truncate_names: bool,
+
+        /// Only process labels whose definition changed in the config since this git ref
+        #[arg(long, value_name = "REF")]
+        since_commit: Option<String>,
+
+        /// Print the API requests (method, path and JSON body) instead of sending them;
+        /// with --dry-run, print them before the dry-run report
+        #[arg(long, conflicts_with = "repos_from_org")]
+        dump_requests: bool,
+
+        /// Never update, rename or delete labels matching this glob; repeatable
+        #[arg(long, value_name = "GLOB")]
+        protect: Vec<String>,
+
+        /// Parse the config as this format instead of going by its extension
+        /// (`-` reads the config from stdin, as TOML unless given)
+        #[arg(long, value_enum, value_name = "FORMAT")]
+        input_format: Option<crate::config::ConfigFormat>,
+
+        /// Validate and preview without GitHub: a dry run against an empty repository
+        #[arg(long, alias = "echo-plan-only", conflicts_with = "repos_from_org")]
+        offline: bool,
+
+        /// Delete labels without asking for confirmation
+        #[arg(long, short = 'y', visible_alias = "yes")]
+        force: bool,
+
+        /// Read back every created, updated or renamed label and check that it matches the config
+        #[arg(long, alias = "color-check", conflicts_with = "repos_from_org")]
+        verify: bool,
+
+        /// Shell command to run first; the apply is aborted if it fails
+        #[arg(long, value_name = "CMD")]
+        pre_hook: Option<String>,
+
+        /// Shell command to run afterwards, even if some operations failed,
+        /// with the summary counts in BIAO_CREATED, BIAO_FAILED, etc.
+        #[arg(long, value_name = "CMD")]
+        post_hook: Option<String>,
+    },
+
+    /// Show how the repository differs from a config file, without changing anything
+    Diff {
+        /// Path to TOML config file (default: labels.toml at the repository
+        /// root, else in the current directory)
+        file: Option<String>,
+
+        /// Don't count differences in this field; repeatable
+        #[arg(long, value_enum)]
+        ignore: Vec<crate::plan::DiffField>,
+
+        /// Exit with an error when there are differences
+        #[arg(long)]
+        exit_code: bool,
+
+        /// Parse the config as this format instead of going by its extension
+        #[arg(long, value_enum, value_name = "FORMAT")]
+        input_format: Option<crate::config::ConfigFormat>,
+
+        /// Compare against an empty repository, without GitHub access
+        #[arg(long, alias = "echo-plan-only")]
+        offline: bool,
+    },
+
+    /// Execute a plan saved with `apply --dry-run --plan-file`
+    ExecutePlan {
+        /// Path to the saved plan
+        file: String,
+
+        /// Execute even if the labels the plan touches changed since it was computed
+        #[arg(long)]
+        no_verify: bool,
+    },
+
+    /// Rename labels from one labeling scheme to another (e.g. `type` → `T`)
+    Migrate {
+        /// Scheme the repository currently uses (standard, semantic, type, T, priority, P, area, A)
+        from: String,
+
+        /// Scheme to migrate to
+        to: String,
+
+        /// TOML file defining additional schemes under `[schemes.<name>]`
+        #[arg(long)]
+        mapping: Option<String>,
+
+        /// Cut scheme label names over GitHub's 50-character limit, with a warning, instead of failing
+        #[arg(long)]
+        truncate_names: bool,
+
+        /// Dry run - show what would be done without making changes
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+    },
+
+    /// Copy another repository's labels into this one
+    #[command(alias = "clone-labels")]
+    Copy {
+        /// Repository to copy labels from, as OWNER/REPO
+        from: String,
+
+        /// Give both repositories the union of their labels; on conflicts the source's color and description win
+        #[arg(long)]
+        bidirectional: bool,
+
+        /// Dry run - show what would be done without making changes
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+    },
+
+    /// Make this repository's labels match another's
+    Sync {
+        /// Repository to take the labels from, as OWNER/REPO
+        #[arg(long, value_name = "OWNER/REPO")]
+        from: String,
+
+        /// Repository to update instead of the current one, as OWNER/REPO
+        #[arg(long, value_name = "OWNER/REPO")]
+        to: Option<String>,
+
+        /// Dry run - show what would be done without making changes
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+
+        /// Leave labels that already exist unchanged instead of updating them
+        #[arg(long)]
+        skip_existing: bool,
+
+        /// Also delete labels the source repository doesn't have
+        #[arg(long)]
+        prune: bool,
+
+        /// Delete labels without asking for confirmation
+        #[arg(long, short = 'y', visible_alias = "yes")]
+        force: bool,
+
+        /// Never update, rename or delete labels matching this glob; repeatable
+        #[arg(long, value_name = "GLOB")]
+        protect: Vec<String>,
+    },
+
+    /// Export the repository's labels as a TOML config
+    Export {
+        /// Fold similar labels together with `update_if_match` for use as a template
+        #[arg(long)]
+        template: bool,
+
+        /// Write to this config file instead of stdout, keeping its comments and formatting
+        /// (`-o`, since `--output` picks the output format)
+        #[arg(long, short = 'o', value_name = "FILE")]
+        into: Option<String>,
+
+        /// Also export the labels GitHub creates in every new repository
+        #[arg(long)]
+        include_default: bool,
+    },
+
+    /// Sort a config file's labels and delete list and normalize its colors
+    SortConfig {
+        /// Path to the TOML config file
+        file: String,
+
+        /// Rewrite the file instead of printing the sorted config
+        #[arg(long, short)]
+        in_place: bool,
+    },
+
+    /// Set the default color used by `create` when no color is given
+    SetDefaultColor {
+        /// Default color (hex without #, e.g., "ededed")
+        color: String,
     },
 
     /// Manage label templates
@@ -90,20 +518,94 @@ pub enum Commands {
 
     /// Generate shell completions
     Completion {
+        /// Shell to generate for; detected from $SHELL when omitted
         #[command(subcommand)]
-        subcommand: CompletionSubcommands,
+        subcommand: Option<CompletionSubcommands>,
+    },
+
+    /// Print the repository's label names starting with PREFIX, one per line,
+    /// for the completion scripts; prints nothing when the labels can't be listed
+    #[command(name = "__complete", hide = true)]
+    Complete {
+        /// Start of the label name being completed
+        #[arg(default_value = "", allow_hyphen_values = true)]
+        prefix: String,
     },
 }
 
+/// Order of `list` output; labels otherwise come in GitHub's order
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum LabelSort {
+    /// Alphabetically, ignoring case
+    Name,
+    /// By hex color, then name
+    Color,
+    /// GitHub's default labels first, then by name
+    Default,
+}
+
+impl LabelSort {
+    fn sort(self, labels: &mut [crate::models::GithubLabel]) {
+        match self {
+            LabelSort::Name => labels.sort_by_key(|label| label.name.to_lowercase()),
+            LabelSort::Color => labels.sort_by_key(|label| (label.color.to_lowercase(), label.name.to_lowercase())),
+            LabelSort::Default => labels.sort_by_key(|label| (!label.default, label.name.to_lowercase())),
+        }
+    }
+}
+
+/// Case normalization applied to label names in a config
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum LabelNameCase {
+    /// Lowercase all label names and `update_if_match` targets
+    Lower,
+    /// Keep names exactly as written
+    #[default]
+    Preserve,
+}
+
 #[derive(Subcommand)]
 pub enum TemplateSubcommands {
     /// List available templates
-    List,
+    List {
+        /// Only templates with this tag
+        #[arg(long)]
+        tag: Option<String>,
+
+        /// Only templates in this category
+        #[arg(long)]
+        category: Option<String>,
+    },
+
+    /// List template categories and the templates in each
+    Categories,
 
     /// Show template content
     Show {
         /// Template name
         name: String,
+
+        /// Print the template as apply would see it, after --var and --color-theme
+        #[arg(long)]
+        rendered: bool,
+
+        /// Value for a `{{ key }}` placeholder in the template; repeatable
+        #[arg(long = "var", value_name = "KEY=VALUE", value_parser = crate::templates::parse_var, requires = "rendered")]
+        vars: Vec<(String, String)>,
+
+        /// TOML file remapping colors (old hex → new hex)
+        #[arg(long, requires = "rendered")]
+        color_theme: Option<String>,
+    },
+
+    /// Create a starter template in ~/.config/biao/templates
+    New {
+        /// Template name; may contain `/` to group templates in subdirectories
+        name: String,
+
+        /// Overwrite an existing template with the same name
+        #[arg(long)]
+        force: bool,
     },
 
     /// Apply a template to the current repository
@@ -118,10 +620,18 @@ pub enum TemplateSubcommands {
         /// Skip labels that already exist instead of failing
         #[arg(short = 's', long)]
         skip_existing: bool,
+
+        /// TOML file remapping colors (old hex → new hex) before applying
+        #[arg(long)]
+        color_theme: Option<String>,
+
+        /// Value for a `{{ key }}` placeholder in the template; repeatable
+        #[arg(long = "var", value_name = "KEY=VALUE", value_parser = crate::templates::parse_var)]
+        vars: Vec<(String, String)>,
     },
 }
 
-#[derive(Subcommand)]
+#[derive(Subcommand, Debug, PartialEq, Eq)]
 pub enum CompletionSubcommands {
     /// Generate bash completions
     ///
@@ -156,41 +666,251 @@ pub enum AuthSubcommands {
     /// Logout from GitHub
     Logout,
 
-    /// Show authentication status
+    /// Show authentication status; with `--json`, print it as JSON and exit
+    /// non-zero when not authenticated
     Status,
 }
 
-pub async fn execute(args: Args) -> Result<()> {
+/// Formats of commands that run a plan
+const APPLY_FORMATS: &[OutputFormat] = &[
+    OutputFormat::Human,
+    OutputFormat::Json,
+    OutputFormat::GithubActions,
+    OutputFormat::Smart,
+    OutputFormat::Ndjson,
+];
+
+/// Reject output formats the command cannot produce
+fn check_output_format(command: &Commands, format: OutputFormat) -> Result<()> {
+    let (name, supported): (&str, &[OutputFormat]) = match command {
+        // Grouping only changes how labels are printed for people
+        Commands::List {
+            group_by_prefix: true, ..
+        } => ("list --group-by-prefix", &[OutputFormat::Human]),
+        Commands::List { .. } => (
+            "list",
+            &[OutputFormat::Human, OutputFormat::Json, OutputFormat::Csv, OutputFormat::Toml],
+        ),
+        Commands::Stats { .. } => ("stats", &[OutputFormat::Human, OutputFormat::Json]),
+        Commands::Get { .. } => (
+            "get",
+            &[OutputFormat::Human, OutputFormat::Json, OutputFormat::Csv, OutputFormat::Toml],
+        ),
+        Commands::Apply { .. } => ("apply", APPLY_FORMATS),
+        Commands::ExecutePlan { .. } => ("execute-plan", APPLY_FORMATS),
+        Commands::Migrate { .. } => ("migrate", APPLY_FORMATS),
+        Commands::Copy { .. } => ("copy", APPLY_FORMATS),
+        Commands::Sync { .. } => ("sync", APPLY_FORMATS),
+        Commands::Auth {
+            subcommand: Some(AuthSubcommands::Status),
+        } => ("auth status", &[OutputFormat::Human, OutputFormat::Json]),
+        Commands::Template {
+            subcommand: TemplateSubcommands::Apply { .. },
+        } => ("template apply", APPLY_FORMATS),
+        _ => ("this command", &[OutputFormat::Human]),
+    };
+
+    if supported.contains(&format) {
+        Ok(())
+    } else {
+        Err(format.unsupported(name))
+    }
+}
+
+pub async fn execute(mut args: Args) -> Result<()> {
+    if args.json {
+        args.output = OutputFormat::Json;
+    }
+    check_output_format(&args.command, args.output)?;
+    let output = args.output;
+    crate::output::set_banner(!args.no_banner);
+    crate::output::set_quiet(args.quiet);
+    crate::output::set_verbose(args.verbose);
+    args.color.apply();
+    let retry = if args.retry_on_secondary_only {
+        if !args.retry_on.is_empty() {
+            eprintln!(
+                "{} --retry-on is ignored with --retry-on-secondary-only",
+                "warning:".yellow().bold()
+            );
+        }
+        RetryPolicy::rate_limits_only(args.max_retries)
+    } else {
+        RetryPolicy::new(&args.retry_on, args.max_retries)
+    };
+    let retry = if args.no_wait { retry.without_waiting() } else { retry };
+
+    // Doctor checks the preconditions itself
+    if let Commands::Doctor = args.command {
+        return crate::doctor::report(&crate::doctor::run_checks().await);
+    }
+    if let Commands::Version = args.command {
+        print!("{}", crate::doctor::version_report(args.verbose));
+        return Ok(());
+    }
+    // Completion must never fail the shell, so it skips everything that could error
+    if let Commands::Complete { prefix } = &args.command {
+        if let Some(client) = completion_client(args.repo.first()) {
+            for name in complete_label_names(client.as_ref(), prefix).await {
+                println!("{}", name);
+            }
+        }
+        return Ok(());
+    }
+    // Config files are sorted and validated without looking at any repository
+    if let Commands::SortConfig { file, in_place } = &args.command {
+        return cmd_sort_config(file, *in_place);
+    }
+    if let Commands::Validate { file, input_format } = &args.command {
+        return cmd_validate(file, *input_format);
+    }
+
+    // `sync --to` is the repository to work on, like `--repo`
+    if let Commands::Sync { to: Some(to), .. } = &args.command {
+        if !args.repo.is_empty() {
+            return Err(crate::error::BiaoError::InvalidInput(
+                "sync: give the target repository with either --to or --repo".to_string(),
+            ));
+        }
+        args.repo.push(to.clone());
+    }
+
+    if let Commands::Apply { repos_file: Some(path), .. } = &args.command {
+        args.repo.extend(read_repos_file(path)?);
+    }
+
+    // `--repo` given more than once spreads `apply` over several repositories;
+    // given once, it replaces detecting the repository from git
+    spread_repos(&mut args.command, &mut args.repo)?;
+    let repo_override = args
+        .repo
+        .first()
+        .map(|slug| crate::git::parse_repo_arg(slug))
+        .transpose()?;
+
     // Auth, Template, and Completion commands don't need git repo
     if matches!(args.command, Commands::Auth { .. } | Commands::Template { .. } | Commands::Completion { .. }) {
         if let Commands::Auth { subcommand } = args.command {
-            return cmd_auth(subcommand).await;
+            return cmd_auth(subcommand, output).await;
         }
         if let Commands::Template { subcommand } = args.command {
-            return cmd_template(subcommand).await;
+            return cmd_template(subcommand, output, repo_override, &retry).await;
         }
         if let Commands::Completion { subcommand } = args.command {
             return cmd_completion(subcommand).await;
         }
     }
 
+    // Multi-repo apply targets the repositories given with --repo, not the current one
+    if let Commands::Apply { repos, .. } = &args.command {
+        if !repos.is_empty() {
+            let (file, options) = apply_options(args.command, output)?;
+            return cmd_apply_repos(&file, &options, &retry).await;
+        }
+    }
+    if let Commands::Apply {
+        repos_from_org: Some(org),
+        include_archived,
+        exclude_repos,
+        ..
+    } = &args.command
+    {
+        if repo_override.is_some() {
+            return Err(crate::error::BiaoError::InvalidInput(
+                "--repos-from-org picks the repositories itself and can't be combined with --repo".to_string(),
+            ));
+        }
+        let mut client = GithubClient::new(org.clone(), String::new());
+        client.set_retry_policy(retry.clone());
+        let repos = select_org_repos(client.list_org_repos(org).await?, *include_archived, exclude_repos);
+        if repos.is_empty() {
+            return Err(crate::error::BiaoError::InvalidInput(format!(
+                "No repositories of {} left to apply to",
+                org
+            )));
+        }
+
+        let (file, mut options) = apply_options(args.command, output)?;
+        options.repos = repos;
+        return cmd_apply_repos(&file, &options, &retry).await;
+    }
+
+    // Offline apply and diff plan against an empty repository, so they work
+    // without GitHub access and even outside a repository
+    let offline = matches!(
+        args.command,
+        Commands::Apply { offline: true, .. } | Commands::Diff { offline: true, .. }
+    );
+
     // Auto-detect git repository
-    let _ = crate::git::find_git_root()?;
-    let (owner, repo) = crate::git::get_repo_info()?;
+    let root = crate::git::find_git_root();
+
+    // Settings only touch the local repository, no GitHub remote needed
+    if let Commands::SetDefaultColor { color } = &args.command {
+        return cmd_set_default_color(&root?, color);
+    }
+
+    let root = match root {
+        Ok(root) => root,
+        Err(_) if offline || repo_override.is_some() => std::path::PathBuf::new(),
+        Err(e) => return Err(e),
+    };
+
+    // The remote's host wins over GH_HOST, so enterprise clones just work
+    let (host, owner, repo) = match repo_override {
+        Some(repo) => repo,
+        None => match crate::git::get_repo_info() {
+            Ok((host, owner, repo)) => (Some(host), owner, repo),
+            Err(_) if offline => {
+                crate::output::set_banner(false);
+                Default::default()
+            }
+            Err(e) => return Err(e),
+        },
+    };
+
+    if let Some(host) = host.as_deref().filter(|host| crate::provider::is_gitlab_host(host)) {
+        let client = crate::gitlab::GitlabClient::new(host, &owner, &repo)?;
+        return execute_gitlab(&client, args.command, &root, output).await;
+    }
 
-    let client = GithubClient::new(owner, repo);
+    let mut client = github_client(host, owner, repo, &retry);
+    if matches!(args.command, Commands::Apply { profile: true, .. }) {
+        client.enable_profiling();
+    }
 
     match args.command {
-        Commands::Auth { subcommand } => cmd_auth(subcommand).await?,
-        Commands::Template { subcommand } => cmd_template(subcommand).await?,
+        Commands::Auth { subcommand } => cmd_auth(subcommand, output).await?,
+        Commands::Template { subcommand } => cmd_template(subcommand, output, None, &retry).await?,
         Commands::Completion { subcommand } => cmd_completion(subcommand).await?,
-        Commands::List => cmd_list(&client).await?,
-        Commands::Get { name } => cmd_get(&client, &name).await?,
+        Commands::List {
+            diff_against_template: Some(template),
+            exit_code,
+            ..
+        } => cmd_list_template_drift(&client, &template, exit_code, output).await?,
+        Commands::List {
+            filter,
+            sort,
+            with_counts: true,
+            ..
+        } => cmd_list_with_counts(&client, output, &filter, sort).await?,
+        Commands::List {
+            group_by_prefix,
+            slim,
+            filter,
+            sort,
+            ..
+        } => cmd_list(&client, output, group_by_prefix, slim, &filter, sort).await?,
+        Commands::Stats { with_counts } => cmd_stats(&client, with_counts, output).await?,
+        Commands::Get { name, web: true } => cmd_get_web(&client, &name).await?,
+        Commands::Get { name, web: false } => cmd_get(&client, &name, output).await?,
+        Commands::Doctor | Commands::Version => unreachable!("handled before the repository is detected"),
         Commands::Create {
             name,
             color,
             description,
-        } => cmd_create(&client, &name, &color, description).await?,
+            strict_contrast,
+        } => cmd_create(&client, &root, &name, color.as_deref(), description, strict_contrast).await?,
         Commands::Update {
             name,
             new_name,
@@ -198,16 +918,177 @@ pub async fn execute(args: Args) -> Result<()> {
             description,
         } => cmd_update(&client, &name, new_name, color, description).await?,
         Commands::Delete { name, force } => cmd_delete(&client, &name, force).await?,
-        Commands::Apply { file, dry_run, skip_existing } => cmd_apply(&client, &file, dry_run, skip_existing).await?,
+        Commands::Clear {
+            force,
+            dry_run,
+            protect,
+        } => cmd_clear(&client, force, dry_run, &protect).await?,
+        Commands::Prune {
+            file,
+            force,
+            dry_run,
+            keep_default,
+        } => {
+            let file = config_file(file)?;
+            cmd_prune(&client, &file, force, dry_run, keep_default).await?
+        }
+        command @ Commands::Apply { .. } => {
+            let (file, options) = apply_options(command, output)?;
+            let result = cmd_apply(&client, &file, &options).await;
+            // On stderr, so machine-readable output on stdout stays intact
+            if let Some(profile) = client.profile() {
+                eprint!("\n{}", profile.report().format());
+            }
+            result?
+        }
+        Commands::Diff {
+            file,
+            ignore,
+            exit_code,
+            input_format,
+            offline,
+        } => {
+            let file = config_file(file)?;
+            cmd_diff(&client, &file, &ignore, exit_code, input_format, offline).await?
+        }
+        Commands::ExecutePlan { file, no_verify } => {
+            cmd_execute_plan(&client, &file, no_verify, output).await?
+        }
+        Commands::Migrate {
+            from,
+            to,
+            mapping,
+            truncate_names,
+            dry_run,
+        } => {
+            let options = ApplyOptions {
+                dry_run,
+                truncate_names,
+                snapshot_dir: crate::snapshot::default_snapshot_dir(None),
+                output,
+                ..Default::default()
+            };
+            cmd_migrate(&client, &from, &to, mapping.as_deref(), &options).await?
+        }
+        Commands::Copy {
+            from,
+            bidirectional,
+            dry_run,
+        } => cmd_copy(&client, &from, bidirectional, dry_run, output, &retry).await?,
+        Commands::Sync {
+            from,
+            to: _,
+            dry_run,
+            skip_existing,
+            prune,
+            force,
+            protect,
+        } => {
+            let options = ApplyOptions {
+                dry_run,
+                skip_existing,
+                force,
+                protect,
+                snapshot_dir: crate::snapshot::default_snapshot_dir(None),
+                output,
+                ..Default::default()
+            };
+            cmd_sync(&client, &from, prune, &options, &retry).await?
+        }
+        Commands::Export {
+            template,
+            into,
+            include_default,
+        } => cmd_export(&client, template, into.as_deref(), include_default).await?,
+        Commands::SetDefaultColor { color } => cmd_set_default_color(&root, &color)?,
+        Commands::SortConfig { file, in_place } => cmd_sort_config(&file, in_place)?,
+        Commands::Validate { file, input_format } => cmd_validate(&file, input_format)?,
+        Commands::Complete { prefix } => {
+            for name in complete_label_names(&client, &prefix).await {
+                println!("{}", name);
+            }
+        }
     }
 
     Ok(())
 }
 
-async fn cmd_auth(subcommand: Option<AuthSubcommands>) -> Result<()> {
-    use std::process::Command;
+/// Client for `owner/repo` on `host`, or the default host when `None`, retrying as asked
+fn github_client(host: Option<String>, owner: String, repo: String, retry: &RetryPolicy) -> GithubClient {
+    let mut client = GithubClient::new(owner, repo);
+    if let Some(host) = host {
+        client.set_host(host);
+    }
+    client.set_retry_policy(retry.clone());
+    client
+}
 
-    let subcommand = subcommand.unwrap_or(AuthSubcommands::Login);
+/// Run a command against a GitLab project, which supports the label CRUD commands only
+async fn execute_gitlab(
+    client: &crate::gitlab::GitlabClient,
+    command: Commands,
+    root: &std::path::Path,
+    output: OutputFormat,
+) -> Result<()> {
+    match command {
+        Commands::Auth { subcommand } => cmd_auth(subcommand, output).await,
+        Commands::Template { subcommand } => cmd_template(subcommand, output, None, &RetryPolicy::default()).await,
+        Commands::Completion { subcommand } => cmd_completion(subcommand).await,
+        Commands::SetDefaultColor { color } => cmd_set_default_color(root, &color),
+        Commands::List {
+            diff_against_template: None,
+            with_counts: false,
+            group_by_prefix,
+            slim,
+            filter,
+            sort,
+            ..
+        } => cmd_list(client, output, group_by_prefix, slim, &filter, sort).await,
+        Commands::List { .. } => Err(crate::error::BiaoError::InvalidInput(
+            "--with-counts and --diff-against-template are not supported for GitLab projects".to_string(),
+        )),
+        Commands::Get { name, web: false } => cmd_get(client, &name, output).await,
+        Commands::Get { web: true, .. } => Err(crate::error::BiaoError::InvalidInput(
+            "--web is not supported for GitLab projects".to_string(),
+        )),
+        Commands::Create {
+            name,
+            color,
+            description,
+            strict_contrast,
+        } => cmd_create(client, root, &name, color.as_deref(), description, strict_contrast).await,
+        Commands::Update {
+            name,
+            new_name,
+            color,
+            description,
+        } => cmd_update(client, &name, new_name, color, description).await,
+        Commands::Delete { name, force } => cmd_delete(client, &name, force).await,
+        _ => Err(crate::error::BiaoError::InvalidInput(
+            "Only list, get, create, update and delete support GitLab projects so far".to_string(),
+        )),
+    }
+}
+
+async fn cmd_auth(subcommand: Option<AuthSubcommands>, output: OutputFormat) -> Result<()> {
+    use std::process::Command;
+
+    let subcommand = subcommand.unwrap_or(AuthSubcommands::Login);
+
+    if let (AuthSubcommands::Status, OutputFormat::Json) = (&subcommand, output) {
+        let status = crate::auth::auth_status()?;
+        let json = serde_json::to_string_pretty(&status).map_err(|e| {
+            crate::error::BiaoError::ParseError {
+                message: format!("Failed to serialize auth status: {}", e),
+            }
+        })?;
+        println!("{}", json);
+
+        if !status.authenticated {
+            return Err(crate::error::BiaoError::Unauthorized);
+        }
+        return Ok(());
+    }
 
     let gh_subcommand = match subcommand {
         AuthSubcommands::Login => "login",
@@ -241,59 +1122,356 @@ async fn cmd_auth(subcommand: Option<AuthSubcommands>) -> Result<()> {
     Ok(())
 }
 
-async fn cmd_list(client: &GithubClient) -> Result<()> {
-    let labels = client.list_labels().await?;
+async fn cmd_list(
+    client: &dyn crate::provider::LabelProvider,
+    output: OutputFormat,
+    group_by_prefix: bool,
+    slim: bool,
+    filter: &[String],
+    sort: Option<LabelSort>,
+) -> Result<()> {
+    if slim && output != OutputFormat::Json {
+        return Err(crate::error::BiaoError::InvalidInput(
+            "--slim only applies to --output json".to_string(),
+        ));
+    }
+
+    let labels = list_matching_labels(client, filter, sort).await?;
+
+    if slim {
+        println!("{}", crate::output::render_slim_labels(&labels)?);
+        return Ok(());
+    }
+    if output != OutputFormat::Human {
+        print!("{}", crate::output::render_labels(&labels, output, &|name| client.label_web_url(name))?);
+        return Ok(());
+    }
+
+    if labels.is_empty() {
+        crate::output::print_banner(&client.repo_url());
+        if filter.is_empty() {
+            println!("No labels found.");
+        } else {
+            println!("No labels matched '{}'", filter.join("' or '"));
+        }
+        return Ok(());
+    }
+
+    println!();
+    crate::output::print_banner(&client.repo_url());
+    println!("{} Labels found:\n", labels.len());
+    if group_by_prefix {
+        print!("{}", crate::output::format_grouped_labels(&labels, &|name| client.label_web_url(name)));
+    } else {
+        print!("{}", crate::output::render_labels(&labels, output, &|name| client.label_web_url(name))?);
+    }
+    Ok(())
+}
+
+/// Labels of the repository that `list --filter` selects, in `--sort` order
+async fn list_matching_labels(
+    client: &dyn crate::provider::LabelProvider,
+    filter: &[String],
+    sort: Option<LabelSort>,
+) -> Result<Vec<crate::models::GithubLabel>> {
+    let mut labels = client.list_labels().await?;
+    if !filter.is_empty() {
+        labels.retain(|label| {
+            filter
+                .iter()
+                .any(|query| crate::filter::query_match(query, &label.name, label.description.as_deref()))
+        });
+    }
+    if let Some(sort) = sort {
+        sort.sort(&mut labels);
+    }
+    Ok(labels)
+}
+
+/// Issue and pull request counts queried concurrently for `list` and `stats --with-counts`
+const COUNT_JOBS: usize = 4;
+
+async fn cmd_list_with_counts(
+    client: &GithubClient,
+    output: OutputFormat,
+    filter: &[String],
+    sort: Option<LabelSort>,
+) -> Result<()> {
+    if output != OutputFormat::Human {
+        return Err(output.unsupported("list --with-counts"));
+    }
 
+    let labels = list_matching_labels(client, filter, sort).await?;
+    crate::output::print_banner(&client.repo_url());
     if labels.is_empty() {
-        println!("Repository: {}", client.repo_url().cyan());
         println!("No labels found.");
         return Ok(());
     }
 
-    println!("\nRepository: {}", client.repo_url().cyan());
+    let counts = count_labeled_issues(client, &labels, "all", COUNT_JOBS).await?;
     println!("{} Labels found:\n", labels.len());
-    for label in labels {
-        print_label(&label);
+    for (label, count) in labels.iter().zip(counts) {
+        print!(
+            "{}",
+            crate::output::format_label_with_count(label, &client.label_web_url(&label.name), count)
+        );
+    }
+    Ok(())
+}
+
+/// Issues and pull requests in `state` using each label, `jobs` labels at a time.
+///
+/// gh calls block, so each one runs on tokio's blocking pool. Labels whose
+/// name contains a comma can't be queried and are left uncounted with a warning.
+async fn count_labeled_issues(
+    client: &GithubClient,
+    labels: &[crate::models::GithubLabel],
+    state: &'static str,
+    jobs: usize,
+) -> Result<Vec<Option<u64>>> {
+    use std::sync::Arc;
+
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(jobs.max(1)));
+    let mut tasks = tokio::task::JoinSet::new();
+    for (index, label) in labels.iter().enumerate() {
+        if label.name.contains(',') {
+            eprintln!(
+                "{} not counting '{}': label names with a comma can't be queried",
+                "warning:".yellow().bold(),
+                label.name
+            );
+            continue;
+        }
+        let client = client.clone();
+        let name = label.name.clone();
+        let semaphore = Arc::clone(&semaphore);
+
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let count = tokio::task::spawn_blocking(move || {
+                tokio::runtime::Handle::current().block_on(client.count_labeled_issues(&name, state))
+            })
+            .await;
+            (index, count)
+        });
+    }
+
+    let mut counts = vec![None; labels.len()];
+    while let Some(joined) = tasks.join_next().await {
+        let (index, count) = joined
+            .map_err(|e| crate::error::BiaoError::InvalidInput(format!("Count task failed: {}", e)))?;
+        let count = count
+            .map_err(|e| crate::error::BiaoError::InvalidInput(format!("Count task failed: {}", e)))?;
+        counts[index] = Some(count?);
+    }
+    Ok(counts)
+}
+
+async fn cmd_stats(client: &GithubClient, with_counts: bool, output: OutputFormat) -> Result<()> {
+    let labels = client.list_labels().await?;
+    let open_issues = if with_counts {
+        Some(count_labeled_issues(client, &labels, "open", COUNT_JOBS).await?)
+    } else {
+        None
+    };
+    let stats = crate::stats::LabelStats::new(client.repo_url(), &labels, open_issues);
+
+    if output == OutputFormat::Json {
+        let json = serde_json::to_string_pretty(&stats).map_err(|e| crate::error::BiaoError::ParseError {
+            message: format!("Failed to serialize stats: {}", e),
+        })?;
+        println!("{}", json);
+        return Ok(());
     }
+
+    crate::output::print_banner(&client.repo_url());
+    println!();
+    print!("{}", crate::stats::format_stats(&stats));
     Ok(())
 }
 
-async fn cmd_get(client: &GithubClient, name: &str) -> Result<()> {
-    println!("Repository: {}", client.repo_url().cyan());
+async fn cmd_get(client: &dyn crate::provider::LabelProvider, name: &str, output: OutputFormat) -> Result<()> {
+    if output != OutputFormat::Human {
+        let label = client.get_label(name).await?;
+        print!("{}", crate::output::render_label(&label, output, &client.label_web_url(&label.name))?);
+        return Ok(());
+    }
+
+    crate::output::print_banner(&client.repo_url());
     let label = client.get_label(name).await?;
     println!();
-    print_label(&label);
+    print!("{}", crate::output::render_label(&label, output, &client.label_web_url(&label.name))?);
+    Ok(())
+}
+
+async fn cmd_get_web(client: &GithubClient, name: &str) -> Result<()> {
+    // Fetch the label first so a typo fails here instead of showing an empty search
+    let label = client.get_label(name).await?;
+    let url = client.issues_web_url(&label.name);
+    if !open_in_browser(&url) {
+        println!("{}", url);
+    }
     Ok(())
 }
 
+/// Open `url` with `$BROWSER` or the platform launcher; false if none worked
+fn open_in_browser(url: &str) -> bool {
+    use std::process::{Command, Stdio};
+
+    let mut launchers: Vec<(String, Vec<&str>)> = Vec::new();
+    if let Ok(browser) = std::env::var("BROWSER") {
+        if !browser.is_empty() {
+            launchers.push((browser, vec![]));
+        }
+    }
+    if cfg!(target_os = "macos") {
+        launchers.push(("open".to_string(), vec![]));
+    } else if cfg!(windows) {
+        launchers.push(("cmd".to_string(), vec!["/C", "start", ""]));
+    } else {
+        launchers.push(("xdg-open".to_string(), vec![]));
+    }
+
+    launchers.into_iter().any(|(program, args)| {
+        Command::new(program)
+            .args(args)
+            .arg(url)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    })
+}
+
 async fn cmd_create(
-    client: &GithubClient,
+    client: &dyn crate::provider::LabelProvider,
+    root: &std::path::Path,
     name: &str,
-    color: &str,
+    color: Option<&str>,
     description: Option<String>,
+    strict_contrast: bool,
 ) -> Result<()> {
-    let color = normalize_color(color)?;
+    let name = crate::models::normalize_name(name)?;
+    let settings = crate::settings::Settings::load(root)?;
+    let color = normalize_color(&settings.resolve_color(color)?)?;
+    check_contrast(&name, &color, strict_contrast)?;
 
     let request = CreateLabelRequest {
-        name: name.to_string(),
+        name,
         color,
         description,
     };
 
-    println!("Repository: {}", client.repo_url().cyan());
-    let label = client.create_label(&request).await?;
-    println!("\n✓ {} created successfully", "Label".green());
-    print_label(&label);
+    crate::output::print_banner(&client.repo_url());
+    let label = client.create_label(&request).await.map_err(|e| match e {
+        crate::error::BiaoError::AlreadyExists { name } => crate::error::BiaoError::InvalidInput(format!(
+            "Label '{}' already exists. Use `biao update` to change it.",
+            name
+        )),
+        e => e,
+    })?;
+    status!("\n✓ {} created successfully", "Label".green());
+    if !crate::output::is_quiet() {
+        print!("{}", crate::output::format_label(&label, &client.label_web_url(&label.name)));
+    }
+    Ok(())
+}
+
+async fn cmd_export(client: &GithubClient, template: bool, into: Option<&str>, include_default: bool) -> Result<()> {
+    let mut labels = client.list_labels().await?;
+    if !include_default {
+        labels.retain(|label| !label.default);
+    }
+    let mut config = crate::export::export_config(&labels, template);
+    let serialize = |config: &crate::config::LabelConfig| {
+        toml::to_string_pretty(config).map_err(|e| {
+            crate::error::BiaoError::InvalidInput(format!("Failed to serialize labels: {}", e))
+        })
+    };
+    let Some(file) = into else {
+        print!("{}", serialize(&config)?);
+        return Ok(());
+    };
+
+    let exported = config.labels.len();
+    let content = match std::fs::read_to_string(file) {
+        // Keys only written by hand, like update_if_exists, stay in the file
+        Ok(existing) => {
+            crate::export::keep_hand_written(&mut config, crate::config::LabelConfig::parse_unexpanded(&existing)?);
+            crate::rewrite::preserve_formatting(&existing, &serialize(&config)?)?
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => serialize(&config)?,
+        Err(e) => return Err(e.into()),
+    };
+    std::fs::write(file, content)?;
+    status!("✓ Exported {} label(s) to {}", exported, file.cyan());
+    Ok(())
+}
+
+fn cmd_sort_config(file: &str, in_place: bool) -> Result<()> {
+    let content = std::fs::read_to_string(file).map_err(|e| {
+        crate::error::BiaoError::Io(std::io::Error::other(format!("Failed to read config file: {}", e)))
+    })?;
+    let sorted = crate::rewrite::sort_config(&content)?;
+    if in_place {
+        std::fs::write(file, sorted)?;
+        status!("✓ Sorted {}", file.cyan());
+    } else {
+        print!("{}", sorted);
+    }
+    Ok(())
+}
+
+/// Report every problem of a config, failing if any of them is an error
+fn cmd_validate(file: &str, input_format: Option<crate::config::ConfigFormat>) -> Result<()> {
+    use crate::config::Severity;
+
+    let config = crate::config::LabelConfig::from_file_as(file, input_format)?;
+    let problems = config.lint();
+    for (severity, message) in &problems {
+        match severity {
+            Severity::Error => eprintln!("{} {}", "error:".red().bold(), message),
+            Severity::Warning => eprintln!("{} {}", "warning:".yellow().bold(), message),
+        }
+    }
+
+    let errors = problems.iter().filter(|(severity, _)| *severity == Severity::Error).count();
+    if errors > 0 {
+        return Err(crate::error::BiaoError::InvalidInput(format!(
+            "{} has {} error(s)",
+            file, errors
+        )));
+    }
+    status!(
+        "✓ {} is valid: {} label(s), {} to delete",
+        file.cyan(),
+        config.labels.len(),
+        config.delete.len()
+    );
+    Ok(())
+}
+
+fn cmd_set_default_color(root: &std::path::Path, color: &str) -> Result<()> {
+    let mut settings = crate::settings::Settings::load(root)?;
+    settings.default_color = Some(normalize_color(color)?);
+    let path = settings.save(root)?;
+    status!(
+        "✓ Default color set to #{} in {}",
+        settings.default_color.as_deref().unwrap_or_default(),
+        path.display().to_string().cyan()
+    );
     Ok(())
 }
 
 async fn cmd_update(
-    client: &GithubClient,
+    client: &dyn crate::provider::LabelProvider,
     name: &str,
     new_name: Option<String>,
     color: Option<String>,
     description: Option<String>,
 ) -> Result<()> {
+    let name = crate::models::normalize_name(name)?;
+    let new_name = new_name.map(|n| crate::models::normalize_name(&n)).transpose()?;
     let color = color.map(|c| normalize_color(&c)).transpose()?;
 
     let request = UpdateLabelRequest {
@@ -302,40 +1480,214 @@ async fn cmd_update(
         description,
     };
 
-    println!("Repository: {}", client.repo_url().cyan());
-    let label = client.update_label(name, &request).await?;
-    println!("\n✓ {} updated successfully", "Label".green());
-    print_label(&label);
+    crate::output::print_banner(&client.repo_url());
+    let label = client.update_label(&name, &request).await?;
+    status!("\n✓ {} updated successfully", "Label".green());
+    if !crate::output::is_quiet() {
+        print!("{}", crate::output::format_label(&label, &client.label_web_url(&label.name)));
+    }
+    Ok(())
+}
+
+async fn cmd_delete(client: &dyn crate::provider::LabelProvider, name: &str, force: bool) -> Result<()> {
+    if !force && !confirm_delete(client, name)? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    client.delete_label(name).await?;
+    status!("✓ {} '{}' from {} deleted", "Label".red(), name, client.repo_url().cyan());
     Ok(())
 }
 
-async fn cmd_delete(client: &GithubClient, name: &str, force: bool) -> Result<()> {
-    if !force {
-        print!("Are you sure you want to delete '{}' from {}? [y/N]: ", name, client.repo_url().cyan());
-        use std::io::{self, Write};
-        io::stdout().flush()?;
+/// Ask whether to delete `name`
+fn confirm_delete(client: &dyn crate::provider::LabelProvider, name: &str) -> Result<bool> {
+    confirm(&format!("Are you sure you want to delete '{}' from {}?", name, client.repo_url().cyan()))
+}
+
+/// Ask a yes/no `question` on stderr; anything but `y` or `yes` declines
+fn confirm(question: &str) -> Result<bool> {
+    use std::io::{self, Write};
+
+    eprint!("{} [y/N]: ", question);
+    io::stderr().flush()?;
+
+    Ok(is_yes(&read_answer(&mut io::stdin().lock())?))
+}
+
+/// One line answering a prompt, from a terminal or piped in (`echo y | biao
+/// delete bug`). Nothing left to read fails rather than counting as "no" and
+/// silently doing nothing.
+fn read_answer(input: &mut impl std::io::BufRead) -> Result<String> {
+    let mut answer = String::new();
+    if input.read_line(&mut answer)? == 0 {
+        return Err(crate::error::BiaoError::InvalidInput(
+            "Deleting labels needs confirmation, but stdin has no answer. Pass --force to delete without asking."
+                .to_string(),
+        ));
+    }
+    Ok(answer)
+}
+
+/// Whether a prompt answer agrees: `y` or `yes`, in any case
+fn is_yes(input: &str) -> bool {
+    let input = input.trim();
+    input.eq_ignore_ascii_case("y") || input.eq_ignore_ascii_case("yes")
+}
+
+/// Delete the labels the config doesn't define, asking about each unless `force`
+async fn cmd_prune(client: &GithubClient, file: &str, force: bool, dry_run: bool, keep_default: bool) -> Result<()> {
+    let config = crate::config::LabelConfig::from_file_as(file, None)?;
+    config.validate()?;
+    let existing = client.list_labels().await?;
+
+    let mut delete = Vec::new();
+    let prunable = crate::plan::prunable(&config, &existing, keep_default);
+    for label in prunable {
+        if force || dry_run || confirm_delete(client, &label.name)? {
+            delete.push(label.name.clone());
+        }
+    }
+    if delete.is_empty() {
+        status!("Nothing to prune.");
+        return Ok(());
+    }
+
+    let config = crate::config::LabelConfig {
+        delete,
+        ..Default::default()
+    };
+    let options = ApplyOptions {
+        dry_run,
+        // Each label was confirmed above
+        force: true,
+        snapshot_dir: crate::snapshot::default_snapshot_dir(None),
+        ..Default::default()
+    };
+    crate::output::print_banner(&client.repo_url());
+    apply_config(client, &config, &options).await
+}
 
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
+/// Delete every label not matching a `protect` glob, after the repository name
+/// is typed back unless `force`
+async fn cmd_clear(client: &GithubClient, force: bool, dry_run: bool, protect: &[String]) -> Result<()> {
+    let mut existing = client.list_labels().await?;
+    if existing.is_empty() {
+        status!("{} has no labels.", client.repo_url().cyan());
+        return Ok(());
+    }
+    existing.retain(|label| !protect.iter().any(|glob| crate::filter::glob_match(glob, &label.name)));
+    if existing.is_empty() {
+        status!("Every label of {} is protected.", client.repo_url().cyan());
+        return Ok(());
+    }
 
-        if !input.trim().eq_ignore_ascii_case("y") {
+    if !force && !dry_run {
+        eprintln!(
+            "{} This deletes {} label(s) from {} and removes them from every issue and pull request.",
+            "warning:".yellow().bold(),
+            existing.len(),
+            client.repo_url().cyan()
+        );
+        if !confirm_exact(&client.repo_url())? {
             println!("Cancelled.");
             return Ok(());
         }
     }
 
-    client.delete_label(name).await?;
-    println!("✓ {} '{}' from {} deleted", "Label".red(), name, client.repo_url().cyan());
-    Ok(())
+    let config = crate::config::LabelConfig {
+        delete: existing.iter().map(|label| label.name.clone()).collect(),
+        ..Default::default()
+    };
+    let options = ApplyOptions {
+        dry_run,
+        // Already confirmed above
+        force: true,
+        snapshot_dir: crate::snapshot::default_snapshot_dir(None),
+        ..Default::default()
+    };
+    crate::output::print_banner(&client.repo_url());
+    apply_config(client, &config, &options).await
+}
+
+/// Plans deleting more labels than this ask for the repository name instead of `y`
+const BULK_DELETE_THRESHOLD: usize = 10;
+
+/// List the labels about to be deleted from `target` and ask to go ahead;
+/// more than [`BULK_DELETE_THRESHOLD`] take typing `expected` instead of `y`.
+/// All on stderr, so machine-readable output on stdout stays intact.
+fn confirm_deletes(deletes: &[&str], target: &str, expected: &str) -> Result<bool> {
+    eprintln!(
+        "{} This plan deletes {} label(s) from {}:",
+        "warning:".yellow().bold(),
+        deletes.len(),
+        target.cyan()
+    );
+    for name in deletes {
+        eprintln!("  - {}", name.red());
+    }
+    if deletes.len() > BULK_DELETE_THRESHOLD {
+        confirm_exact(expected)
+    } else {
+        confirm("Delete them?")
+    }
+}
+
+/// Ask the user, on stderr, to type `expected` until they do; empty input aborts
+fn confirm_exact(expected: &str) -> Result<bool> {
+    use std::io::{self, Write};
+
+    loop {
+        eprint!("Type {} to confirm (empty to abort): ", expected.bold());
+        io::stderr().flush()?;
+
+        let input = read_answer(&mut io::stdin().lock())?;
+        if input.trim().is_empty() {
+            return Ok(false);
+        }
+        if matches_exact(&input, expected) {
+            return Ok(true);
+        }
+        eprintln!("'{}' does not match {}.", input.trim(), expected);
+    }
+}
+
+/// Whether typed `input` is `expected`, ignoring surrounding whitespace but not case
+fn matches_exact(input: &str, expected: &str) -> bool {
+    input.trim() == expected
 }
 
-fn normalize_color(color: &str) -> Result<String> {
+/// Color names accepted in place of hex, mapped to the colors of GitHub's label palette
+const NAMED_COLORS: &[(&str, &str)] = &[
+    ("red", "b60205"),
+    ("orange", "d93f0b"),
+    ("yellow", "fbca04"),
+    ("green", "0e8a16"),
+    ("teal", "006b75"),
+    ("blue", "1d76db"),
+    ("navy", "0052cc"),
+    ("purple", "5319e7"),
+    ("pink", "e99695"),
+    ("gray", "ededed"),
+    ("grey", "ededed"),
+    ("black", "000000"),
+    ("white", "ffffff"),
+];
+
+/// Lowercase 6-digit hex for a color given as hex (with or without `#`) or by name
+pub fn normalize_color(color: &str) -> Result<String> {
+    if let Some((_, hex)) = NAMED_COLORS.iter().find(|(name, _)| name.eq_ignore_ascii_case(color.trim())) {
+        return Ok(hex.to_string());
+    }
+
     let color = color.trim_start_matches('#');
 
     if color.len() != 6 {
-        return Err(crate::error::BiaoError::InvalidInput(
-            "Color must be 6 hex digits (e.g., ff0000)".to_string(),
-        ));
+        let names: Vec<&str> = NAMED_COLORS.iter().map(|(name, _)| *name).collect();
+        return Err(crate::error::BiaoError::InvalidInput(format!(
+            "Color must be 6 hex digits (e.g., ff0000) or one of: {}",
+            names.join(", ")
+        )));
     }
 
     // Validate hex
@@ -348,294 +1700,1530 @@ fn normalize_color(color: &str) -> Result<String> {
     Ok(color.to_lowercase())
 }
 
-async fn cmd_apply(client: &GithubClient, file: &str, dry_run: bool, skip_existing: bool) -> Result<()> {
-    use crate::config::LabelConfig;
-    use crate::models::{CreateLabelRequest, UpdateLabelRequest};
-
-    println!("Repository: {}", client.repo_url().cyan());
-    println!("Reading config from: {}\n", file.cyan());
-
-    let config = LabelConfig::from_file(file)?;
+/// Contrast ratio below which label text counts as hard to read: WCAG's minimum for large text
+const MIN_LABEL_CONTRAST: f64 = 3.0;
+
+/// Contrast ratio between a normalized label color and the text GitHub draws on it.
+///
+/// GitHub picks black or white text by perceived lightness rather than by
+/// contrast, so colors just below its cutoff get white text on a light background.
+fn label_text_contrast(color: &str) -> f64 {
+    let channel = |i: usize| u8::from_str_radix(&color[i..i + 2], 16).unwrap_or(0) as f64 / 255.0;
+    let (r, g, b) = (channel(0), channel(2), channel(4));
+
+    let perceived = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let linear = |c: f64| {
+        if c <= 0.03928 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let luminance = 0.2126 * linear(r) + 0.7152 * linear(g) + 0.0722 * linear(b);
+
+    if perceived > 0.453 {
+        // Black text
+        (luminance + 0.05) / 0.05
+    } else {
+        // White text
+        1.05 / (luminance + 0.05)
+    }
+}
 
-    if !config.has_actions() {
-        println!("No actions to perform. Config file is empty.");
+/// Warn, or fail if `strict`, when label `name`'s text would be hard to read on `color`
+fn check_contrast(name: &str, color: &str, strict: bool) -> Result<()> {
+    let ratio = label_text_contrast(color);
+    if ratio >= MIN_LABEL_CONTRAST {
         return Ok(());
     }
+    let message = format!(
+        "label '{}' has low contrast: its text on #{} is {:.1}:1, below {:.0}:1",
+        name, color, ratio, MIN_LABEL_CONTRAST
+    );
+    if strict {
+        return Err(crate::error::BiaoError::InvalidInput(format!(
+            "{}. Pick another color or drop --strict-contrast.",
+            message
+        )));
+    }
+    if !crate::output::is_quiet() {
+        eprintln!("{}", format!("warning: {}", message).dimmed());
+    }
+    Ok(())
+}
 
-    if dry_run {
-        println!("{}", "=== DRY RUN MODE ===".yellow().bold());
-        println!("No changes will be made.\n");
-    }
-
-    let mut success_count = 0;
-    let mut error_count = 0;
-    let mut skipped_count = 0;
-
-    // Process labels (create or update)
-    if !config.labels.is_empty() {
-        println!("{} Processing {} label(s):", "▶".green(), config.labels.len());
-        for label in &config.labels {
-            // First, handle update_if_match: rename matching labels to the new name
-            if !label.update_if_match.is_empty() {
-                // Check if the target label already exists
-                let target_exists = if !dry_run {
-                    client.get_label(&label.name).await.is_ok()
-                } else {
-                    false
-                };
+/// Options controlling an `apply` run
+#[derive(Clone, Default)]
+struct ApplyOptions {
+    dry_run: bool,
+    skip_existing: bool,
+    label_name_case: LabelNameCase,
+    color_theme: Option<String>,
+    require_clean: bool,
+    report_unchanged: bool,
+    plan_file: Option<String>,
+    repos: Vec<String>,
+    /// Repositories applied to concurrently
+    jobs: usize,
+    /// Label changes sent concurrently within one repository
+    label_jobs: usize,
+    filter: crate::filter::LabelFilter,
+    locked: bool,
+    /// Fields whose differences don't make an existing label need an update
+    ignore: Vec<crate::plan::DiffField>,
+    /// Only create labels missing from the repository, never update or delete
+    only_new: bool,
+    truncate_names: bool,
+    /// Git ref whose version of the config the labels are compared with
+    since_commit: Option<String>,
+    /// Print requests instead of sending them
+    dump_requests: bool,
+    /// Globs of labels the plan may not modify or delete
+    protect: Vec<String>,
+    /// Config syntax, if not implied by the file extension
+    input_format: Option<crate::config::ConfigFormat>,
+    /// Plan against an empty repository instead of fetching its labels
+    offline: bool,
+    /// Delete labels without confirmation
+    force: bool,
+    /// Read back applied labels and compare them with the plan
+    verify: bool,
+    /// Shell commands run before and after applying to each repository
+    pre_hook: Option<String>,
+    post_hook: Option<String>,
+    label_limit: crate::plan::LabelLimit,
+    /// Fail on labels whose text would be hard to read instead of warning
+    strict_contrast: bool,
+    /// `None` when snapshots are disabled
+    snapshot_dir: Option<std::path::PathBuf>,
+    output: OutputFormat,
+}
 
-                if target_exists {
-                    print!("  {} Label '{}' ", "→".blue(), label.name.cyan());
-                    println!("{}", "ALREADY EXISTS".yellow());
-                    skipped_count += 1;
-                    continue;
-                }
+/// Label changes `apply` sends concurrently unless given `--jobs`
+const DEFAULT_LABEL_JOBS: usize = 4;
+
+/// Config file and options of an `apply` command
+fn apply_options(command: Commands, output: OutputFormat) -> Result<(String, ApplyOptions)> {
+    let Commands::Apply {
+        file,
+        dry_run,
+        skip_existing,
+        label_name_case,
+        color_theme,
+        require_clean,
+        report_unchanged,
+        plan_file,
+        repos,
+        repos_file: _,
+        repos_from_org: _,
+        include_archived: _,
+        exclude_repos: _,
+        jobs,
+        include,
+        exclude,
+        locked,
+        ignore,
+        max_labels,
+        strict,
+        strict_contrast,
+        profile: _,
+        snapshot_dir,
+        no_snapshot,
+        only_new,
+        truncate_names,
+        since_commit,
+        dump_requests,
+        protect,
+        input_format,
+        offline,
+        force,
+        verify,
+        pre_hook,
+        post_hook,
+    } = command
+    else {
+        unreachable!("apply_options called for a non-apply command");
+    };
 
-                let mut found_any = false;
-                for old_name in &label.update_if_match {
-                    print!("  {} Renaming '{}' → '{}'... ", "↻".blue(), old_name.cyan(), label.name.cyan());
-                    
-                    if dry_run {
-                        println!("{}", "[DRY RUN]".yellow());
-                        success_count += 1;
-                        found_any = true;
-                    } else {
-                        let color = label.color.as_ref().map(|c| normalize_color(c)).transpose()?;
-                        let request = UpdateLabelRequest {
-                            name: Some(label.name.clone()),
-                            color,
-                            description: label.description.clone(),
-                        };
-
-                        match client.update_label(old_name, &request).await {
-                            Ok(_) => {
-                                println!("{}", "OK".green());
-                                success_count += 1;
-                                found_any = true;
-                            }
-                            Err(e) => {
-                                let err_msg = format!("{}", e);
-                                if err_msg.contains("Not Found") || err_msg.contains("404") {
-                                    println!("{}", "NOT FOUND".yellow());
-                                } else {
-                                    println!("{}: {}", "FAILED".red(), e);
-                                    error_count += 1;
-                                }
-                            }
-                        }
-                    }
-                }
-                
-                // If none of the update_if_match labels were found, create a new label
-                if !found_any && label.color.is_some() {
-                    print!("  {} Creating '{}'... ", "✓".green(), label.name.cyan());
-                    
-                    if dry_run {
-                        println!("{}", "[DRY RUN]".yellow());
-                        success_count += 1;
-                    } else {
-                        let color = normalize_color(label.color.as_ref().unwrap())?;
-                        let request = CreateLabelRequest {
-                            name: label.name.clone(),
-                            color,
-                            description: label.description.clone(),
-                        };
-
-                        match client.create_label(&request).await {
-                            Ok(_) => {
-                                println!("{}", "OK".green());
-                                success_count += 1;
-                            }
-                            Err(e) => {
-                                println!("{}: {}", "FAILED".red(), e);
-                                error_count += 1;
-                            }
-                        }
-                    }
-                }
-                continue;
-            }
+    let options = ApplyOptions {
+        // Offline runs can't change anything
+        dry_run: dry_run || offline,
+        skip_existing,
+        label_name_case,
+        color_theme,
+        require_clean,
+        report_unchanged,
+        plan_file,
+        repos,
+        jobs: jobs.unwrap_or(1),
+        label_jobs: jobs.unwrap_or(DEFAULT_LABEL_JOBS),
+        filter: crate::filter::LabelFilter { include, exclude },
+        locked,
+        ignore,
+        only_new,
+        truncate_names,
+        since_commit,
+        dump_requests,
+        protect,
+        input_format,
+        offline,
+        force,
+        verify,
+        pre_hook,
+        post_hook,
+        label_limit: crate::plan::LabelLimit {
+            max: max_labels,
+            strict,
+        },
+        strict_contrast,
+        snapshot_dir: if no_snapshot {
+            None
+        } else {
+            crate::snapshot::default_snapshot_dir(snapshot_dir.as_deref())
+        },
+        output,
+    };
+    Ok((config_file(file)?, options))
+}
 
-            // If color is present, try to create (or update if exists)
-            if let Some(color) = &label.color {
-                print!("  {} Creating '{}'... ", "✓".green(), label.name.cyan());
-                
-                if dry_run {
-                    println!("{}", "[DRY RUN]".yellow());
-                    success_count += 1;
-                } else {
-                    let color = normalize_color(color)?;
-                    let request = CreateLabelRequest {
-                        name: label.name.clone(),
-                        color,
-                        description: label.description.clone(),
-                    };
-
-                    match client.create_label(&request).await {
-                        Ok(_) => {
-                            println!("{}", "OK".green());
-                            success_count += 1;
-                        }
-                        Err(e) => {
-                            // Check if it's a "already exists" error (422 status)
-                            let err_msg = format!("{}", e);
-                            let should_skip = skip_existing || label.skip_if_exists;
-                            let should_update = label.update_if_exists;
-                            
-                            if err_msg.contains("already_exists") {
-                                if should_update {
-                                    // Try to update instead
-                                    print!("{} (updating)... ", "EXISTS".yellow());
-                                    let update_color = normalize_color(label.color.as_ref().unwrap())?;
-                                    let update_request = UpdateLabelRequest {
-                                        name: None,
-                                        color: Some(update_color),
-                                        description: label.description.clone(),
-                                    };
-                                    match client.update_label(&label.name, &update_request).await {
-                                        Ok(_) => {
-                                            println!("{}", "UPDATED".green());
-                                            success_count += 1;
-                                        }
-                                        Err(update_err) => {
-                                            println!("{}: {}", "FAILED".red(), update_err);
-                                            error_count += 1;
-                                        }
-                                    }
-                                } else if should_skip {
-                                    println!("{}", "SKIPPED (already exists)".yellow());
-                                    skipped_count += 1;
-                                } else {
-                                    println!("{}: {}", "FAILED".red(), e);
-                                    error_count += 1;
-                                }
-                            } else {
-                                println!("{}: {}", "FAILED".red(), e);
-                                error_count += 1;
-                            }
-                        }
-                    }
-                }
-            } else {
-                // No color means update only
-                print!("  {} Updating '{}'... ", "✓".blue(), label.name.cyan());
-                
-                if dry_run {
-                    println!("{}", "[DRY RUN]".yellow());
-                    success_count += 1;
-                } else {
-                    let request = UpdateLabelRequest {
-                        name: None,
-                        color: None,
-                        description: label.description.clone(),
-                    };
-
-                    match client.update_label(&label.name, &request).await {
-                        Ok(_) => {
-                            println!("{}", "OK".green());
-                            success_count += 1;
-                        }
-                        Err(e) => {
-                            println!("{}: {}", "FAILED".red(), e);
-                            error_count += 1;
-                        }
-                    }
-                }
-            }
-        }
-        println!();
+/// Config `apply`, `diff` and `prune` read when not given one
+const DEFAULT_CONFIG: &str = "labels.toml";
+
+/// `file`, else the discovered [`DEFAULT_CONFIG`]
+fn config_file(file: Option<String>) -> Result<String> {
+    match file {
+        Some(file) => Ok(file),
+        None => default_config_file(),
     }
+}
 
-    // Process deletes
-    if !config.delete.is_empty() {
-        println!("{} Deleting {} label(s):", "▶".red(), config.delete.len());
-        for name in &config.delete {
-            print!("  {} Deleting '{}'... ", "✗".red(), name.cyan());
-            
-            if dry_run {
-                println!("{}", "[DRY RUN]".yellow());
-                success_count += 1;
-            } else {
-                match client.delete_label(name).await {
-                    Ok(_) => {
-                        println!("{}", "OK".green());
-                        success_count += 1;
-                    }
-                    Err(e) => {
-                        println!("{}: {}", "FAILED".red(), e);
-                        error_count += 1;
-                    }
-                }
-            }
+/// [`DEFAULT_CONFIG`] at the repository root or in the current directory, so
+/// the config commands work from anywhere in the repository
+fn default_config_file() -> Result<String> {
+    let root = crate::git::find_git_root().ok();
+    let cwd = std::env::current_dir()?;
+    discover_config(root.as_deref(), &cwd).map(|path| path.display().to_string())
+}
+
+/// The first existing [`DEFAULT_CONFIG`] in `root`, then `cwd`; relative when it is in `cwd`
+fn discover_config(root: Option<&std::path::Path>, cwd: &std::path::Path) -> Result<std::path::PathBuf> {
+    let mut candidates: Vec<std::path::PathBuf> = root
+        .into_iter()
+        .chain([cwd])
+        .map(|dir| dir.join(DEFAULT_CONFIG))
+        .collect();
+    candidates.dedup();
+
+    match candidates.iter().find(|path| path.is_file()) {
+        Some(found) => Ok(found.strip_prefix(cwd).map_or_else(|_| found.clone(), |relative| relative.to_path_buf())),
+        None => {
+            let checked: Vec<String> = candidates.iter().map(|path| path.display().to_string()).collect();
+            Err(crate::error::BiaoError::InvalidInput(format!(
+                "No {} found at {}. Pass the config file explicitly, e.g. `biao apply path/to/labels.toml`.",
+                DEFAULT_CONFIG,
+                checked.join(" or ")
+            )))
         }
-        println!();
     }
+}
 
-    // Summary
-    println!("{}", "=== Summary ===".bold());
-    println!("  {} {}", "Success:".green(), success_count);
-    if skipped_count > 0 {
-        println!("  {} {}", "Skipped:".yellow(), skipped_count);
-    }
-    if error_count > 0 {
-        println!("  {} {}", "Failed:".red(), error_count);
+async fn cmd_apply(client: &GithubClient, file: &str, options: &ApplyOptions) -> Result<()> {
+    if options.output == OutputFormat::Human {
+        crate::output::print_banner(&client.repo_url());
+        let source = if file == "-" { "stdin" } else { file };
+        status!("Reading config from: {}\n", source.cyan());
     }
 
-    if dry_run {
-        println!("\n{}", "This was a dry run. No actual changes were made.".yellow());
+    let config = load_apply_config(file, options)?;
+    if options.output == OutputFormat::Human {
+        print_config_heading(&config);
     }
-
-    Ok(())
+    apply_config(client, &config, options).await
 }
 
-async fn cmd_completion(subcommand: CompletionSubcommands) -> Result<()> {
-    use clap::CommandFactory;
+/// "Applying: <name>" from the config's own metadata, so logs of several configs read clearly
+fn print_config_heading(config: &crate::config::LabelConfig) {
+    match (&config.name, &config.description) {
+        (Some(name), description) => {
+            status!("{} {}", "Applying:".bold(), name.bold());
+            if let Some(description) = description {
+                status!("{}", description.dimmed());
+            }
+            status!();
+        }
+        (None, Some(description)) => status!("{} {}\n", "Applying:".bold(), description.bold()),
+        (None, None) => {}
+    }
+}
 
-    let mut cmd = Args::command();
+/// Read the config for `apply`, checking it is committed and normalizing it as requested
+fn load_apply_config(file: &str, options: &ApplyOptions) -> Result<crate::config::LabelConfig> {
+    use crate::config::{ConfigFormat, LabelConfig};
 
-    match subcommand {
-        CompletionSubcommands::Bash => {
-            use clap_complete::shells::Bash;
-            clap_complete::generate(Bash, &mut cmd, "biao", &mut std::io::stdout());
-        }
-        CompletionSubcommands::Zsh => {
-            use clap_complete::shells::Zsh;
-            clap_complete::generate(Zsh, &mut cmd, "biao", &mut std::io::stdout());
-        }
-        CompletionSubcommands::Fish => {
-            use clap_complete::shells::Fish;
-            clap_complete::generate(Fish, &mut cmd, "biao", &mut std::io::stdout());
-        }
-        CompletionSubcommands::Elvish => {
-            use clap_complete::shells::Elvish;
-            clap_complete::generate(Elvish, &mut cmd, "biao", &mut std::io::stdout());
-        }
+    // Both look at the config's file in git
+    if file == "-" && (options.require_clean || options.since_commit.is_some()) {
+        return Err(crate::error::BiaoError::InvalidInput(
+            "--require-clean and --since-commit need a config file, not stdin".to_string(),
+        ));
+    }
+    if options.require_clean && crate::git::has_uncommitted_changes(std::path::Path::new(file))? {
+        return Err(crate::error::BiaoError::InvalidInput(format!(
+            "{} has uncommitted changes. Commit it or drop --require-clean.",
+            file
+        )));
     }
 
-    Ok(())
+    let format = options
+        .input_format
+        .or_else(|| ConfigFormat::from_path(std::path::Path::new(file)));
+    let mut config = LabelConfig::from_file_as(file, format)?;
+    if let Some(git_ref) = &options.since_commit {
+        // A config that didn't exist at the ref is new in its entirety
+        let base: LabelConfig = match crate::git::file_at_ref(git_ref, std::path::Path::new(file))? {
+            Some(content) => LabelConfig::parse(&content, format)?,
+            None => LabelConfig::default(),
+        };
+        let changed = config.changed_since(&base);
+        config.retain_names(&changed);
+    }
+    prepare_config(config, options)
 }
 
-fn print_label(label: &crate::models::GithubLabel) {
-    let color_display = format!("■");
-    let color_rgb = format!("#{}", label.color);
+/// Normalize, filter and validate a config as the `apply` options request
+fn prepare_config(
+    mut config: crate::config::LabelConfig,
+    options: &ApplyOptions,
+) -> Result<crate::config::LabelConfig> {
+    use crate::theme::ColorTheme;
 
-    println!("  Name:        {}", label.name.cyan());
-    println!("  Color:       {} {}", color_display, color_rgb);
-    if let Some(desc) = &label.description {
-        println!("  Description: {}", desc);
+    if options.label_name_case == LabelNameCase::Lower {
+        config.lowercase_names();
     }
-    println!("  URL:         {}", label.url.dimmed());
+    if options.truncate_names {
+        for (name, truncated) in config.truncate_names() {
+            eprintln!("{} truncated '{}' to '{}'", "warning:".yellow().bold(), name, truncated);
+        }
+    }
+    if let Some(theme) = &options.color_theme {
+        ColorTheme::from_file(theme)?.apply(&mut config)?;
+    }
+    options.filter.apply(&mut config);
+    for label in &mut config.labels {
+        for warning in crate::models::sanitize_label(label)? {
+            eprintln!("{} {}", "warning:".yellow().bold(), warning);
+        }
+    }
+    config.validate()?;
+    for label in &config.labels {
+        if let Some(color) = &label.color {
+            check_contrast(&label.name, &normalize_color(color)?, options.strict_contrast)?;
+        }
+    }
+
+    Ok(config)
+}
+
+/// `owner/name` of the organization repositories `--repos-from-org` applies to:
+/// unarchived ones unless `include_archived`, minus those matching an `--exclude-repo` glob
+fn select_org_repos(
+    repos: Vec<crate::models::OrgRepository>,
+    include_archived: bool,
+    exclude: &[String],
+) -> Vec<String> {
+    repos
+        .into_iter()
+        .filter(|repo| include_archived || !repo.archived)
+        .filter(|repo| !exclude.iter().any(|glob| crate::filter::glob_match(glob, &repo.name)))
+        .map(|repo| repo.full_name)
+        .collect()
+}
+
+/// Apply one config to several repositories, `--jobs` at a time, and print a summary matrix
+async fn cmd_apply_repos(file: &str, options: &ApplyOptions, retry: &RetryPolicy) -> Result<()> {
+    use crate::output::RepoResult;
+    use std::sync::Arc;
+
+    let human = matches!(options.output, OutputFormat::Human | OutputFormat::Smart);
+    let clients = options
+        .repos
+        .iter()
+        .map(|slug| repo_client(slug, retry))
+        .collect::<Result<Vec<_>>>()?;
+
+    if human {
+        status!("Reading config from: {}", file.cyan());
+        status!("Applying to {} repositories\n", clients.len());
+        if options.dry_run {
+            status!("{}", "=== DRY RUN MODE ===".yellow().bold());
+            status!("No changes will be made.\n");
+        }
+    }
+
+    let config = Arc::new(load_apply_config(file, options)?);
+
+    // Asked once for all repositories, before any of them changes
+    let deletes: Vec<&str> = config
+        .delete
+        .iter()
+        .filter(|name| !options.protect.iter().any(|glob| crate::filter::glob_match(glob, name)))
+        .map(String::as_str)
+        .collect();
+    if !options.dry_run && !options.force && !deletes.is_empty() {
+        let target = format!("{} repositories", clients.len());
+        if !confirm_deletes(&deletes, &target, &target)? {
+            eprintln!("Cancelled.");
+            return Ok(());
+        }
+    }
+
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(options.jobs.max(1)));
+    // `--jobs` counts repositories here; each one's labels go one at a time
+    let shared_options = Arc::new(ApplyOptions {
+        label_jobs: 1,
+        ..options.clone()
+    });
+    let mut tasks = tokio::task::JoinSet::new();
+
+    for (index, client) in clients.into_iter().enumerate() {
+        let config = Arc::clone(&config);
+        let semaphore = Arc::clone(&semaphore);
+        let options = Arc::clone(&shared_options);
+
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let outcome = apply_to_repo(&client, &config, &options).await;
+            (index, client.repo_url(), outcome)
+        });
+    }
+
+    let mut results = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        let (index, repository, outcome) = joined.map_err(|e| {
+            crate::error::BiaoError::InvalidInput(format!("Apply task failed: {}", e))
+        })?;
+
+        if human {
+            match &outcome {
+                Ok(summary) if summary.failed == 0 => status!("  {} {}", "✓".green(), repository.cyan()),
+                Ok(summary) => print_failure(&format!("  {} {} ({} failed)", "✗".red(), repository.cyan(), summary.failed)),
+                Err(e) => print_failure(&format!("  {} {}: {}", "✗".red(), repository.cyan(), e)),
+            }
+        }
+
+        let (summary, error) = match outcome {
+            Ok(summary) => (Some(summary), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let result = RepoResult { repository, summary, error };
+        // Streamed as repositories finish, rather than in the order given
+        if options.output == OutputFormat::Ndjson {
+            crate::output::print_ndjson(&result)?;
+        }
+        results.push((index, result));
+    }
+
+    // Report repositories in the order they were given, not the order they finished
+    results.sort_by_key(|(index, _)| *index);
+    let results: Vec<RepoResult> = results.into_iter().map(|(_, result)| result).collect();
+    // A repository that could not be applied at all counts as one failure;
+    // operations a dry run predicts to fail don't
+    let failed = results
+        .iter()
+        .map(|result| match &result.summary {
+            Some(_) if options.dry_run => 0,
+            Some(summary) => summary.failed,
+            None => 1,
+        })
+        .sum();
+
+    if options.output == OutputFormat::GithubActions {
+        let matrix = crate::output::format_repo_matrix(&results);
+        print!("{}", crate::output::format_repo_workflow_commands(&results));
+        print!("{}", matrix);
+        write_step_summary(&format!("## biao apply\n\n```\n{}```\n", matrix))?;
+        return partial_failure(failed);
+    }
+
+    if options.output == OutputFormat::Ndjson {
+        return partial_failure(failed);
+    }
+
+    if !human {
+        let json = serde_json::to_string_pretty(&results).map_err(|e| {
+            crate::error::BiaoError::ParseError {
+                message: format!("Failed to serialize results: {}", e),
+            }
+        })?;
+        println!("{}", json);
+        return partial_failure(failed);
+    }
+
+    if !crate::output::is_quiet() {
+        println!("\n{}", "=== Summary ===".bold());
+        print!("{}", crate::output::format_repo_matrix(&results));
+        if options.dry_run {
+            println!("\n{}", "This was a dry run. No actual changes were made.".yellow());
+        }
+    }
+
+    partial_failure(failed)
+}
+
+/// Client for one of the repositories `apply` targets, given as OWNER/REPO
+/// or as a URL, which keeps its host
+fn repo_client(slug: &str, retry: &RetryPolicy) -> Result<GithubClient> {
+    let (host, owner, repo) = crate::git::parse_repo_arg(slug)?;
+    if host.as_deref().is_some_and(crate::provider::is_gitlab_host) {
+        return Err(crate::error::BiaoError::InvalidInput(format!(
+            "{} is a GitLab project; applying to several repositories works on GitHub only",
+            slug
+        )));
+    }
+    Ok(github_client(host, owner, repo, retry))
+}
+
+/// Repositories listed in an `apply --repos-file`, skipping blank lines and `#` comments
+fn read_repos_file(path: &std::path::Path) -> Result<Vec<String>> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        crate::error::BiaoError::Io(std::io::Error::other(format!(
+            "Failed to read {}: {}",
+            path.display(),
+            e
+        )))
+    })?;
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            crate::git::parse_repo_arg(line)?;
+            Ok(line.to_string())
+        })
+        .collect()
+}
+
+/// Move a repeated `--repo` into `apply`'s repositories, leaving `repos` empty.
+/// Other commands work on one repository, as do some `apply` flags.
+fn spread_repos(command: &mut Commands, repos: &mut Vec<String>) -> Result<()> {
+    if repos.len() < 2 {
+        return Ok(());
+    }
+    let Commands::Apply {
+        repos: targets,
+        plan_file,
+        repos_from_org,
+        profile,
+        dump_requests,
+        offline,
+        verify,
+        ..
+    } = command
+    else {
+        return Err(crate::error::BiaoError::InvalidInput(
+            "Only `apply` accepts --repo more than once".to_string(),
+        ));
+    };
+
+    let single_repo_flags = [
+        (plan_file.is_some(), "--plan-file"),
+        (repos_from_org.is_some(), "--repos-from-org"),
+        (*profile, "--profile"),
+        (*dump_requests, "--dump-requests"),
+        (*offline, "--offline"),
+        (*verify, "--verify"),
+    ];
+    if let Some((_, flag)) = single_repo_flags.iter().find(|(set, _)| *set) {
+        return Err(crate::error::BiaoError::InvalidInput(format!(
+            "{} works on one repository and can't be combined with several --repo",
+            flag
+        )));
+    }
+    *targets = std::mem::take(repos);
+    Ok(())
+}
+
+/// Plan and run `config` against one repository without per-operation output
+async fn apply_to_repo(
+    client: &GithubClient,
+    config: &crate::config::LabelConfig,
+    options: &ApplyOptions,
+) -> Result<crate::plan::Summary> {
+    use crate::plan::Plan;
+
+    if let Some(hook) = &options.pre_hook {
+        crate::hook::run("--pre-hook", hook, &crate::hook::pre_env(&client.repo_url(), options.dry_run))?;
+    }
+
+    let existing = client.list_labels().await?;
+    let mut plan = Plan::build_ignoring(config, &existing, options.skip_existing, &options.ignore)?;
+    if options.only_new {
+        plan.retain_new(&existing);
+    }
+    for blocked in plan.protect(&options.protect) {
+        eprintln!("{} {}: --protect blocked {}", "warning:".yellow().bold(), client.repo_url(), blocked);
+    }
+    for warning in &plan.warnings {
+        eprintln!("{} {}: {}", "warning:".yellow().bold(), client.repo_url(), warning);
+    }
+    if options.locked {
+        check_locked(&plan)?;
+    }
+    if let Some(warning) = options.label_limit.check(plan.label_count_after(existing.len()))? {
+        eprintln!("{} {}: {}", "warning:".yellow().bold(), client.repo_url(), warning);
+    }
+    let summary = if options.dry_run {
+        plan.summary()
+    } else {
+        take_snapshot(client, &plan, &existing, options.snapshot_dir.as_deref())?;
+
+        let mut summary = crate::plan::Summary::default();
+        for op in &plan.operations {
+            match run_operation(client, op, false).await {
+                (_, Some(_)) => summary.failed += 1,
+                _ => summary.record(&op.kind),
+            }
+        }
+        summary
+    };
+    if let Some(hook) = &options.post_hook {
+        run_post_hook(hook, &client.repo_url(), options.dry_run, &summary);
+    }
+    Ok(summary)
+}
+
+async fn cmd_migrate(
+    client: &GithubClient,
+    from: &str,
+    to: &str,
+    mapping: Option<&str>,
+    options: &ApplyOptions,
+) -> Result<()> {
+    use crate::migrate::{migration_config, SchemeMapping};
+
+    let mapping = match mapping {
+        Some(path) => SchemeMapping::from_file(path)?,
+        None => SchemeMapping::default(),
+    };
+    // User-defined schemes can hold names GitHub would reject
+    let config = prepare_config(migration_config(&mapping.scheme(from)?, &mapping.scheme(to)?), options)?;
+
+    if options.output == OutputFormat::Human {
+        crate::output::print_banner(&client.repo_url());
+        status!("Migrating labels from {} to {}\n", from.cyan(), to.cyan());
+    }
+
+    apply_config(client, &config, options).await
+}
+
+async fn cmd_copy(
+    client: &GithubClient,
+    from: &str,
+    bidirectional: bool,
+    dry_run: bool,
+    output: OutputFormat,
+    retry: &RetryPolicy,
+) -> Result<()> {
+    let (owner, repo) = crate::git::parse_repo_slug(from)?;
+    let mut source = GithubClient::new(owner, repo);
+    source.set_retry_policy(retry.clone());
+
+    let source_labels = source.list_labels().await?;
+    // One way, the target's own labels are left alone and need not be merged
+    let target_labels = if bidirectional { client.list_labels().await? } else { Vec::new() };
+    let (config, conflicts) = crate::export::union_config(&source_labels, &target_labels);
+    for conflict in conflicts {
+        eprintln!("{} {}", "warning:".yellow().bold(), conflict);
+    }
+
+    let options = ApplyOptions {
+        dry_run,
+        snapshot_dir: crate::snapshot::default_snapshot_dir(None),
+        output,
+        ..Default::default()
+    };
+    // Each repository paired with the one its labels come from
+    let targets: &[(&GithubClient, &GithubClient)] = if bidirectional {
+        &[(client, &source), (&source, client)]
+    } else {
+        &[(client, &source)]
+    };
+    for (target, other) in targets {
+        if output == OutputFormat::Human {
+            crate::output::print_banner(&target.repo_url());
+            status!("Copying labels from {}\n", other.repo_url().cyan());
+        }
+        apply_config(target, &config, &options).await?;
+    }
+    Ok(())
+}
+
+/// Create and update the labels of the `from` repository in this one, deleting
+/// the others with `prune`
+async fn cmd_sync(
+    client: &GithubClient,
+    from: &str,
+    prune: bool,
+    options: &ApplyOptions,
+    retry: &RetryPolicy,
+) -> Result<()> {
+    let (owner, repo) = crate::git::parse_repo_slug(from)?;
+    let mut source = GithubClient::new(owner, repo);
+    source.set_retry_policy(retry.clone());
+
+    let source_labels = source.list_labels().await?;
+    let target_labels = if prune { client.list_labels().await? } else { Vec::new() };
+    let config = crate::export::sync_config(&source_labels, &target_labels, options.skip_existing, prune);
+
+    if options.output == OutputFormat::Human {
+        crate::output::print_banner(&client.repo_url());
+        status!("Syncing labels from {}\n", source.repo_url().cyan());
+    }
+    apply_config(client, &config, options).await
+}
+
+/// Fail if the plan would change anything, for `apply --locked`
+fn check_locked(plan: &crate::plan::Plan) -> Result<()> {
+    let pending: Vec<String> = plan
+        .changes()
+        .map(|op| format!("{} '{}'", op.kind.action(), op.name))
+        .collect();
+    if pending.is_empty() {
+        return Ok(());
+    }
+    Err(crate::error::BiaoError::InvalidInput(format!(
+        "--locked: the repository does not match the config ({} pending change(s): {}). \
+         Run `biao apply` locally and commit the config once they match.",
+        pending.len(),
+        pending.join(", ")
+    )))
+}
+
+/// The repository's labels; none when `--offline`
+async fn current_labels(client: &GithubClient, options: &ApplyOptions) -> Result<Vec<crate::models::GithubLabel>> {
+    if options.offline {
+        Ok(Vec::new())
+    } else {
+        client.list_labels().await
+    }
+}
+
+/// Plan `config` against the repository and run the plan
+async fn apply_config(
+    client: &GithubClient,
+    config: &crate::config::LabelConfig,
+    options: &ApplyOptions,
+) -> Result<()> {
+    use crate::plan::{Plan, SavedPlan};
+
+    let human = options.output == OutputFormat::Human;
+    let dry_run = options.dry_run;
+
+    if options.dump_requests && !human {
+        return Err(options.output.unsupported("apply --dump-requests"));
+    }
+
+    if !config.has_actions() && human {
+        status!("No actions to perform. Config file is empty.");
+        return Ok(());
+    }
+
+    if dry_run && human {
+        status!("{}", "=== DRY RUN MODE ===".yellow().bold());
+        status!("No changes will be made.\n");
+    }
+
+    if let Some(hook) = &options.pre_hook {
+        crate::hook::run("--pre-hook", hook, &crate::hook::pre_env(&client.repo_url(), dry_run))?;
+    }
+
+    let existing = current_labels(client, options).await?;
+    let mut plan = Plan::build_ignoring(config, &existing, options.skip_existing, &options.ignore)?;
+    if options.only_new {
+        plan.retain_new(&existing);
+    }
+    for blocked in plan.protect(&options.protect) {
+        eprintln!("{} --protect blocked {}", "warning:".yellow().bold(), blocked);
+    }
+    for warning in &plan.warnings {
+        eprintln!("{} {}", "warning:".yellow().bold(), warning);
+    }
+
+    if options.locked {
+        check_locked(&plan)?;
+    }
+
+    if let Some(warning) = options.label_limit.check(plan.label_count_after(existing.len()))? {
+        eprintln!("{} {}", "warning:".yellow().bold(), warning);
+    }
+
+    if let Some(path) = &options.plan_file {
+        SavedPlan::new(client.repo_url(), &plan, &existing).save(path)?;
+        if human {
+            status!("Plan written to: {}\n", path.cyan());
+        }
+    }
+
+    if options.dump_requests {
+        print!("{}", format_request_dump(client, &plan)?);
+        // Only a dry run goes on to report the plan; nothing is ever sent
+        if !dry_run {
+            return Ok(());
+        }
+    }
+
+    let deletes: Vec<&str> = plan
+        .operations
+        .iter()
+        .filter(|op| matches!(op.kind, crate::plan::OperationKind::Delete))
+        .map(|op| op.name.as_str())
+        .collect();
+    if !dry_run && !options.force && !deletes.is_empty() {
+        // Many deletions take typing the repository name, not just `y`
+        if !confirm_deletes(&deletes, &client.repo_url(), &client.repo_url())? {
+            eprintln!("Cancelled.");
+            return Ok(());
+        }
+    }
+
+    if !dry_run {
+        if let Some(path) = take_snapshot(client, &plan, &existing, options.snapshot_dir.as_deref())? {
+            if human {
+                status!("Snapshot saved to: {}\n", path.display().to_string().cyan());
+            }
+        }
+    }
+
+    let summary = run_plan(
+        client,
+        &plan,
+        dry_run,
+        options.report_unchanged,
+        options.verify,
+        options.label_jobs,
+        options.output,
+    )
+    .await?;
+    if let Some(hook) = &options.post_hook {
+        run_post_hook(hook, &client.repo_url(), dry_run, &summary);
+    }
+    // A dry run reports labels that would fail but still succeeds
+    if !dry_run {
+        partial_failure(summary.failed)?;
+    }
+    if summary.unverified > 0 {
+        return Err(crate::error::BiaoError::Unverified(summary.unverified));
+    }
+    Ok(())
+}
+
+/// Run `--post-hook`; it can't undo the apply, so a failing hook only warns
+fn run_post_hook(hook: &str, repository: &str, dry_run: bool, summary: &crate::plan::Summary) {
+    let env = crate::hook::post_env(repository, dry_run, summary);
+    if let Err(e) = crate::hook::run("--post-hook", hook, &env) {
+        eprintln!("{} {}", "warning:".yellow().bold(), e);
+    }
+}
+
+/// Save the current labels before a plan changes them; nothing to save if it changes nothing
+fn take_snapshot(
+    client: &GithubClient,
+    plan: &crate::plan::Plan,
+    existing: &[crate::models::GithubLabel],
+    dir: Option<&std::path::Path>,
+) -> Result<Option<std::path::PathBuf>> {
+    match dir {
+        Some(dir) if plan.changes().next().is_some() => {
+            crate::snapshot::save_snapshot(dir, &client.repo_url(), existing).map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+async fn cmd_diff(
+    client: &GithubClient,
+    file: &str,
+    ignore: &[crate::plan::DiffField],
+    exit_code: bool,
+    input_format: Option<crate::config::ConfigFormat>,
+    offline: bool,
+) -> Result<()> {
+    use crate::config::LabelConfig;
+    use crate::plan::Plan;
+
+    crate::output::print_banner(&client.repo_url());
+    status!("Comparing with: {}\n", file.cyan());
+
+    let config = LabelConfig::from_file_as(file, input_format)?;
+    config.validate()?;
+    let existing = if offline { Vec::new() } else { client.list_labels().await? };
+    let plan = Plan::build_ignoring(&config, &existing, false, ignore)?;
+
+    print_diff(&plan, &existing, ignore, exit_code)
+}
+
+/// Show how the repository's labels differ from a template
+async fn cmd_list_template_drift(
+    client: &GithubClient,
+    template: &str,
+    exit_code: bool,
+    output: OutputFormat,
+) -> Result<()> {
+    if output != OutputFormat::Human {
+        return Err(output.unsupported("list --diff-against-template"));
+    }
+
+    let content = crate::templates::TemplateManager::new()?.get(template)?;
+    crate::output::print_banner(&client.repo_url());
+    status!("Comparing with template: {}\n", template.cyan());
+
+    let existing = client.list_labels().await?;
+    let plan = template_drift_plan(&content, &existing)?;
+    print_diff(&plan, &existing, &[], exit_code)
+}
+
+/// Changes that would bring the repository in line with a template.
+///
+/// Unlike `apply`, existing labels always count as drift when their color or
+/// description differs from the template.
+fn template_drift_plan(
+    template: &str,
+    existing: &[crate::models::GithubLabel],
+) -> Result<crate::plan::Plan> {
+    let mut config: crate::config::LabelConfig = template.parse()?;
+    for label in &mut config.labels {
+        label.update_if_exists = true;
+    }
+    crate::plan::Plan::build(&config, existing, false)
+}
+
+/// Print the changes of `plan` and, with `exit_code`, fail if there are any
+fn print_diff(
+    plan: &crate::plan::Plan,
+    existing: &[crate::models::GithubLabel],
+    ignore: &[crate::plan::DiffField],
+    exit_code: bool,
+) -> Result<()> {
+    let changes = plan.changes().count();
+    if changes == 0 {
+        println!("No differences.");
+    } else {
+        print!("{}", crate::output::format_diff(plan, existing, ignore));
+    }
+    println!();
+    print!(
+        "{}",
+        crate::output::format_diff_summary(&plan.summary(), &plan.unmanaged(existing))
+    );
+
+    if exit_code && changes > 0 {
+        return Err(crate::error::BiaoError::Drift(changes));
+    }
+    Ok(())
+}
+
+async fn cmd_execute_plan(
+    client: &GithubClient,
+    file: &str,
+    no_verify: bool,
+    output: OutputFormat,
+) -> Result<()> {
+    use crate::plan::SavedPlan;
+
+    let saved = SavedPlan::from_file(file)?;
+    if saved.repository != client.repo_url() {
+        return Err(crate::error::BiaoError::InvalidInput(format!(
+            "Plan was computed for {}, not {}",
+            saved.repository,
+            client.repo_url()
+        )));
+    }
+
+    if output == OutputFormat::Human {
+        crate::output::print_banner(&client.repo_url());
+        status!("Executing plan {} from: {}\n", saved.plan_hash.bold(), file.cyan());
+    }
+
+    let stale = saved.stale_labels(&client.list_labels().await?);
+    if !stale.is_empty() {
+        let message = format!(
+            "Labels changed since the plan was computed: {}",
+            stale.join(", ")
+        );
+        if !no_verify {
+            return Err(crate::error::BiaoError::InvalidInput(format!(
+                "{}. Re-run the dry run or pass --no-verify.",
+                message
+            )));
+        }
+        eprintln!("{} {}", "warning:".yellow().bold(), message);
+    }
+
+    let summary = run_plan(client, &saved.plan(), false, false, false, 1, output).await?;
+    partial_failure(summary.failed)
+}
+
+/// Execute (or on a dry run, just report) each operation of `plan`; failed
+/// operations are counted in the summary rather than failing the run
+async fn run_plan(
+    client: &GithubClient,
+    plan: &crate::plan::Plan,
+    dry_run: bool,
+    report_unchanged: bool,
+    verify: bool,
+    jobs: usize,
+    output: OutputFormat,
+) -> Result<crate::plan::Summary> {
+    use crate::output::{ApplyReport, OperationReport};
+    use crate::plan::{OperationKind, Summary};
+
+    let smart = output == OutputFormat::Smart;
+    let human = output == OutputFormat::Human || smart;
+
+    // A dry run reports what the plan would do; a real run tallies actual outcomes
+    let mut summary = if dry_run { plan.summary() } else { Summary::default() };
+    let mut reports = Vec::new();
+    let mut log = crate::output::OperationLog::new(smart);
+    // Labels that should now read back exactly as planned
+    let mut applied = Vec::new();
+
+    if human {
+        log.push(format!(
+            "{} Processing {} operation(s):\n",
+            "▶".green(),
+            plan.operations.len()
+        ));
+    }
+    // Each batch is sent concurrently, then reported in plan order as if run one
+    // by one, so output streams batch by batch
+    let concurrent = jobs > 1 && !dry_run;
+    let batches = if concurrent {
+        plan.batches()
+    } else {
+        std::iter::once(0..plan.operations.len()).collect()
+    };
+    for batch in batches {
+        let outcomes = if concurrent {
+            run_concurrently(client, plan, batch.clone(), jobs).await?.into_iter().map(Some).collect()
+        } else {
+            vec![None; batch.len()]
+        };
+        for (op, outcome) in plan.operations[batch].iter().zip(outcomes) {
+            let hidden = op.kind == OperationKind::Noop && !report_unchanged;
+            if human && !hidden {
+                log.push(format_operation(op, dry_run));
+            }
+
+            let (result, error) = match outcome {
+                Some(outcome) => outcome,
+                None => run_operation(client, op, dry_run).await,
+            };
+
+            if !dry_run {
+                if error.is_some() {
+                    summary.failed += 1;
+                } else {
+                    summary.record(&op.kind);
+                    if matches!(op.kind, OperationKind::Create | OperationKind::Update | OperationKind::Rename { .. }) {
+                        applied.push(op);
+                    }
+                }
+            }
+
+            if human {
+                match (&op.kind, result, &error) {
+                    (OperationKind::Noop | OperationKind::Skip { .. }, _, _) => {}
+                    (_, _, Some(e)) => {
+                        // The log is silenced by --quiet, but failures still go to stderr
+                        if crate::output::is_quiet() {
+                            eprintln!("{} {} '{}': {}", "FAILED".red(), op.kind.action(), op.name, e);
+                        }
+                        log.push(format!("{}: {}\n", "FAILED".red(), e))
+                    }
+                    (kind, "dry_run", _) => log.push(format!("{}\n", dry_run_tag(kind).yellow())),
+                    _ => log.push(format!("{}\n", "OK".green())),
+                }
+            }
+
+            if hidden {
+                continue;
+            }
+
+            let report = OperationReport {
+                name: op.name.clone(),
+                action: op.kind.action(),
+                from: match &op.kind {
+                    OperationKind::Rename { from } => Some(from.clone()),
+                    _ => None,
+                },
+                result,
+                error,
+            };
+            if output == OutputFormat::Ndjson {
+                crate::output::print_ndjson(&report)?;
+            }
+            reports.push(report);
+        }
+    }
+
+    if verify {
+        for (op, mismatches) in applied.iter().zip(verify_applied(client, &applied, jobs).await?) {
+            if !mismatches.is_empty() {
+                summary.unverified += 1;
+                eprintln!(
+                    "{} --verify: '{}' {}",
+                    "warning:".yellow().bold(),
+                    op.name,
+                    mismatches.join(", ")
+                );
+            }
+        }
+    }
+
+    if !human {
+        let report = ApplyReport {
+            repository: client.repo_url(),
+            dry_run,
+            plan_hash: dry_run.then(|| plan.hash()),
+            operations: reports,
+            summary,
+        };
+        print!("{}", crate::output::render_apply_report(&report, output)?);
+        if output == OutputFormat::Json {
+            println!();
+        }
+        if output == OutputFormat::GithubActions {
+            write_step_summary(&crate::output::format_step_summary(&report))?;
+        }
+        return Ok(report.summary);
+    }
+
+    if crate::output::is_quiet() {
+        return Ok(summary);
+    }
+    if smart {
+        if summary.failed == 0 {
+            let line = crate::output::format_summary_line(&client.repo_url(), &summary);
+            if dry_run {
+                println!("{} {}", line, format!("(dry run, plan {})", plan.hash()).yellow());
+            } else {
+                println!("{}", line);
+            }
+            return Ok(summary);
+        }
+        print!("{}", log.flush(summary.failed));
+    }
+
     println!();
+    print_summary(&summary, report_unchanged);
+
+    if dry_run {
+        println!("\nPlan hash: {}", plan.hash().bold());
+        println!("{}", "This was a dry run. No actual changes were made.".yellow());
+    } else {
+        println!("\nLabels: {}", client.labels_web_url().dimmed());
+    }
+
+    Ok(summary)
+}
+
+/// A failed line of a run's progress, on stdout with the rest of it, or alone
+/// on stderr under `--quiet`
+fn print_failure(line: &str) {
+    if crate::output::is_quiet() {
+        eprintln!("{}", line);
+    } else {
+        println!("{}", line);
+    }
+}
+
+/// Fail the command once everything is reported if any operation failed
+fn partial_failure(failed: usize) -> Result<()> {
+    match failed {
+        0 => Ok(()),
+        failed => Err(crate::error::BiaoError::PartialFailure { failed }),
+    }
 }
 
-async fn cmd_template(subcommand: TemplateSubcommands) -> Result<()> {
+/// Append to the job summary when running inside GitHub Actions
+fn write_step_summary(markdown: &str) -> Result<()> {
+    use std::io::Write;
+
+    let Some(path) = std::env::var_os("GITHUB_STEP_SUMMARY") else {
+        return Ok(());
+    };
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(markdown.as_bytes())?;
+    Ok(())
+}
+
+/// Outcome of each operation in `batch` of `plan`, running up to `jobs` gh calls at a time.
+///
+/// gh calls block, so each one runs on tokio's blocking pool.
+async fn run_concurrently(
+    client: &GithubClient,
+    plan: &crate::plan::Plan,
+    batch: std::ops::Range<usize>,
+    jobs: usize,
+) -> Result<Vec<(&'static str, Option<String>)>> {
+    use std::sync::Arc;
+
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(jobs.max(1)));
+    let mut tasks = tokio::task::JoinSet::new();
+    for index in batch.clone() {
+        let client = client.clone();
+        let op = plan.operations[index].clone();
+        let semaphore = Arc::clone(&semaphore);
+
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let outcome = tokio::task::spawn_blocking(move || {
+                tokio::runtime::Handle::current().block_on(run_operation(&client, &op, false))
+            })
+            .await;
+            (index, outcome)
+        });
+    }
+
+    let mut outcomes = vec![("unchanged", None); batch.len()];
+    while let Some(joined) = tasks.join_next().await {
+        let (index, outcome) = joined
+            .map_err(|e| crate::error::BiaoError::InvalidInput(format!("Apply task failed: {}", e)))?;
+        outcomes[index - batch.start] = outcome
+            .map_err(|e| crate::error::BiaoError::InvalidInput(format!("Apply task failed: {}", e)))?;
+    }
+    Ok(outcomes)
+}
+
+/// How each of the `applied` labels reads back differently from what was sent,
+/// reading up to `jobs` at a time
+async fn verify_applied(
+    client: &GithubClient,
+    applied: &[&crate::plan::Operation],
+    jobs: usize,
+) -> Result<Vec<Vec<String>>> {
+    use std::sync::Arc;
+
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(jobs.max(1)));
+    let mut tasks = tokio::task::JoinSet::new();
+    for (index, op) in applied.iter().enumerate() {
+        let client = client.clone();
+        let op = (*op).clone();
+        let semaphore = Arc::clone(&semaphore);
+
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let label = tokio::task::spawn_blocking(move || {
+                let label = tokio::runtime::Handle::current().block_on(client.get_label(&op.name));
+                match label {
+                    Ok(label) => op.mismatches(&label),
+                    Err(e) => vec![format!("could not be read back: {}", e)],
+                }
+            })
+            .await;
+            (index, label)
+        });
+    }
+
+    let mut mismatches = vec![Vec::new(); applied.len()];
+    while let Some(joined) = tasks.join_next().await {
+        let (index, found) = joined
+            .map_err(|e| crate::error::BiaoError::InvalidInput(format!("Verify task failed: {}", e)))?;
+        mismatches[index] =
+            found.map_err(|e| crate::error::BiaoError::InvalidInput(format!("Verify task failed: {}", e)))?;
+    }
+    Ok(mismatches)
+}
+
+/// Failure of creating a label that exists, when the config neither skips nor updates it
+const ALREADY_EXISTS: &str = "label already exists (set skip_if_exists or update_if_exists to handle it)";
+
+async fn run_operation(
+    client: &GithubClient,
+    op: &crate::plan::Operation,
+    dry_run: bool,
+) -> (&'static str, Option<String>) {
+    use crate::plan::OperationKind;
+
+    match &op.kind {
+        OperationKind::Noop => ("unchanged", None),
+        OperationKind::Skip { .. } => ("skipped", None),
+        _ if dry_run => ("dry_run", None),
+        OperationKind::Exists => ("failed", Some(ALREADY_EXISTS.to_string())),
+        _ => match execute_with_retry(client, op).await {
+            Ok(()) => ("ok", None),
+            // Created by someone else since the labels were listed
+            Err(crate::error::BiaoError::AlreadyExists { .. }) => ("failed", Some(ALREADY_EXISTS.to_string())),
+            Err(e) => ("failed", Some(e.to_string())),
+        },
+    }
+}
+
+/// Log line for an operation; pending ones end without a newline, awaiting their result
+fn format_operation(op: &crate::plan::Operation, dry_run: bool) -> String {
+    use crate::plan::OperationKind;
+
+    match &op.kind {
+        OperationKind::Create => format!("  {} Creating '{}'... ", "✓".green(), op.name.cyan()),
+        // Fails without a request; marked like `diff` marks it
+        OperationKind::Exists => format!("  {} Creating '{}'... ", "!".red(), op.name.cyan()),
+        OperationKind::Update => format!("  {} Updating '{}'... ", "✓".blue(), op.name.cyan()),
+        OperationKind::Rename { from } => {
+            format!("  {} Renaming '{}' → '{}'... ", "↻".blue(), from.cyan(), op.name.cyan())
+        }
+        OperationKind::Delete => format!("  {} Deleting '{}'... ", "✗".red(), op.name.cyan()),
+        OperationKind::Noop => {
+            format!("  {} '{}' {}\n", "=".dimmed(), op.name.cyan(), "UNCHANGED".dimmed())
+        }
+        OperationKind::Skip { reason } if dry_run => format!(
+            "  {} '{}' {}\n",
+            "→".blue(),
+            op.name.cyan(),
+            format!("{} ({})", dry_run_tag(&op.kind), reason).yellow()
+        ),
+        OperationKind::Skip { reason } => format!(
+            "  {} '{}' {}\n",
+            "→".blue(),
+            op.name.cyan(),
+            format!("SKIPPED ({})", reason).yellow()
+        ),
+    }
+}
+
+/// What a dry run predicts for an operation, e.g. `[DRY RUN: would create]`
+fn dry_run_tag(kind: &crate::plan::OperationKind) -> String {
+    match kind {
+        crate::plan::OperationKind::Noop => "[DRY RUN: unchanged]".to_string(),
+        crate::plan::OperationKind::Exists => "[DRY RUN: would fail, already exists]".to_string(),
+        kind => format!("[DRY RUN: would {}]", kind.action()),
+    }
+}
+
+/// Run an operation, waiting out rate limits (with jitter) and retrying failures
+/// whose status is covered by the client's retry policy
+async fn execute_with_retry(client: &GithubClient, op: &crate::plan::Operation) -> Result<()> {
+    // Deletes already retry inside the client
+    if matches!(op.kind, crate::plan::OperationKind::Delete) {
+        return execute_operation(client, op).await;
+    }
+
+    let mut retries = 0;
+    loop {
+        let error = match execute_operation(client, op).await {
+            Ok(()) => return Ok(()),
+            Err(e) => e,
+        };
+        match client.retry_policy().rate_limit_wait(&error, retries) {
+            Some(delay) => {
+                crate::client::wait_for_rate_limit(delay + jitter(std::time::Duration::from_secs(5))).await;
+            }
+            None if client.retry_policy().should_retry(&error, retries) => {
+                tokio::time::sleep(client.retry_delay(retries)).await;
+            }
+            None => return Err(error),
+        }
+        retries += 1;
+    }
+}
+
+/// Random-ish delay up to `max`, so parallel runs don't retry in lockstep
+fn jitter(max: std::time::Duration) -> std::time::Duration {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos() as u64;
+    std::time::Duration::from_millis(nanos % (max.as_millis() as u64).max(1))
+}
+
+async fn execute_operation(client: &GithubClient, op: &crate::plan::Operation) -> Result<()> {
+    match op.request() {
+        Some(request) => client.send(&request).await,
+        None => Ok(()),
+    }
+}
+
+/// Every request `plan` would send, for `--dump-requests`
+fn format_request_dump(client: &GithubClient, plan: &crate::plan::Plan) -> Result<String> {
+    let mut dump = String::new();
+    for request in plan.operations.iter().filter_map(|op| op.request()) {
+        dump.push_str(&client.dump_request(&request)?);
+        dump.push('\n');
+    }
+    Ok(dump)
+}
+
+fn print_summary(summary: &crate::plan::Summary, report_unchanged: bool) {
+    println!("{}", "=== Summary ===".bold());
+    for (label, count) in summary.rows(report_unchanged) {
+        let label = match label {
+            "Unchanged:" => label.dimmed(),
+            "Skipped:" => label.yellow(),
+            "Failed:" | "Unverified:" => label.red(),
+            _ => label.green(),
+        };
+        println!("  {} {}", label, count);
+    }
+}
+
+/// Completion generator for a shell given by name or path, like `$SHELL`
+fn completion_for_shell(shell: &str) -> Option<CompletionSubcommands> {
+    let name = std::path::Path::new(shell).file_name()?.to_str()?;
+    // Login shells show up as `-zsh`
+    let name = name.trim_start_matches('-');
+    let name = name.strip_suffix(".exe").unwrap_or(name);
+
+    match name {
+        "bash" => Some(CompletionSubcommands::Bash),
+        "zsh" => Some(CompletionSubcommands::Zsh),
+        "fish" => Some(CompletionSubcommands::Fish),
+        "elvish" => Some(CompletionSubcommands::Elvish),
+        _ => None,
+    }
+}
+
+/// Client for the repository `__complete` lists labels of, if there is one.
+///
+/// Requests are not retried, so a slow or rate-limited API doesn't hang the shell.
+fn completion_client(repo: Option<&String>) -> Option<Box<dyn crate::provider::LabelProvider>> {
+    let (host, owner, repo) = match repo {
+        Some(slug) => crate::git::parse_repo_arg(slug).ok()?,
+        None => crate::git::get_repo_info().ok().map(|(host, owner, repo)| (Some(host), owner, repo))?,
+    };
+    if let Some(host) = host.as_deref().filter(|host| crate::provider::is_gitlab_host(host)) {
+        return Some(Box::new(crate::gitlab::GitlabClient::new(host, &owner, &repo).ok()?));
+    }
+    let mut client = GithubClient::new(owner, repo);
+    if let Some(host) = host {
+        client.set_host(host);
+    }
+    client.set_retry_policy(RetryPolicy::new(&[], Some(0)).without_waiting());
+    Some(Box::new(client))
+}
+
+/// Label names starting with `prefix`, ignoring case; none when listing fails
+async fn complete_label_names(client: &dyn crate::provider::LabelProvider, prefix: &str) -> Vec<String> {
+    let prefix = prefix.to_lowercase();
+    client
+        .list_labels()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|label| label.name)
+        .filter(|name| name.to_lowercase().starts_with(&prefix))
+        .collect()
+}
+
+/// Completes label names for `get`, `update` and `delete` through `biao __complete`;
+/// wraps the generated `_biao` and registers itself instead
+const BASH_LABEL_COMPLETION: &str = r#"
+_biao_labels() {
+    if [[ ${COMP_CWORD} -eq 2 && ${COMP_WORDS[1]} =~ ^(get|update|delete)$ ]]; then
+        compopt +o default +o bashdefault 2>/dev/null
+        local IFS=$'\n'
+        COMPREPLY=( $(biao __complete "${COMP_WORDS[COMP_CWORD]}" 2>/dev/null | while IFS= read -r name; do printf '%q\n' "$name"; done) )
+        return 0
+    fi
+    _biao "$@"
+}
+
+complete -F _biao_labels -o bashdefault -o default biao
+"#;
+
+/// The generated zsh script's dispatch, replaced by [`ZSH_LABEL_COMPLETION`]
+const ZSH_DISPATCH: &str = r#"if [ "$funcstack[1]" = "_biao" ]; then
+    _biao "$@"
+else
+    compdef _biao biao
+fi
+"#;
+
+const ZSH_LABEL_COMPLETION: &str = r#"_biao_labels() {
+    if (( CURRENT == 3 )) && [[ ${words[2]} == (get|update|delete) ]]; then
+        local -a labels
+        labels=(${(f)"$(biao __complete "${words[CURRENT]}" 2>/dev/null)"})
+        compadd -a labels
+        return
+    fi
+    _biao "$@"
+}
+
+if [ "$funcstack[1]" = "_biao" ]; then
+    _biao_labels "$@"
+else
+    compdef _biao_labels biao
+fi
+"#;
+
+const FISH_LABEL_COMPLETION: &str = r#"
+complete -c biao -n "__fish_seen_subcommand_from get update delete; and test (count (commandline -opc)) -eq 2" -f -a "(biao __complete (commandline -ct) 2>/dev/null)"
+"#;
+
+/// A generated completion script, extended to complete label names where the shell allows it
+fn with_label_completion(shell: &CompletionSubcommands, script: String) -> String {
+    match shell {
+        CompletionSubcommands::Bash => script + BASH_LABEL_COMPLETION,
+        // zsh runs the dispatch when the function is autoloaded, so it has to be swapped out
+        CompletionSubcommands::Zsh => script.replace(ZSH_DISPATCH, ZSH_LABEL_COMPLETION),
+        CompletionSubcommands::Fish => script + FISH_LABEL_COMPLETION,
+        CompletionSubcommands::Elvish => script,
+    }
+}
+
+async fn cmd_completion(subcommand: Option<CompletionSubcommands>) -> Result<()> {
+    use clap::CommandFactory;
+
+    let subcommand = match subcommand {
+        Some(subcommand) => subcommand,
+        None => {
+            let shell = std::env::var("SHELL").unwrap_or_default();
+            completion_for_shell(&shell).ok_or_else(|| {
+                crate::error::BiaoError::InvalidInput(format!(
+                    "Could not detect a supported shell from $SHELL ('{}'). \
+                     Run `biao completion <bash|zsh|fish|elvish>` instead.",
+                    shell
+                ))
+            })?
+        }
+    };
+
+    let mut cmd = Args::command();
+    let mut script = Vec::new();
+
+    match subcommand {
+        CompletionSubcommands::Bash => {
+            use clap_complete::shells::Bash;
+            clap_complete::generate(Bash, &mut cmd, "biao", &mut script);
+        }
+        CompletionSubcommands::Zsh => {
+            use clap_complete::shells::Zsh;
+            clap_complete::generate(Zsh, &mut cmd, "biao", &mut script);
+        }
+        CompletionSubcommands::Fish => {
+            use clap_complete::shells::Fish;
+            clap_complete::generate(Fish, &mut cmd, "biao", &mut script);
+        }
+        CompletionSubcommands::Elvish => {
+            use clap_complete::shells::Elvish;
+            clap_complete::generate(Elvish, &mut cmd, "biao", &mut script);
+        }
+    }
+
+    print!("{}", with_label_completion(&subcommand, String::from_utf8_lossy(&script).into_owned()));
+    Ok(())
+}
+
+async fn cmd_template(
+    subcommand: TemplateSubcommands,
+    output: OutputFormat,
+    repo: Option<(Option<String>, String, String)>,
+    retry: &RetryPolicy,
+) -> Result<()> {
     use crate::templates::TemplateManager;
 
     let manager = TemplateManager::new()?;
 
     match subcommand {
-        TemplateSubcommands::List => {
-            let templates = manager.list()?;
+        TemplateSubcommands::List { tag, category } => {
+            let templates = crate::templates::filter(manager.list()?, tag.as_deref(), category.as_deref());
             println!("{}", "Available Templates:".bold());
             println!();
 
@@ -657,53 +3245,766 @@ async fn cmd_template(subcommand: TemplateSubcommands) -> Result<()> {
                     template.description,
                     path_display
                 );
+                if !template.tags.is_empty() {
+                    println!("      {}", format!("tags: {}", template.tags.join(", ")).dimmed());
+                }
             }
             println!();
             println!("Use {} to apply a template", "biao template apply <name>".italic());
         }
 
-        TemplateSubcommands::Show { name } => {
+        TemplateSubcommands::Categories => {
+            for (category, names) in crate::templates::categories(&manager.list()?) {
+                println!("{} ({})", category.cyan().bold(), names.len());
+                for name in names {
+                    println!("  {}", name);
+                }
+            }
+            println!();
+            println!("Use {} to see one category", "biao template list --category <name>".italic());
+        }
+
+        TemplateSubcommands::Show {
+            name,
+            rendered: true,
+            vars,
+            color_theme,
+        } => {
+            let theme = color_theme.map(crate::theme::ColorTheme::from_file).transpose()?;
+            print!("{}", crate::templates::render(&manager.get(&name)?, &vars, theme.as_ref())?);
+        }
+
+        TemplateSubcommands::Show { name, .. } => {
             let content = manager.get(&name)?;
             println!("{}", "Template: ".bold());
             println!("{}\n", name.cyan().bold());
             println!("{}", content);
         }
 
+        TemplateSubcommands::New { name, force } => {
+            let dir = crate::templates::user_template_dir().ok_or_else(|| {
+                crate::error::BiaoError::InvalidInput("HOME is not set; can't locate the template directory".to_string())
+            })?;
+            let path = crate::templates::create_template(&dir, &name, force)?;
+            status!("✓ Created template {} at {}", name.cyan(), path.display());
+        }
+
         TemplateSubcommands::Apply {
             name,
             dry_run,
             skip_existing,
+            color_theme,
+            vars,
         } => {
-            let content = manager.get(&name)?;
-            println!("Repository: {}", "auto-detected".cyan());
-            println!("Template: {}\n", name.cyan());
-
-            // We need to get the client for this
-            // Since we're here, we know the git repo was already validated
-            let _ = crate::git::find_git_root()?;
-            let (owner, repo) = crate::git::get_repo_info()?;
-            let client = GithubClient::new(owner, repo);
-
-            // Write template to temp file
-            let timestamp = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs();
-            let temp_file = format!("/tmp/biao-template-{}.toml", timestamp);
-            std::fs::write(&temp_file, &content).map_err(|e| {
-                crate::error::BiaoError::Io(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    format!("Failed to write template to temp file: {}", e),
-                ))
-            })?;
-
-            // Apply the temp file
-            cmd_apply(&client, &temp_file, dry_run, skip_existing).await?;
+            let content = crate::templates::substitute_vars(&manager.get(&name)?, &vars)?;
+            let (host, owner, repo) = match repo {
+                Some(repo) => repo,
+                None => {
+                    crate::git::find_git_root()?;
+                    let (host, owner, repo) = crate::git::get_repo_info()?;
+                    (Some(host), owner, repo)
+                }
+            };
+            let client = github_client(host, owner, repo, retry);
+            if output == OutputFormat::Human {
+                crate::output::print_banner(&client.repo_url());
+                println!("Template: {}\n", name.cyan());
+            }
 
-            // Clean up
-            let _ = std::fs::remove_file(&temp_file);
+            let options = ApplyOptions {
+                dry_run,
+                skip_existing,
+                color_theme,
+                snapshot_dir: crate::snapshot::default_snapshot_dir(None),
+                output,
+                ..Default::default()
+            };
+            apply_template(&client, &content, &options).await?;
         }
     }
 
     Ok(())
 }
+
+/// Apply a rendered template, parsed in memory rather than through a temp file
+async fn apply_template(client: &GithubClient, content: &str, options: &ApplyOptions) -> Result<()> {
+    let config = prepare_config(content.parse()?, options)?;
+    if options.output == OutputFormat::Human {
+        print_config_heading(&config);
+    }
+    apply_config(client, &config, options).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::mock::{self, MockRunner};
+
+    const LABELS: &str = r#"[{"name":"bug","color":"d73a49","description":null,"url":"u","id":1,"node_id":"n","default":false}]"#;
+
+    #[tokio::test]
+    async fn test_clear_keeps_protected_labels() {
+        let runner = MockRunner::new(vec![mock::ok(LABELS)]);
+        let calls = runner.calls.clone();
+        let client = GithubClient::with_runner("o", "r", runner);
+
+        cmd_clear(&client, true, false, &["b*".to_string()]).await.unwrap();
+        // Listed once, nothing deleted
+        assert_eq!(calls.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_locked_apply_with_drift_makes_no_changes() {
+        let runner = MockRunner::new(vec![mock::ok(LABELS)]);
+        let calls = runner.calls.clone();
+        let client = GithubClient::with_runner("o", "r", runner);
+        let config: crate::config::LabelConfig =
+            "delete = [\"bug\"]\n[[labels]]\nname = \"feature\"\ncolor = \"a2eeef\"\n"
+                .parse()
+                .unwrap();
+        let options = ApplyOptions {
+            locked: true,
+            output: OutputFormat::Json,
+            ..Default::default()
+        };
+
+        let err = apply_config(&client, &config, &options).await.unwrap_err();
+        assert!(err.to_string().contains("2 pending change(s): create 'feature', delete 'bug'"));
+        // Only the label listing was requested
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec!["--include --method GET repos/o/r/labels -F per_page=100 -F page=1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply_to_empty_repository_only_creates() {
+        let client = GithubClient::with_runner("o", "r", MockRunner::new(vec![mock::ok("[]")]));
+        let config: crate::config::LabelConfig =
+            "delete = [\"wontfix\"]\n[[labels]]\nname = \"bug\"\ncolor = \"d73a49\"\n".parse().unwrap();
+        let options = ApplyOptions {
+            locked: true,
+            output: OutputFormat::Json,
+            ..Default::default()
+        };
+
+        let err = apply_config(&client, &config, &options).await.unwrap_err();
+        assert!(err.to_string().contains("(1 pending change(s): create 'bug')"));
+    }
+
+    #[tokio::test]
+    async fn test_offline_apply_plans_creates_without_gh() {
+        let runner = MockRunner::new(vec![]);
+        let calls = runner.calls.clone();
+        let client = GithubClient::with_runner("", "", runner);
+        let config: crate::config::LabelConfig =
+            "[[labels]]\nname = \"bug\"\ncolor = \"d73a49\"\n\n[[labels]]\nname = \"feature\"\ncolor = \"a2eeef\"\n"
+                .parse()
+                .unwrap();
+        let options = ApplyOptions {
+            offline: true,
+            dry_run: true,
+            locked: true,
+            output: OutputFormat::Json,
+            ..Default::default()
+        };
+
+        // --locked reports every pending change, which offline are all creates
+        let err = apply_config(&client, &config, &options).await.unwrap_err();
+        assert!(err.to_string().contains("2 pending change(s): create 'bug', create 'feature'"));
+        assert!(calls.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_label_sort() {
+        let mut labels: Vec<crate::models::GithubLabel> = serde_json::from_str(
+            r#"[
+                {"name":"wontfix","color":"ffffff","description":null,"url":"u","id":1,"node_id":"n","default":true},
+                {"name":"area/cli","color":"FFFFFF","description":null,"url":"u","id":2,"node_id":"n","default":false},
+                {"name":"Bug","color":"d73a49","description":null,"url":"u","id":3,"node_id":"n","default":true}
+            ]"#,
+        )
+        .unwrap();
+        let names = |labels: &[crate::models::GithubLabel]| -> Vec<String> {
+            labels.iter().map(|label| label.name.clone()).collect()
+        };
+
+        LabelSort::Name.sort(&mut labels);
+        assert_eq!(names(&labels), ["area/cli", "Bug", "wontfix"]);
+        LabelSort::Color.sort(&mut labels);
+        assert_eq!(names(&labels), ["Bug", "area/cli", "wontfix"]);
+        LabelSort::Default.sort(&mut labels);
+        assert_eq!(names(&labels), ["Bug", "wontfix", "area/cli"]);
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        use crate::error::BiaoError;
+
+        assert_eq!(BiaoError::PartialFailure { failed: 2 }.exit_code(), 1);
+        assert_eq!(BiaoError::Drift(3).exit_code(), 1);
+        assert_eq!(BiaoError::Unverified(1).exit_code(), 1);
+        assert_eq!(BiaoError::InvalidInput("bad".to_string()).exit_code(), 2);
+        assert_eq!(BiaoError::Unauthorized.exit_code(), 3);
+
+        // clap's own usage errors agree
+        let err = Args::try_parse_from(["biao", "list", "--no-such-flag"]).err().unwrap();
+        assert_eq!(err.exit_code(), crate::error::EXIT_USAGE as i32);
+    }
+
+    #[tokio::test]
+    async fn test_migrate_truncates_long_scheme_names() {
+        let path = std::env::temp_dir().join(format!("biao_mapping_{}.toml", std::process::id()));
+        let long = "x".repeat(crate::config::MAX_NAME_LENGTH + 5);
+        std::fs::write(&path, format!("[schemes.old]\nbug = \"bug\"\n\n[schemes.new]\nbug = \"{}\"\n", long)).unwrap();
+        let mapping = path.to_str().unwrap();
+        let mut options = ApplyOptions {
+            dry_run: true,
+            output: OutputFormat::Json,
+            ..Default::default()
+        };
+
+        let runner = MockRunner::new(vec![]);
+        let calls = runner.calls.clone();
+        let client = GithubClient::with_runner("o", "r", runner);
+        assert!(cmd_migrate(&client, "old", "new", Some(mapping), &options).await.is_err());
+        assert!(calls.lock().unwrap().is_empty());
+
+        options.truncate_names = true;
+        let client = GithubClient::with_runner("o", "r", MockRunner::new(vec![mock::ok(LABELS)]));
+        cmd_migrate(&client, "old", "new", Some(mapping), &options).await.unwrap();
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_repo_client_keeps_host() {
+        // An enterprise host gh is logged in to
+        let dir = std::env::temp_dir().join(format!("biao-gh-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("hosts.yml"), "ghe.example.com:\n    user: octocat\n").unwrap();
+        std::env::set_var("GH_CONFIG_DIR", &dir);
+
+        let retry = RetryPolicy::default();
+        let client = repo_client("https://ghe.example.com/o/api", &retry).unwrap();
+        assert_eq!(client.labels_web_url(), "https://ghe.example.com/o/api/labels");
+        let client = repo_client("o/web", &retry).unwrap();
+        assert_eq!(client.repo_url(), "o/web");
+        assert!(repo_client("https://gitlab.com/o/docs", &retry).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_read_repos_file() {
+        let path = std::env::temp_dir().join(format!("biao_repos_{}.txt", std::process::id()));
+        std::fs::write(&path, "# services\no/api\n\n  o/web  \n").unwrap();
+        assert_eq!(read_repos_file(&path).unwrap(), vec!["o/api", "o/web"]);
+
+        std::fs::write(&path, "o/api\nnot-a-repo\n").unwrap();
+        assert!(read_repos_file(&path).is_err());
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_discover_config() {
+        let root = std::env::temp_dir().join(format!("biao-discover-{}", std::process::id()));
+        let sub = root.join("crates").join("core");
+        std::fs::create_dir_all(&sub).unwrap();
+
+        let missing = discover_config(Some(&root), &sub).unwrap_err().to_string();
+        assert!(missing.contains(&root.join("labels.toml").display().to_string()));
+        assert!(missing.contains(&sub.join("labels.toml").display().to_string()));
+
+        // The subdirectory's config is the fallback, the root's wins
+        std::fs::write(sub.join("labels.toml"), "").unwrap();
+        assert_eq!(discover_config(Some(&root), &sub).unwrap(), std::path::Path::new("labels.toml"));
+        std::fs::write(root.join("labels.toml"), "").unwrap();
+        assert_eq!(discover_config(Some(&root), &sub).unwrap(), root.join("labels.toml"));
+        assert_eq!(discover_config(Some(&root), &root).unwrap(), std::path::Path::new("labels.toml"));
+        assert_eq!(discover_config(None, &sub).unwrap(), std::path::Path::new("labels.toml"));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_global_repo_flag() {
+        use clap::CommandFactory;
+        Args::command().debug_assert();
+
+        let args = Args::try_parse_from(["biao", "list", "--repo", "cli/cli"]).unwrap();
+        assert_eq!(args.repo, vec!["cli/cli"]);
+
+        // Repeated, it spreads apply over several repositories
+        let mut args =
+            Args::try_parse_from(["biao", "apply", "labels.toml", "--repo", "o/a", "--repo", "o/b"]).unwrap();
+        spread_repos(&mut args.command, &mut args.repo).unwrap();
+        assert!(args.repo.is_empty());
+        assert!(matches!(&args.command, Commands::Apply { repos, .. } if repos == &["o/a", "o/b"]));
+
+        let mut args = Args::try_parse_from(["biao", "--repo", "o/a", "--repo", "o/b", "list"]).unwrap();
+        assert!(spread_repos(&mut args.command, &mut args.repo).is_err());
+
+        let mut args =
+            Args::try_parse_from(["biao", "--repo", "o/a", "--repo", "o/b", "apply", "l.toml", "--verify"]).unwrap();
+        let err = spread_repos(&mut args.command, &mut args.repo).unwrap_err();
+        assert!(err.to_string().contains("--verify works on one repository"));
+    }
+
+    #[test]
+    fn test_json_flag() {
+        let args = Args::try_parse_from(["biao", "list", "--json"]).unwrap();
+        assert!(args.json);
+        assert!(Args::try_parse_from(["biao", "get", "bug", "--json", "--output", "csv"]).is_err());
+
+        // `auth status --json` is the global flag
+        let args = Args::try_parse_from(["biao", "auth", "status", "--json"]).unwrap();
+        assert!(args.json);
+        assert!(check_output_format(&args.command, OutputFormat::Json).is_ok());
+        let args = Args::try_parse_from(["biao", "auth", "login"]).unwrap();
+        assert!(check_output_format(&args.command, OutputFormat::Json).is_err());
+
+        let args = Args::try_parse_from(["biao", "list", "--group-by-prefix"]).unwrap();
+        assert!(check_output_format(&args.command, OutputFormat::Human).is_ok());
+        assert!(check_output_format(&args.command, OutputFormat::Json).is_err());
+    }
+
+    #[test]
+    fn test_normalize_color() {
+        assert_eq!(normalize_color("red").unwrap(), "b60205");
+        assert_eq!(normalize_color("Blue").unwrap(), "1d76db");
+        assert_eq!(normalize_color("#D73A49").unwrap(), "d73a49");
+        assert_eq!(normalize_color("0075ca").unwrap(), "0075ca");
+
+        let err = normalize_color("reddish").unwrap_err().to_string();
+        assert!(err.contains("6 hex digits") && err.contains("red, orange"));
+        assert!(normalize_color("zzzzzz").is_err());
+    }
+
+    #[test]
+    fn test_label_text_contrast() {
+        // GitHub's own palette is readable
+        for color in ["d73a49", "0075ca", "cfd3d7", "a2eeef", "7057ff", "008672", "e4e669", "ffffff"] {
+            assert!(label_text_contrast(color) >= MIN_LABEL_CONTRAST, "{}", color);
+        }
+        assert!((label_text_contrast("000000") - 21.0).abs() < 0.01);
+
+        // Just dark enough for GitHub to draw white text on it
+        assert!(label_text_contrast("ff30ff") < MIN_LABEL_CONTRAST);
+        assert!(check_contrast("pink", "ff30ff", false).is_ok());
+        let err = check_contrast("pink", "ff30ff", true).unwrap_err().to_string();
+        assert!(err.contains("'pink'") && err.contains("--strict-contrast"));
+    }
+
+    #[test]
+    fn test_is_yes() {
+        assert!(is_yes("y\n"));
+        assert!(is_yes(" YES "));
+        assert!(is_yes("Yes"));
+        assert!(!is_yes(""));
+        assert!(!is_yes("no"));
+        assert!(!is_yes("yess"));
+    }
+
+    #[test]
+    fn test_read_answer_from_pipe() {
+        // `echo y | biao delete bug`
+        let mut piped = "y\n".as_bytes();
+        assert!(is_yes(&read_answer(&mut piped).unwrap()));
+        let mut piped = "\n".as_bytes();
+        assert!(!is_yes(&read_answer(&mut piped).unwrap()));
+
+        // Nothing piped in, as in CI
+        let err = read_answer(&mut "".as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("Pass --force"));
+    }
+
+    #[test]
+    fn test_matches_exact() {
+        assert!(matches_exact("owner/repo\n", "owner/repo"));
+        assert!(matches_exact("  owner/repo  ", "owner/repo"));
+        assert!(!matches_exact("Owner/Repo", "owner/repo"));
+        assert!(!matches_exact("y", "owner/repo"));
+        assert!(!matches_exact("owner/repo2", "owner/repo"));
+    }
+
+    #[test]
+    fn test_dry_run_tags() {
+        use crate::plan::{Operation, OperationKind};
+
+        assert_eq!(dry_run_tag(&OperationKind::Create), "[DRY RUN: would create]");
+        assert_eq!(dry_run_tag(&OperationKind::Update), "[DRY RUN: would update]");
+        assert_eq!(
+            dry_run_tag(&OperationKind::Rename { from: "bug".to_string() }),
+            "[DRY RUN: would rename]"
+        );
+        assert_eq!(dry_run_tag(&OperationKind::Delete), "[DRY RUN: would delete]");
+
+        let skip = Operation {
+            name: "bug".to_string(),
+            kind: OperationKind::Skip {
+                reason: "already exists".to_string(),
+            },
+            color: None,
+            description: None,
+        };
+        colored::control::set_override(false);
+        assert_eq!(
+            format_operation(&skip, true),
+            "  → 'bug' [DRY RUN: would skip] (already exists)\n"
+        );
+        assert_eq!(format_operation(&skip, false), "  → 'bug' SKIPPED (already exists)\n");
+
+        let exists = Operation {
+            kind: OperationKind::Exists,
+            ..skip
+        };
+        assert_eq!(format_operation(&exists, false), "  ! Creating 'bug'... ");
+    }
+
+    #[test]
+    fn test_select_org_repos() {
+        let repos: Vec<crate::models::OrgRepository> = serde_json::from_str(
+            r#"[
+                {"name":"api","full_name":"org/api","archived":false,"private":true},
+                {"name":"legacy","full_name":"org/legacy","archived":true},
+                {"name":"docs-site","full_name":"org/docs-site"},
+                {"name":"sandbox-1","full_name":"org/sandbox-1","archived":false}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            select_org_repos(repos.clone(), false, &["sandbox-*".to_string()]),
+            vec!["org/api", "org/docs-site"]
+        );
+        assert_eq!(select_org_repos(repos, true, &[]).len(), 4);
+    }
+
+    #[test]
+    fn test_completion_for_shell() {
+        assert_eq!(completion_for_shell("/bin/bash"), Some(CompletionSubcommands::Bash));
+        assert_eq!(completion_for_shell("/usr/local/bin/zsh"), Some(CompletionSubcommands::Zsh));
+        assert_eq!(completion_for_shell("-zsh"), Some(CompletionSubcommands::Zsh));
+        assert_eq!(completion_for_shell("/opt/homebrew/bin/fish"), Some(CompletionSubcommands::Fish));
+        assert_eq!(completion_for_shell("elvish"), Some(CompletionSubcommands::Elvish));
+        assert_eq!(completion_for_shell("/bin/tcsh"), None);
+        assert_eq!(completion_for_shell(""), None);
+    }
+
+    #[tokio::test]
+    async fn test_complete_label_names() {
+        let labels = r#"[
+            {"name": "bug", "color": "d73a4a", "description": null, "url": "", "id": 1, "node_id": "", "default": true},
+            {"name": "Build", "color": "ededed", "description": null, "url": "", "id": 2, "node_id": "", "default": false},
+            {"name": "docs", "color": "0075ca", "description": null, "url": "", "id": 3, "node_id": "", "default": false}
+        ]"#;
+        let client = GithubClient::with_runner("o", "r", MockRunner::new(vec![mock::ok(labels), mock::ok(labels)]));
+        assert_eq!(complete_label_names(&client, "b").await, ["bug", "Build"]);
+        assert_eq!(complete_label_names(&client, "").await.len(), 3);
+
+        // A failing listing completes nothing instead of erroring
+        let failing = crate::client::GhOutput {
+            success: false,
+            stdout: String::new(),
+            stderr: "gh: Not Found (HTTP 404)".to_string(),
+        };
+        let client = GithubClient::with_runner("o", "r", MockRunner::new(vec![failing]));
+        assert!(complete_label_names(&client, "b").await.is_empty());
+    }
+
+    #[test]
+    fn test_label_completion_scripts() {
+        let zsh = format!("#compdef biao\n_biao() {{}}\n\n{}", ZSH_DISPATCH);
+        let zsh = with_label_completion(&CompletionSubcommands::Zsh, zsh);
+        assert!(zsh.contains("compdef _biao_labels biao"));
+        assert!(!zsh.contains("compdef _biao biao"));
+
+        let bash = with_label_completion(&CompletionSubcommands::Bash, "_biao() { :; }\n".to_string());
+        assert!(bash.ends_with("complete -F _biao_labels -o bashdefault -o default biao\n"));
+        assert!(with_label_completion(&CompletionSubcommands::Fish, String::new()).contains("biao __complete"));
+    }
+
+    #[test]
+    fn test_template_drift_against_standard() {
+        colored::control::set_override(false);
+        let existing: Vec<crate::models::GithubLabel> = serde_json::from_str(
+            r#"[
+                {"name":"bug","color":"ff0000","description":"Something isn't working","url":"u","id":1,"node_id":"n","default":true},
+                {"name":"enhancement","color":"a2eeef","description":"New feature or request","url":"u","id":2,"node_id":"n","default":true}
+            ]"#,
+        )
+        .unwrap();
+        let content = crate::templates::TemplateManager::default().get("standard").unwrap();
+
+        let plan = template_drift_plan(&content, &existing).unwrap();
+        let diff = crate::output::format_diff(&plan, &existing, &[]);
+        let lines: Vec<&str> = diff.lines().collect();
+
+        assert!(lines[0].starts_with("~ bug color: #ff0000 → #d73a49"));
+        assert!(lines[1].starts_with("> enhancement → feature"));
+        assert!(lines.contains(&"+ documentation #0075ca"));
+    }
+
+    #[tokio::test]
+    async fn test_export_skips_default_labels() {
+        let labels = r#"[
+            {"name":"bug","color":"d73a49","description":"Something isn't working","url":"u","id":1,"node_id":"n","default":true},
+            {"name":"A-parser","color":"c2e0c6","description":null,"url":"u","id":2,"node_id":"n","default":false}
+        ]"#;
+        let file = std::env::temp_dir().join(format!("biao-export-{}.toml", std::process::id()));
+        let path = file.to_str().unwrap();
+
+        let client = GithubClient::with_runner("o", "r", MockRunner::new(vec![mock::ok(labels)]));
+        cmd_export(&client, false, Some(path), false).await.unwrap();
+        let content = std::fs::read_to_string(&file).unwrap();
+        assert!(!content.contains("\"bug\""));
+        assert!(content.contains("name = \"A-parser\""));
+        assert!(!content.contains("description"));
+        std::fs::remove_file(&file).unwrap();
+
+        let client = GithubClient::with_runner("o", "r", MockRunner::new(vec![mock::ok(labels)]));
+        cmd_export(&client, false, Some(path), true).await.unwrap();
+        let content = std::fs::read_to_string(&file).unwrap();
+        assert!(content.contains("name = \"bug\""));
+        std::fs::remove_file(&file).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_apply_skips_create_call_for_existing_label() {
+        let runner = MockRunner::new(vec![mock::ok(LABELS)]);
+        let calls = runner.calls.clone();
+        let client = GithubClient::with_runner("o", "r", runner);
+        let config: crate::config::LabelConfig =
+            "[[labels]]\nname = \"bug\"\ncolor = \"ff0000\"\n".parse().unwrap();
+        let options = ApplyOptions {
+            output: OutputFormat::Json,
+            ..Default::default()
+        };
+
+        let err = apply_config(&client, &config, &options).await.unwrap_err();
+        assert!(matches!(err, crate::error::BiaoError::PartialFailure { failed: 1 }));
+        // Only the label listing; the doomed create is never sent
+        assert_eq!(calls.lock().unwrap().len(), 1);
+
+        // A dry run reports it but still exits successfully
+        let client = GithubClient::with_runner("o", "r", MockRunner::new(vec![mock::ok(LABELS)]));
+        let options = ApplyOptions {
+            dry_run: true,
+            ..options
+        };
+        apply_config(&client, &config, &options).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_apply_template_in_memory() {
+        let template_files = || {
+            std::fs::read_dir(std::env::temp_dir())
+                .map(|entries| {
+                    entries
+                        .flatten()
+                        .filter(|e| e.file_name().to_string_lossy().starts_with("biao-template-"))
+                        .count()
+                })
+                .unwrap_or(0)
+        };
+        let before = template_files();
+
+        const CREATED: &str =
+            r#"{"name":"infra/triage","color":"d73a49","description":null,"url":"u","id":2,"node_id":"n","default":false}"#;
+        let runner = MockRunner::new(vec![mock::ok(LABELS), mock::ok(CREATED)]);
+        let calls = runner.calls.clone();
+        let inputs = runner.inputs.clone();
+        let client = GithubClient::with_runner("o", "r", runner);
+        let content = crate::templates::substitute_vars(
+            "[[labels]]\nname = \"{{ team }}/triage\"\ncolor = \"d73a49\"\n",
+            &[("team".to_string(), "infra".to_string())],
+        )
+        .unwrap();
+        let options = ApplyOptions {
+            skip_existing: true,
+            output: OutputFormat::Json,
+            ..Default::default()
+        };
+        apply_template(&client, &content, &options).await.unwrap();
+
+        assert_eq!(calls.lock().unwrap().len(), 2);
+        assert!(inputs.lock().unwrap().iter().flatten().any(|body| body.contains("infra/triage")));
+        assert_eq!(template_files(), before);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_apply() {
+        let labels: String = (0..6)
+            .map(|i| format!("[[labels]]\nname = \"l{}\"\ncolor = \"d73a49\"\n", i))
+            .collect();
+        let config: crate::config::LabelConfig = labels.parse().unwrap();
+        const CREATED: &str =
+            r#"{"name":"l","color":"d73a49","description":null,"url":"u","id":1,"node_id":"n","default":false}"#;
+        let runner = MockRunner::new((0..6).map(|_| mock::ok(CREATED)).collect());
+        let calls = runner.calls.clone();
+        let client = GithubClient::with_runner("o", "r", runner);
+
+        let plan = crate::plan::Plan::build(&config, &[], false).unwrap();
+        let outcomes = run_concurrently(&client, &plan, 0..6, 4).await.unwrap();
+        assert_eq!(outcomes, vec![("ok", None); 6]);
+        assert_eq!(calls.lock().unwrap().len(), 6);
+
+        let options = ApplyOptions {
+            label_jobs: 4,
+            output: OutputFormat::Json,
+            ..Default::default()
+        };
+        let runner = MockRunner::new((0..7).map(|i| mock::ok(if i == 0 { "[]" } else { CREATED })).collect());
+        let client = GithubClient::with_runner("o", "r", runner);
+        apply_config(&client, &config, &options).await.unwrap();
+    }
+
+    /// Runner answering label `lN` after `60 - 10N` ms, so later labels finish
+    /// first, and failing the odd ones
+    struct ReversedRunner;
+
+    impl crate::client::GhRunner for ReversedRunner {
+        fn run(&self, _args: &[&str], input: Option<&str>) -> std::io::Result<crate::client::GhOutput> {
+            let body: serde_json::Value = serde_json::from_str(input.unwrap_or("{}")).unwrap();
+            let index: u64 = body["name"].as_str().unwrap()[1..].parse().unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(60 - 10 * index));
+            Ok(match index % 2 {
+                0 => mock::ok(&format!(
+                    r#"{{"name":"l{}","color":"d73a49","description":null,"url":"u","id":1,"node_id":"n","default":false}}"#,
+                    index
+                )),
+                _ => crate::client::GhOutput {
+                    success: false,
+                    stdout: "HTTP/2.0 404 Not Found\r\n\r\n{\"message\":\"Not Found\"}".to_string(),
+                    stderr: String::new(),
+                },
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_outcomes_follow_plan_order() {
+        let labels: String = (0..6)
+            .map(|i| format!("[[labels]]\nname = \"l{}\"\ncolor = \"d73a49\"\n", i))
+            .collect();
+        let config: crate::config::LabelConfig = labels.parse().unwrap();
+        let client = GithubClient::with_runner("o", "r", ReversedRunner);
+        let plan = crate::plan::Plan::build(&config, &[], false).unwrap();
+
+        let outcomes = run_concurrently(&client, &plan, 0..6, 6).await.unwrap();
+        let results: Vec<&str> = outcomes.iter().map(|(result, _)| *result).collect();
+        assert_eq!(results, ["ok", "failed", "ok", "failed", "ok", "failed"]);
+    }
+
+    #[tokio::test]
+    async fn test_ndjson_one_line_per_operation() {
+        let labels: String = (0..4)
+            .map(|i| format!("[[labels]]\nname = \"l{}\"\ncolor = \"d73a49\"\n", i))
+            .collect();
+        let config: crate::config::LabelConfig = labels.parse().unwrap();
+        let plan = crate::plan::Plan::build(&config, &[], false).unwrap();
+        let printed = || crate::output::PRINTED_NDJSON.with(|printed| printed.take());
+        printed();
+
+        // Later labels finish first, yet lines follow the plan
+        let client = GithubClient::with_runner("o", "r", ReversedRunner);
+        let summary = run_plan(&client, &plan, false, false, false, 4, OutputFormat::Ndjson).await.unwrap();
+        assert_eq!(summary.failed, 2);
+        let stream = printed();
+        let records: Vec<serde_json::Value> =
+            stream.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        let names: Vec<&str> = records.iter().map(|record| record["name"].as_str().unwrap()).collect();
+        assert_eq!(names, ["l0", "l1", "l2", "l3"]);
+        assert_eq!(
+            stream.lines().next().unwrap(),
+            r#"{"name":"l0","action":"create","result":"ok"}"#
+        );
+
+        let client = GithubClient::with_runner("o", "r", MockRunner::new(vec![]));
+        run_plan(&client, &plan, true, false, false, 1, OutputFormat::Ndjson).await.unwrap();
+        assert_eq!(printed().lines().count(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_verify_failure_exits_with_failed_operations() {
+        const CREATED: &str =
+            r#"{"name":"bug","color":"d73a49","description":null,"url":"u","id":1,"node_id":"n","default":false}"#;
+        const READ_BACK: &str =
+            r#"{"name":"bug","color":"ededed","description":null,"url":"u","id":1,"node_id":"n","default":false}"#;
+        let runner = MockRunner::new(vec![mock::ok("[]"), mock::ok(CREATED), mock::ok(READ_BACK)]);
+        let client = GithubClient::with_runner("o", "r", runner);
+        let config: crate::config::LabelConfig =
+            "[[labels]]\nname = \"bug\"\ncolor = \"d73a49\"\n".parse().unwrap();
+        let options = ApplyOptions {
+            verify: true,
+            output: OutputFormat::Json,
+            ..Default::default()
+        };
+
+        let err = apply_config(&client, &config, &options).await.unwrap_err();
+        assert!(matches!(err, crate::error::BiaoError::Unverified(1)));
+        assert_eq!(err.exit_code(), crate::error::EXIT_FAILED_OPERATIONS);
+    }
+
+    #[tokio::test]
+    async fn test_locked_multi_repo_apply_makes_no_changes() {
+        let config: crate::config::LabelConfig =
+            "delete = [\"bug\"]\n[[labels]]\nname = \"feature\"\ncolor = \"a2eeef\"\n"
+                .parse()
+                .unwrap();
+        let options = ApplyOptions {
+            locked: true,
+            force: true,
+            label_jobs: 1,
+            ..Default::default()
+        };
+
+        for repo in ["a", "b"] {
+            let runner = MockRunner::new(vec![mock::ok(LABELS)]);
+            let calls = runner.calls.clone();
+            let client = GithubClient::with_runner("o", repo, runner);
+            let err = apply_to_repo(&client, &config, &options).await.unwrap_err();
+            assert!(err.to_string().contains("2 pending change(s)"));
+            // Only the label listing was requested
+            assert_eq!(
+                *calls.lock().unwrap(),
+                vec![format!("--include --method GET repos/o/{}/labels -F per_page=100 -F page=1", repo)]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_locked_apply_without_drift_succeeds() {
+        let runner = MockRunner::new(vec![mock::ok(LABELS)]);
+        let client = GithubClient::with_runner("o", "r", runner);
+        let config: crate::config::LabelConfig =
+            "[[labels]]\nname = \"bug\"\ncolor = \"d73a49\"\n".parse().unwrap();
+        let options = ApplyOptions {
+            locked: true,
+            output: OutputFormat::Json,
+            ..Default::default()
+        };
+
+        apply_config(&client, &config, &options).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_locked_apply_ignores_ignored_fields() {
+        let config: crate::config::LabelConfig =
+            "[[labels]]\nname = \"bug\"\ncolor = \"d73a49\"\ndescription = \"Something broke\"\nupdate_if_exists = true\n"
+                .parse()
+                .unwrap();
+        let mut options = ApplyOptions {
+            locked: true,
+            output: OutputFormat::Json,
+            ..Default::default()
+        };
+
+        let client = GithubClient::with_runner("o", "r", MockRunner::new(vec![mock::ok(LABELS)]));
+        let err = apply_config(&client, &config, &options).await.unwrap_err();
+        assert!(err.to_string().contains("(1 pending change(s): update 'bug')"));
+
+        options.ignore = vec![crate::plan::DiffField::Description];
+        let client = GithubClient::with_runner("o", "r", MockRunner::new(vec![mock::ok(LABELS)]));
+        apply_config(&client, &config, &options).await.unwrap();
+    }
+}
@@ -1,66 +1,947 @@
 use crate::error::{BiaoError, Result};
-use crate::models::{CreateLabelRequest, GithubLabel, UpdateLabelRequest};
-use std::process::Command;
+use crate::models::{CreateLabelRequest, GithubLabel, OrgRepository, RepoPermissions, UpdateLabelRequest};
+use crate::profile::{Phase, Profile};
+use colored::Colorize;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+/// Wait used when GitHub signals a secondary rate limit without `Retry-After`
+const DEFAULT_SECONDARY_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Retries made for a request that keeps failing with a retryable status
+const DEFAULT_MAX_RETRIES: u32 = 3;
+
+/// Page size used when listing an organization's repositories
+const REPOS_PER_PAGE: usize = 100;
+
+/// Page size used when listing a repository's labels
+const LABELS_PER_PAGE: usize = 100;
+
+/// Base delay between retries, doubled after each failure
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Captured result of one `gh` invocation
+#[derive(Debug, Default)]
+pub struct GhOutput {
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Runs `gh api` with the given arguments, feeding `input` to its stdin
+pub trait GhRunner: Send + Sync {
+    fn run(&self, args: &[&str], input: Option<&str>) -> std::io::Result<GhOutput>;
+}
+
+/// Runner spawning the real `gh` binary
+struct GhCommand;
+
+impl GhRunner for GhCommand {
+    fn run(&self, args: &[&str], input: Option<&str>) -> std::io::Result<GhOutput> {
+        let mut child = Command::new("gh")
+            .arg("api")
+            .args(args)
+            .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+            stdin.write_all(input.as_bytes())?;
+        }
+
+        let output = child.wait_with_output()?;
+        let stdout = String::from_utf8(output.stdout)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+        Ok(GhOutput {
+            success: output.status.success(),
+            stdout,
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        })
+    }
+}
+
+/// Runner calling the REST API with `curl` and a token from `GH_TOKEN` or
+/// `GITHUB_TOKEN`, for environments without gh.
+///
+/// It takes the same `gh api` arguments, and `curl --include` prints the
+/// response in the same shape, so the client can't tell the two apart.
+struct TokenRunner {
+    token: String,
+}
+
+impl TokenRunner {
+    /// Runner for the token in the environment, picked in gh's order
+    fn from_env() -> Option<Self> {
+        ["GH_TOKEN", "GITHUB_TOKEN"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|token| !token.is_empty())
+            .map(|token| TokenRunner { token })
+    }
+}
+
+impl GhRunner for TokenRunner {
+    fn run(&self, args: &[&str], input: Option<&str>) -> std::io::Result<GhOutput> {
+        run_curl(&curl_config(args, input, &self.token), "to use GH_TOKEN or GITHUB_TOKEN without gh")
+    }
+}
+
+/// Send the request described by a `curl --config`, returning `curl --include`
+/// output; `needed_for` explains the error when curl isn't installed
+pub(crate) fn run_curl(config: &str, needed_for: &str) -> std::io::Result<GhOutput> {
+    // The config, token included, goes through stdin to stay out of the process list
+    let mut child = Command::new("curl")
+        .args(["--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => std::io::Error::other(format!("curl is needed {}", needed_for)),
+            _ => e,
+        })?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(config.as_bytes())?;
+    }
+
+    let output = child.wait_with_output()?;
+    let stdout = String::from_utf8(output.stdout)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    let stdout = strip_interim_responses(&stdout).to_string();
+    // Unlike gh, curl exits successfully whatever the HTTP status
+    let ok_status = GhResponse::parse(&stdout).status.is_some_and(|s| (200..300).contains(&s));
+
+    Ok(GhOutput {
+        success: output.status.success() && ok_status,
+        stdout,
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+    })
+}
+
+/// `curl --config` for the request described by `gh api` arguments
+fn curl_config(args: &[&str], input: Option<&str>, token: &str) -> String {
+    let mut host = DEFAULT_HOST;
+    let mut method = None;
+    let mut path = "";
+    let mut fields = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--include" => {}
+            // The body is `input`
+            "--input" => {
+                args.next();
+            }
+            "--hostname" => host = args.next().copied().unwrap_or(host),
+            "--method" | "-X" => method = args.next().copied(),
+            "-f" | "-F" => {
+                if let Some((key, value)) = args.next().and_then(|field| field.split_once('=')) {
+                    let value = match serde_json::from_str::<serde_json::Value>(value) {
+                        Ok(typed) if *arg == "-F" && !typed.is_string() => typed,
+                        _ => serde_json::Value::String(value.to_string()),
+                    };
+                    fields.push((key, value));
+                }
+            }
+            other => path = other,
+        }
+    }
+    // gh's default: POST once there is something to send
+    let method = method.unwrap_or(if fields.is_empty() && input.is_none() { "GET" } else { "POST" });
+
+    let api = if host == DEFAULT_HOST {
+        "https://api.github.com".to_string()
+    } else {
+        format!("https://{}/api/v3", host)
+    };
+    // Label names in the path are already encoded, as gh needs them
+    let mut url = format!("{}/{}", api, path.trim_start_matches('/'));
+
+    let mut body = input.map(str::to_string);
+    if !fields.is_empty() {
+        if method == "GET" {
+            let query: Vec<String> = fields
+                .iter()
+                .map(|(key, value)| match value {
+                    serde_json::Value::String(s) => format!("{}={}", percent_encode(key), percent_encode(s)),
+                    typed => format!("{}={}", percent_encode(key), typed),
+                })
+                .collect();
+            url = format!("{}?{}", url, query.join("&"));
+        } else {
+            let object: serde_json::Map<String, serde_json::Value> =
+                fields.into_iter().map(|(key, value)| (key.to_string(), value)).collect();
+            body = Some(serde_json::Value::Object(object).to_string());
+        }
+    }
+
+    let mut config = vec![
+        "silent".to_string(),
+        "show-error".to_string(),
+        "include".to_string(),
+        format!("request = {}", curl_quote(method)),
+        format!("url = {}", curl_quote(&url)),
+        format!("header = {}", curl_quote(&format!("Authorization: Bearer {}", token))),
+        format!("header = {}", curl_quote("Accept: application/vnd.github+json")),
+        format!("header = {}", curl_quote("X-GitHub-Api-Version: 2022-11-28")),
+    ];
+    if let Some(body) = body {
+        config.push(format!("header = {}", curl_quote("Content-Type: application/json")));
+        config.push(format!("data-raw = {}", curl_quote(&body)));
+    }
+    config.join("\n") + "\n"
+}
+
+/// Double-quoted `curl --config` value
+pub(crate) fn curl_quote(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Prefixes of GitHub and GitLab tokens, e.g. `ghp_` for classic personal access tokens
+const TOKEN_PREFIXES: &[&str] = &["github_pat_", "ghp_", "gho_", "ghu_", "ghs_", "ghr_", "glpat-"];
+
+/// Headers carrying credentials, whose values are never echoed
+const SECRET_HEADERS: &[&str] = &["authorization", "private-token"];
+
+/// Echo an API call for `--verbose`, on stderr with tokens redacted
+pub(crate) fn log_call(command: &str, input: Option<&str>) {
+    eprintln!("{} {}", "$".dimmed(), redact(command));
+    if let Some(input) = input {
+        eprintln!("{}", redact(input).dimmed());
+    }
+}
+
+/// Echo a raw response for `--verbose`, on stderr with tokens redacted
+pub(crate) fn log_response(raw: &str, stderr: &str) {
+    eprintln!("{}", redact(raw.trim_end()).dimmed());
+    if !stderr.trim().is_empty() {
+        eprintln!("{}", redact(stderr.trim_end()).dimmed());
+    }
+}
+
+/// `text` with the tokens from the environment, anything shaped like a token
+/// and credential header values replaced by `[REDACTED]`
+fn redact(text: &str) -> String {
+    let secrets: Vec<String> = ["GH_TOKEN", "GITHUB_TOKEN", "GITLAB_TOKEN"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .filter(|token| !token.is_empty())
+        .collect();
+    redact_with(text, &secrets)
+}
+
+fn redact_with(text: &str, secrets: &[String]) -> String {
+    const REDACTED: &str = "[REDACTED]";
+
+    let mut text = secrets.iter().fold(text.to_string(), |text, secret| text.replace(secret.as_str(), REDACTED));
+    for prefix in TOKEN_PREFIXES {
+        let mut redacted = String::with_capacity(text.len());
+        let mut rest = text.as_str();
+        while let Some(start) = rest.find(prefix) {
+            let token = &rest[start..];
+            let end = token
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+                .unwrap_or(token.len());
+            redacted.push_str(&rest[..start]);
+            redacted.push_str(REDACTED);
+            rest = &token[end..];
+        }
+        redacted.push_str(rest);
+        text = redacted;
+    }
+
+    text.lines()
+        .map(|line| match line.split_once(':') {
+            Some((name, _)) if SECRET_HEADERS.contains(&name.trim().to_ascii_lowercase().as_str()) => {
+                format!("{}: {}", name, REDACTED)
+            }
+            _ => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Drop `100 Continue` and other informational responses curl prints before the final one
+fn strip_interim_responses(raw: &str) -> &str {
+    let mut raw = raw;
+    while raw.starts_with("HTTP/")
+        && raw.split_whitespace().nth(1).is_some_and(|status| status.starts_with('1'))
+    {
+        match raw.find("\r\n\r\n").or_else(|| raw.find("\n\n")) {
+            Some(end) => raw = raw[end..].trim_start_matches(['\r', '\n']),
+            None => break,
+        }
+    }
+    raw
+}
+
+/// HTTP response captured from `gh api --include`
+#[derive(Debug, Default)]
+pub(crate) struct GhResponse {
+    pub(crate) status: Option<u16>,
+    headers: Vec<(String, String)>,
+    pub(crate) body: String,
+}
+
+impl GhResponse {
+    /// Split `gh api --include` output into status line, headers and body
+    pub(crate) fn parse(raw: &str) -> Self {
+        if !raw.starts_with("HTTP/") {
+            return GhResponse {
+                body: raw.to_string(),
+                ..Default::default()
+            };
+        }
+
+        let raw = raw.replace("\r\n", "\n");
+        let (head, body) = raw.split_once("\n\n").unwrap_or((raw.as_str(), ""));
+        let mut lines = head.lines();
+        let status = lines
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .and_then(|code| code.parse().ok());
+        let headers = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+            .collect();
+
+        GhResponse {
+            status,
+            headers,
+            body: body.to_string(),
+        }
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// First `errors[].code` of a GitHub validation error body, e.g. `already_exists`
+    fn error_code(&self) -> Option<String> {
+        let body: serde_json::Value = serde_json::from_str(&self.body).ok()?;
+        body.get("errors")?
+            .as_array()?
+            .iter()
+            .find_map(|error| error.get("code")?.as_str())
+            .map(str::to_string)
+    }
+
+    /// HTTP status from the response, or from gh's `(HTTP nnn)` error suffix
+    fn http_status(&self, stderr: &str) -> Option<u16> {
+        self.status.or_else(|| {
+            let (_, rest) = stderr.split_once("(HTTP ")?;
+            rest.get(..3)?.parse().ok()
+        })
+    }
+
+    fn is_secondary_rate_limit(&self, stderr: &str) -> bool {
+        matches!(self.status, Some(403) | Some(429) | None)
+            && (self.body.contains("secondary rate limit") || stderr.contains("secondary rate limit"))
+    }
+}
+
+/// Page number of the `rel="last"` entry of a `Link` header
+fn last_page(link: &str) -> Option<u64> {
+    let last = link.split(',').find(|entry| entry.contains("rel=\"last\""))?;
+    let url = last.split(['<', '>']).nth(1)?;
+    let query = url.split_once('?')?.1;
+    query.split('&').find_map(|param| param.strip_prefix("page="))?.parse().ok()
+}
+
+/// Parse a `Retry-After` header given in seconds
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    value.trim().parse::<u64>().ok().map(Duration::from_secs)
+}
+
+/// API path of a gh request: the first argument that isn't a flag or a flag value
+fn request_path<'a>(args: &[&'a str]) -> &'a str {
+    args.iter()
+        .find(|a| !a.starts_with('-') && a.contains('/'))
+        .copied()
+        .unwrap_or("")
+}
+
+/// Turn a failed gh call into the most specific error.
+///
+/// `path` is the requested API path (`repos/o/r/labels/bug`) and `input` the
+/// JSON request body, used to name the missing or conflicting resource.
+fn classify_failure(response: &GhResponse, stderr: String, path: &str, input: Option<&str>) -> BiaoError {
+    if response.is_secondary_rate_limit(&stderr) {
+        let retry_after = response
+            .header("Retry-After")
+            .and_then(parse_retry_after)
+            .unwrap_or(DEFAULT_SECONDARY_RETRY_AFTER);
+        return BiaoError::SecondaryRateLimited { retry_after };
+    }
+
+    let status = response.http_status(&stderr);
+    let segments: Vec<&str> = path.splitn(5, '/').collect();
+    let label = match segments.as_slice() {
+        ["repos", _, _, "labels", name] => Some(percent_decode(name)),
+        _ => None,
+    };
+
+    match status {
+        Some(401) => BiaoError::Unauthorized,
+        Some(403) | Some(429) if response.header("X-RateLimit-Remaining") == Some("0") => BiaoError::RateLimited {
+            reset_at: response.header("X-RateLimit-Reset").and_then(|t| t.parse().ok()),
+        },
+        Some(404) => match (label, segments.as_slice()) {
+            (Some(name), _) => BiaoError::NotFound {
+                resource: format!("Label '{}'", name),
+            },
+            (None, ["repos", owner, repo, ..]) => BiaoError::RepoNotFound {
+                owner: owner.to_string(),
+                repo: repo.to_string(),
+            },
+            _ => BiaoError::NotFound {
+                resource: path.to_string(),
+            },
+        },
+        Some(422) if response.error_code().as_deref() == Some("already_exists") => {
+            let requested = input
+                .and_then(|body| serde_json::from_str::<serde_json::Value>(body).ok())
+                .and_then(|body| body.get("name")?.as_str().map(str::to_string));
+            BiaoError::AlreadyExists {
+                name: requested.or(label).unwrap_or_default(),
+            }
+        }
+        _ => BiaoError::GhError {
+            status,
+            code: response.error_code(),
+            message: stderr,
+        },
+    }
+}
+
+/// Which failed requests are retried, and how often (`--retry-on`, `--max-retries`,
+/// `--retry-on-secondary-only`, `--no-wait`)
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// HTTP statuses worth retrying; `None` means any 5xx
+    statuses: Option<HashSet<u16>>,
+    /// Retry rate limits only, ignoring `statuses`
+    rate_limits_only: bool,
+    max_retries: u32,
+    /// Wait for rate limits to reset instead of failing
+    wait: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            statuses: None,
+            rate_limits_only: false,
+            max_retries: DEFAULT_MAX_RETRIES,
+            wait: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy from the CLI; an empty `statuses` keeps the transient (5xx) default
+    pub fn new(statuses: &[u16], max_retries: Option<u32>) -> Self {
+        Self {
+            statuses: (!statuses.is_empty()).then(|| statuses.iter().copied().collect()),
+            rate_limits_only: false,
+            max_retries: max_retries.unwrap_or(DEFAULT_MAX_RETRIES),
+            wait: true,
+        }
+    }
+
+    /// Policy retrying only (secondary) rate limits, so server errors fail fast
+    pub fn rate_limits_only(max_retries: Option<u32>) -> Self {
+        Self {
+            statuses: None,
+            rate_limits_only: true,
+            max_retries: max_retries.unwrap_or(DEFAULT_MAX_RETRIES),
+            wait: true,
+        }
+    }
+
+    /// The same policy, but failing on rate limits instead of waiting for them
+    pub fn without_waiting(self) -> Self {
+        Self { wait: false, ..self }
+    }
+
+    /// How long to wait before retrying a rate-limited request, or `None` to fail.
+    ///
+    /// Secondary limits say how long to wait with `Retry-After`; the primary
+    /// limit is waited out until `X-RateLimit-Reset`.
+    pub fn rate_limit_wait(&self, error: &BiaoError, retries: u32) -> Option<Duration> {
+        if !self.wait || retries >= self.max_retries {
+            return None;
+        }
+        match error {
+            BiaoError::SecondaryRateLimited { retry_after } => Some(*retry_after),
+            BiaoError::RateLimited { reset_at } => Some(match reset_at {
+                Some(reset_at) => {
+                    let now = std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs();
+                    // A second past the reset, so the next request lands after it
+                    Duration::from_secs(reset_at.saturating_sub(now) + 1)
+                }
+                None => DEFAULT_SECONDARY_RETRY_AFTER,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Whether a request that failed with `error` after `retries` retries should be sent again.
+    ///
+    /// Besides the retried statuses, requests that got no response at all are retried.
+    pub fn should_retry(&self, error: &BiaoError, retries: u32) -> bool {
+        if self.rate_limits_only {
+            let rate_limited = matches!(
+                error,
+                BiaoError::SecondaryRateLimited { .. } | BiaoError::RateLimited { .. }
+            );
+            return rate_limited && self.wait && retries < self.max_retries;
+        }
+        if error.is_connection_error() {
+            return retries < self.max_retries;
+        }
+
+        let Some(status) = error.status() else {
+            return false;
+        };
+        let retryable = match &self.statuses {
+            Some(statuses) => statuses.contains(&status),
+            None => (500..=599).contains(&status),
+        };
+        retryable && retries < self.max_retries
+    }
+}
+
+/// Value of a request field; strings are sent verbatim, everything else typed
+// Not every variant is used by the label endpoints yet
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    String(String),
+    Bool(bool),
+    Number(i64),
+    Null,
+}
+
+/// A `key=value` request field passed to `gh api`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub key: String,
+    pub value: FieldValue,
+}
+
+impl Field {
+    pub fn new(key: &str, value: FieldValue) -> Self {
+        Field {
+            key: key.to_string(),
+            value,
+        }
+    }
+
+    /// gh arguments for this field: `-f` keeps strings as-is, `-F` converts
+    /// `true`, `false`, `null` and numbers to JSON types
+    fn to_args(&self) -> [String; 2] {
+        match &self.value {
+            FieldValue::String(s) => ["-f".to_string(), format!("{}={}", self.key, s)],
+            FieldValue::Bool(b) => ["-F".to_string(), format!("{}={}", self.key, b)],
+            FieldValue::Number(n) => ["-F".to_string(), format!("{}={}", self.key, n)],
+            FieldValue::Null => ["-F".to_string(), format!("{}=null", self.key)],
+        }
+    }
+}
+
+/// Sleep until a rate limit resets, telling the user why nothing is happening
+pub async fn wait_for_rate_limit(delay: Duration) {
+    if !crate::output::is_quiet() {
+        eprintln!("{}", format!("Rate limited, waiting {}s…", delay.as_secs_f64().ceil()).dimmed());
+    }
+    tokio::time::sleep(delay).await;
+}
+
+/// Host used when `GH_HOST` is not set
+pub const DEFAULT_HOST: &str = "github.com";
+
+/// The host gh is configured for: `GH_HOST`, default github.com
+pub fn configured_host() -> String {
+    std::env::var("GH_HOST")
+        .ok()
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| DEFAULT_HOST.to_string())
+}
+
+/// Web page of a repository on `host`
+pub fn repo_web_url(host: &str, owner: &str, repo: &str) -> String {
+    format!("https://{}/{}/{}", host, owner, repo)
+}
+
+/// Web page listing the issues and pull requests carrying label `name`
+pub fn label_web_url(host: &str, owner: &str, repo: &str, name: &str) -> String {
+    format!("{}/labels/{}", repo_web_url(host, owner, repo), percent_encode(name))
+}
+
+/// Issue search for label `name`, e.g. `.../issues?q=label:"good first issue"`
+pub fn issues_web_url(host: &str, owner: &str, repo: &str, name: &str) -> String {
+    format!(
+        "{}/issues?q={}",
+        repo_web_url(host, owner, repo),
+        percent_encode(&format!("label:\"{}\"", name))
+    )
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters
+pub(crate) fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Undo [`percent_encode`]
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| value.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// A call to the label API, as `apply` sends it and `--dump-requests` prints it
+#[derive(Debug)]
+pub enum LabelRequest {
+    Create(CreateLabelRequest),
+    /// Update (or rename) the label currently called `name`
+    Update { name: String, body: UpdateLabelRequest },
+    Delete { name: String },
+}
+
+/// Serialize a request body the way it is sent to `gh api --input -`
+fn json_body<T: Serialize>(body: &T) -> Result<String> {
+    serde_json::to_string(body).map_err(|e| BiaoError::ParseError {
+        message: format!("Failed to serialize request: {}", e),
+    })
+}
+
+/// Talk to the API directly when a token is set, otherwise through gh
+fn default_runner() -> Arc<dyn GhRunner> {
+    match TokenRunner::from_env() {
+        Some(runner) => Arc::new(runner),
+        None => Arc::new(GhCommand),
+    }
+}
+
+/// Clones share the runner and the profile, so concurrent tasks can each own one
+#[derive(Clone)]
 pub struct GithubClient {
+    host: String,
     owner: String,
     repo: String,
+    runner: Arc<dyn GhRunner>,
+    retry: RetryPolicy,
+    retry_backoff: Duration,
+    profile: Option<Arc<Profile>>,
 }
 
 impl GithubClient {
+    /// Client for `owner/repo` on the host gh is configured for (`GH_HOST`, default github.com)
     pub fn new(owner: String, repo: String) -> Self {
-        Self { owner, repo }
+        Self {
+            host: configured_host(),
+            owner,
+            repo,
+            runner: default_runner(),
+            retry: RetryPolicy::default(),
+            retry_backoff: RETRY_BACKOFF,
+            profile: None,
+        }
+    }
+
+    /// Client driven by a custom runner, without retry delays
+    #[cfg(test)]
+    pub(crate) fn with_runner(owner: &str, repo: &str, runner: impl GhRunner + 'static) -> Self {
+        Self {
+            host: DEFAULT_HOST.to_string(),
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            runner: Arc::new(runner),
+            retry: RetryPolicy::default(),
+            retry_backoff: Duration::ZERO,
+            profile: None,
+        }
+    }
+
+    /// Talk to `host`, e.g. a GitHub Enterprise server the remote points at
+    pub fn set_host(&mut self, host: String) {
+        self.host = host;
+    }
+
+    pub fn set_retry_policy(&mut self, retry: RetryPolicy) {
+        self.retry = retry;
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry
+    }
+
+    /// Delay before retry number `retries + 1`
+    pub fn retry_delay(&self, retries: u32) -> Duration {
+        self.retry_backoff * 2u32.pow(retries)
+    }
+
+    /// Record the duration of every gh call from now on
+    pub fn enable_profiling(&mut self) {
+        self.profile = Some(Arc::new(Profile::default()));
+    }
+
+    /// Timings recorded since profiling was enabled
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_deref()
     }
 
     pub fn repo_url(&self) -> String {
         format!("{}/{}", self.owner, self.repo)
     }
 
+    /// Web page listing the repository's labels
+    pub fn labels_web_url(&self) -> String {
+        format!("{}/labels", repo_web_url(&self.host, &self.owner, &self.repo))
+    }
+
+    /// Web page of a label in the repository
+    pub fn label_web_url(&self, name: &str) -> String {
+        label_web_url(&self.host, &self.owner, &self.repo, name)
+    }
+
+    /// Issue search for a label in the repository
+    pub fn issues_web_url(&self, name: &str) -> String {
+        issues_web_url(&self.host, &self.owner, &self.repo, name)
+    }
+
     fn run_gh(&self, args: &[&str]) -> Result<String> {
-        let mut cmd = Command::new("gh");
-        cmd.args(["api"]);
-        cmd.args(args);
+        self.run_gh_with_input(args, None)
+    }
+
+    /// Request `path` with `method`, passing `fields` as typed gh fields
+    /// (query parameters for `GET`, the JSON body otherwise)
+    fn request(&self, method: &str, path: &str, fields: &[Field]) -> Result<String> {
+        self.request_response(method, path, fields)
+            .map(|response| response.body.trim().to_string())
+    }
+
+    /// `request`, keeping the response headers
+    fn request_response(&self, method: &str, path: &str, fields: &[Field]) -> Result<GhResponse> {
+        let field_args: Vec<String> = fields.iter().flat_map(Field::to_args).collect();
+        let mut args = vec!["--method", method, path];
+        args.extend(field_args.iter().map(String::as_str));
+        self.run_gh_response(&args, None)
+    }
+
+    /// Send `body` as the JSON request body via `--input -`, so values are
+    /// transmitted verbatim instead of being parsed as `key=value` fields
+    fn send_json<T: Serialize>(&self, method: &str, path: &str, body: &T) -> Result<String> {
+        let input = json_body(body)?;
+        self.run_gh_with_input(&[path, "-X", method, "--input", "-"], Some(&input))
+    }
 
-        let output = cmd.output().map_err(|e| {
+    fn run_gh_with_input(&self, args: &[&str], input: Option<&str>) -> Result<String> {
+        self.run_gh_response(args, input)
+            .map(|response| response.body.trim().to_string())
+    }
+
+    fn run_gh_response(&self, args: &[&str], input: Option<&str>) -> Result<GhResponse> {
+        let mut full_args = vec!["--include"];
+        // gh picks github.com (or GH_HOST) by itself
+        if self.host != DEFAULT_HOST {
+            full_args.extend(["--hostname", self.host.as_str()]);
+        }
+        full_args.extend_from_slice(args);
+
+        let path = request_path(args);
+        let verbose = crate::output::is_verbose();
+        if verbose {
+            let command: Vec<String> = full_args
+                .iter()
+                .map(|arg| if arg.contains(' ') { format!("{:?}", arg) } else { arg.to_string() })
+                .collect();
+            log_call(&format!("gh api {}", command.join(" ")), input);
+        }
+        let started = Instant::now();
+        let output = self.runner.run(&full_args, input);
+        if let Some(profile) = &self.profile {
+            let method = args
+                .iter()
+                .position(|a| *a == "--method" || *a == "-X")
+                .and_then(|i| args.get(i + 1))
+                .copied()
+                .unwrap_or("GET");
+            profile.record(Phase::from_method(method), format!("{} {}", method, path), started.elapsed());
+        }
+
+        let output = output.map_err(|e| {
             if e.kind() == std::io::ErrorKind::NotFound {
                 BiaoError::GhNotFound {
                     message: "github.com/cli/cli".to_string(),
                 }
             } else {
                 BiaoError::GhError {
-                    message: format!("Failed to execute gh: {}", e),
+                    message: format!("Failed to send request: {}", e),
+                    status: None,
+                    code: None,
                 }
             }
         })?;
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-            return Err(BiaoError::GhError { message: stderr });
+        if verbose {
+            log_response(&output.stdout, &output.stderr);
         }
+        let response = GhResponse::parse(&output.stdout);
 
-        Ok(String::from_utf8(output.stdout)
-            .map_err(|e| BiaoError::GhError {
-                message: format!("Invalid UTF-8 from gh: {}", e),
-            })?
-            .trim()
-            .to_string())
+        if !output.success {
+            return Err(classify_failure(&response, output.stderr, path, input));
+        }
+
+        Ok(response)
     }
 
+    /// Send an idempotent request until it succeeds or the retry policy gives up.
+    ///
+    /// Rate limits are waited out up to the policy's retry count; other
+    /// failures fail fast unless the policy retries them.
+    async fn retrying<T>(&self, send: impl Fn() -> Result<T>) -> Result<T> {
+        let mut retries = 0;
+        loop {
+            let error = match send() {
+                Ok(output) => return Ok(output),
+                Err(e) => e,
+            };
+            match self.retry.rate_limit_wait(&error, retries) {
+                Some(delay) => wait_for_rate_limit(delay).await,
+                None if self.retry.should_retry(&error, retries) => {
+                    tokio::time::sleep(self.retry_delay(retries)).await;
+                }
+                None => return Err(error),
+            }
+            retries += 1;
+        }
+    }
+
+    /// All repositories of an organization, fetched page by page, retrying failures
+    pub async fn list_org_repos(&self, org: &str) -> Result<Vec<OrgRepository>> {
+        let path = format!("orgs/{}/repos", org);
+        let mut repos = Vec::new();
+        for page in 1.. {
+            let fields = [
+                Field::new("per_page", FieldValue::Number(REPOS_PER_PAGE as i64)),
+                Field::new("page", FieldValue::Number(page)),
+            ];
+            let output = self.retrying(|| self.request("GET", &path, &fields)).await?;
+
+            let batch: Vec<OrgRepository> = serde_json::from_str(&output).map_err(|e| BiaoError::ParseError {
+                message: format!("Failed to parse repositories: {}", e),
+            })?;
+            let last = batch.len() < REPOS_PER_PAGE;
+            repos.extend(batch);
+            if last {
+                break;
+            }
+        }
+        Ok(repos)
+    }
+
+    /// The authenticated user's permissions on the repository
+    pub async fn repo_permissions(&self) -> Result<RepoPermissions> {
+        #[derive(serde::Deserialize)]
+        struct Repository {
+            #[serde(default)]
+            permissions: RepoPermissions,
+        }
+
+        let path = format!("repos/{}/{}", self.owner, self.repo);
+        let output = self.run_gh(&[&path])?;
+        let repository: Repository = serde_json::from_str(&output).map_err(|e| BiaoError::ParseError {
+            message: format!("Failed to parse repository: {}", e),
+        })?;
+        Ok(repository.permissions)
+    }
+
+    /// All labels of the repository, fetched page by page until a short page, retrying failures
     pub async fn list_labels(&self) -> Result<Vec<GithubLabel>> {
         let path = format!("repos/{}/{}/labels", self.owner, self.repo);
-        let output = self.run_gh(&[&path])?;
-        
-        let labels: Vec<GithubLabel> =
-            serde_json::from_str(&output).map_err(|e| BiaoError::ParseError {
-                message: format!("Failed to parse labels: {}", e),
-            })?;
+        let mut labels = Vec::new();
+        for page in 1.. {
+            let fields = [
+                Field::new("per_page", FieldValue::Number(LABELS_PER_PAGE as i64)),
+                Field::new("page", FieldValue::Number(page)),
+            ];
+            let output = self.retrying(|| self.request("GET", &path, &fields)).await?;
+
+            let batch: Vec<GithubLabel> =
+                serde_json::from_str(&output).map_err(|e| BiaoError::ParseError {
+                    message: format!("Failed to parse labels: {}", e),
+                })?;
+            let last = batch.len() < LABELS_PER_PAGE;
+            labels.extend(batch);
+            if last {
+                break;
+            }
+        }
 
         Ok(labels)
     }
 
+    /// API path of label `name`; gh sends the path as given, so slashes and
+    /// spaces in the name are encoded here
+    fn label_path(&self, name: &str) -> String {
+        format!("repos/{}/{}/labels/{}", self.owner, self.repo, percent_encode(name))
+    }
+
     pub async fn get_label(&self, name: &str) -> Result<GithubLabel> {
-        let path = format!("repos/{}/{}/labels/{}", self.owner, self.repo, name);
-        let output = self.run_gh(&[&path])?;
+        let path = self.label_path(name);
+        let output = self.retrying(|| self.run_gh(&[&path])).await?;
         
         let label: GithubLabel =
             serde_json::from_str(&output).map_err(|e| BiaoError::ParseError {
@@ -70,26 +951,60 @@ impl GithubClient {
         Ok(label)
     }
 
-    pub async fn create_label(&self, label: &CreateLabelRequest) -> Result<GithubLabel> {
-        let path = format!("repos/{}/{}/labels", self.owner, self.repo);
-        
-        let name_arg = format!("name={}", label.name);
-        let color_arg = format!("color={}", label.color);
-        
-        let mut args = vec![
-            path.as_str(),
-            "-f", &name_arg,
-            "-f", &color_arg,
+    /// Issues and pull requests carrying label `name` in `state` (`open`,
+    /// `closed` or `all`), counted from the last page of a one-per-page
+    /// listing, retrying failures. `labels=` takes a comma-separated list, so
+    /// names containing a comma can't be counted.
+    pub async fn count_labeled_issues(&self, name: &str, state: &str) -> Result<u64> {
+        if name.contains(',') {
+            return Err(BiaoError::InvalidInput(format!(
+                "Can't count issues labeled '{}': GitHub reads the comma as a separator",
+                name
+            )));
+        }
+        let path = format!("repos/{}/{}/issues", self.owner, self.repo);
+        let fields = [
+            Field::new("labels", FieldValue::String(name.to_string())),
+            Field::new("state", FieldValue::String(state.to_string())),
+            Field::new("per_page", FieldValue::Number(1)),
         ];
+        let response = self.retrying(|| self.request_response("GET", &path, &fields)).await?;
+
+        if let Some(count) = response.header("link").and_then(last_page) {
+            return Ok(count);
+        }
+        // A single page: no issues, or just one
+        let issues: Vec<serde_json::Value> =
+            serde_json::from_str(&response.body).map_err(|e| BiaoError::ParseError {
+                message: format!("Failed to parse issues: {}", e),
+            })?;
+        Ok(issues.len() as u64)
+    }
 
-        let desc_arg;
-        if let Some(desc) = &label.description {
-            desc_arg = format!("description={}", desc);
-            args.push("-f");
-            args.push(&desc_arg);
+    /// Send a label request
+    pub async fn send(&self, request: &LabelRequest) -> Result<()> {
+        match request {
+            LabelRequest::Create(body) => self.create_label(body).await.map(drop),
+            LabelRequest::Update { name, body } => self.update_label(name, body).await.map(drop),
+            LabelRequest::Delete { name } => self.delete_label(name).await,
         }
+    }
+
+    /// Method, path and JSON body of a label request, exactly as it would be sent
+    pub fn dump_request(&self, request: &LabelRequest) -> Result<String> {
+        let labels = format!("repos/{}/{}/labels", self.owner, self.repo);
+        Ok(match request {
+            LabelRequest::Create(body) => format!("POST {}\n{}\n", labels, json_body(body)?),
+            LabelRequest::Update { name, body } => {
+                format!("PATCH {}\n{}\n", self.label_path(name), json_body(body)?)
+            }
+            LabelRequest::Delete { name } => format!("DELETE {}\n", self.label_path(name)),
+        })
+    }
 
-        let output = self.run_gh(&args)?;
+    pub async fn create_label(&self, label: &CreateLabelRequest) -> Result<GithubLabel> {
+        let path = format!("repos/{}/{}/labels", self.owner, self.repo);
+        let output = self.send_json("POST", &path, label)?;
         
         let created: GithubLabel =
             serde_json::from_str(&output).map_err(|e| BiaoError::ParseError {
@@ -104,41 +1019,709 @@ impl GithubClient {
         name: &str,
         label: &UpdateLabelRequest,
     ) -> Result<GithubLabel> {
-        let path = format!("repos/{}/{}/labels/{}", self.owner, self.repo, name);
+        let path = self.label_path(name);
+        let output = self.send_json("PATCH", &path, label)?;
         
-        let mut args: Vec<&str> = vec![path.as_str(), "-X", "PATCH"];
-        let mut arg_storage: Vec<String> = Vec::new();
+        let updated: GithubLabel =
+            serde_json::from_str(&output).map_err(|e| BiaoError::ParseError {
+                message: format!("Failed to parse updated label: {}", e),
+            })?;
 
-        if let Some(new_name) = &label.name {
-            arg_storage.push(format!("name={}", new_name));
+        Ok(updated)
+    }
+
+    /// Delete a label, retrying transient failures.
+    ///
+    /// Deleting is idempotent, so a 404 means an earlier attempt or run
+    /// already removed the label and counts as success.
+    pub async fn delete_label(&self, name: &str) -> Result<()> {
+        let path = self.label_path(name);
+        match self.retrying(|| self.run_gh(&[&path, "-X", "DELETE"])).await {
+            Ok(_) | Err(BiaoError::NotFound { .. }) => Ok(()),
+            Err(e) => Err(e),
         }
+    }
+}
 
-        if let Some(color) = &label.color {
-            arg_storage.push(format!("color={}", color));
+/// Scripted `gh` runner for tests
+#[cfg(test)]
+pub(crate) mod mock {
+    use super::{GhOutput, GhRunner};
+    use std::sync::{Arc, Mutex};
+
+    /// Runner replaying canned outputs and recording the calls it receives
+    pub(crate) struct MockRunner {
+        responses: Mutex<Vec<GhOutput>>,
+        pub calls: Arc<Mutex<Vec<String>>>,
+        pub inputs: Arc<Mutex<Vec<Option<String>>>>,
+    }
+
+    impl MockRunner {
+        pub fn new(responses: Vec<GhOutput>) -> Self {
+            Self {
+                responses: Mutex::new(responses.into_iter().rev().collect()),
+                calls: Default::default(),
+                inputs: Default::default(),
+            }
         }
+    }
 
-        if let Some(desc) = &label.description {
-            arg_storage.push(format!("description={}", desc));
+    impl GhRunner for MockRunner {
+        fn run(&self, args: &[&str], input: Option<&str>) -> std::io::Result<GhOutput> {
+            self.calls.lock().unwrap().push(args.join(" "));
+            self.inputs.lock().unwrap().push(input.map(|i| i.to_string()));
+            Ok(self.responses.lock().unwrap().pop().expect("unexpected gh call"))
         }
+    }
 
-        for arg in &arg_storage {
-            args.push("-f");
-            args.push(arg);
+    /// Successful `gh api --include` output with a JSON body
+    pub fn ok(body: &str) -> GhOutput {
+        GhOutput {
+            success: true,
+            stdout: format!("HTTP/2.0 200 OK\r\n\r\n{}", body),
+            stderr: String::new(),
         }
+    }
+}
 
-        let output = self.run_gh(&args)?;
-        
-        let updated: GithubLabel =
-            serde_json::from_str(&output).map_err(|e| BiaoError::ParseError {
-                message: format!("Failed to parse updated label: {}", e),
-            })?;
+#[cfg(test)]
+mod tests {
+    use super::mock::MockRunner;
+    use super::*;
 
-        Ok(updated)
+    #[test]
+    fn test_redact_tokens() {
+        let secrets = vec!["s3cr3t-value".to_string()];
+        let text = "Authorization: Bearer abc\nPRIVATE-TOKEN: xyz\nbody {\"t\": \"ghp_AbC123\"} glpat-x_y-z.\ntoken=s3cr3t-value";
+        let redacted = redact_with(text, &secrets);
+        assert_eq!(
+            redacted,
+            "Authorization: [REDACTED]\nPRIVATE-TOKEN: [REDACTED]\nbody {\"t\": \"[REDACTED]\"} [REDACTED].\ntoken=[REDACTED]"
+        );
+        assert_eq!(redact_with("gh api repos/o/r/labels", &[]), "gh api repos/o/r/labels");
     }
 
-    pub async fn delete_label(&self, name: &str) -> Result<()> {
-        let path = format!("repos/{}/{}/labels/{}", self.owner, self.repo, name);
-        self.run_gh(&[&path, "-X", "DELETE"])?;
-        Ok(())
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after("37"), Some(Duration::from_secs(37)));
+        assert_eq!(parse_retry_after(" 5 "), Some(Duration::from_secs(5)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), None);
+    }
+
+    #[tokio::test]
+    async fn test_count_labeled_issues() {
+        let link = "HTTP/2.0 200 OK\r\nLink: <https://api.github.com/repositories/1/issues?labels=bug&state=all&per_page=1&page=2>; rel=\"next\", \
+                    <https://api.github.com/repositories/1/issues?labels=bug&state=all&per_page=1&page=57>; rel=\"last\"\r\n\r\n[{}]";
+        let runner = MockRunner::new(vec![
+            GhOutput {
+                success: true,
+                stdout: link.to_string(),
+                stderr: String::new(),
+            },
+            super::mock::ok("[]"),
+        ]);
+        let calls = runner.calls.clone();
+        let client = GithubClient::with_runner("o", "r", runner);
+
+        assert_eq!(client.count_labeled_issues("bug", "all").await.unwrap(), 57);
+        assert_eq!(
+            calls.lock().unwrap()[0],
+            "--include --method GET repos/o/r/issues -f labels=bug -f state=all -F per_page=1"
+        );
+        assert_eq!(client.count_labeled_issues("question", "open").await.unwrap(), 0);
+        assert!(client.count_labeled_issues("needs info, triage", "open").await.is_err());
+    }
+
+    #[test]
+    fn test_parse_include_output() {
+        let raw = "HTTP/2.0 403 Forbidden\r\nContent-Type: application/json\r\nRetry-After: 30\r\n\r\n{\"message\":\"You have exceeded a secondary rate limit\"}";
+        let response = GhResponse::parse(raw);
+        assert_eq!(response.status, Some(403));
+        assert_eq!(response.header("retry-after").and_then(parse_retry_after), Some(Duration::from_secs(30)));
+        assert!(response.body.starts_with('{'));
+        assert!(response.is_secondary_rate_limit(""));
+    }
+
+    fn failure(status: u16) -> GhOutput {
+        GhOutput {
+            success: false,
+            stdout: format!("HTTP/2.0 {} Error\r\n\r\n{{\"message\":\"error\"}}", status),
+            stderr: format!("gh: error (HTTP {})", status),
+        }
+    }
+
+    /// A 422 for a duplicate label, as printed by `gh api --include`
+    fn already_exists() -> GhOutput {
+        GhOutput {
+            success: false,
+            stdout: "HTTP/2.0 422 Unprocessable Entity\r\nContent-Type: application/json\r\n\r\n\
+                     {\"message\":\"Validation Failed\",\"errors\":[{\"resource\":\"Label\",\
+                     \"code\":\"already_exists\",\"field\":\"name\"}]}"
+                .to_string(),
+            stderr: "gh: Validation Failed (HTTP 422)\nLabel.name already exists".to_string(),
+        }
+    }
+
+    fn no_content() -> GhOutput {
+        GhOutput {
+            success: true,
+            stdout: "HTTP/2.0 204 No Content\r\n\r\n".to_string(),
+            stderr: String::new(),
+        }
+    }
+
+    fn label_response(name: &str) -> GhOutput {
+        super::mock::ok(&format!(
+            r#"{{"name":"{}","color":"d73a49","description":null,"url":"u","id":1,"node_id":"n","default":false}}"#,
+            name
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_create_sends_json_body() {
+        let runner = MockRunner::new(vec![label_response("bug")]);
+        let (calls, inputs) = (runner.calls.clone(), runner.inputs.clone());
+        let client = GithubClient::with_runner("o", "r", runner);
+
+        let description = "a=b\nsecond line ✓";
+        client
+            .create_label(&CreateLabelRequest {
+                name: "bug".to_string(),
+                color: "d73a49".to_string(),
+                description: Some(description.to_string()),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(*calls.lock().unwrap(), vec!["--include repos/o/r/labels -X POST --input -"]);
+        let input = inputs.lock().unwrap()[0].clone().unwrap();
+        let body: serde_json::Value = serde_json::from_str(&input).unwrap();
+        assert_eq!(body["name"], "bug");
+        assert_eq!(body["description"], description);
+    }
+
+    #[tokio::test]
+    async fn test_update_sends_only_given_fields() {
+        let runner = MockRunner::new(vec![label_response("defect")]);
+        let (calls, inputs) = (runner.calls.clone(), runner.inputs.clone());
+        let client = GithubClient::with_runner("o", "r", runner);
+
+        client
+            .update_label(
+                "bug",
+                &UpdateLabelRequest {
+                    name: Some("defect".to_string()),
+                    color: None,
+                    description: Some("x = y\nz".to_string()),
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(*calls.lock().unwrap(), vec!["--include repos/o/r/labels/bug -X PATCH --input -"]);
+        let input = inputs.lock().unwrap()[0].clone().unwrap();
+        assert_eq!(input, r#"{"name":"defect","description":"x = y\nz"}"#);
+    }
+
+    #[tokio::test]
+    async fn test_delete_missing_label_is_success() {
+        let runner = MockRunner::new(vec![failure(404)]);
+        let calls = runner.calls.clone();
+        let client = GithubClient::with_runner("o", "r", runner);
+
+        client.delete_label("gone").await.unwrap();
+        assert_eq!(*calls.lock().unwrap(), vec!["--include repos/o/r/labels/gone -X DELETE"]);
+    }
+
+    #[tokio::test]
+    async fn test_label_names_are_encoded_in_paths() {
+        let runner = MockRunner::new(vec![mock::ok(""), failure(404)]);
+        let calls = runner.calls.clone();
+        let client = GithubClient::with_runner("o", "r", runner);
+
+        client.delete_label("area/api").await.unwrap();
+        assert_eq!(*calls.lock().unwrap(), vec!["--include repos/o/r/labels/area%2Fapi -X DELETE"]);
+
+        let err = client.get_label("good first issue").await.unwrap_err();
+        assert_eq!(calls.lock().unwrap()[1], "--include repos/o/r/labels/good%20first%20issue");
+        assert!(err.to_string().contains("'good first issue'"));
+    }
+
+    #[tokio::test]
+    async fn test_enterprise_host_is_passed_to_gh() {
+        let runner = MockRunner::new(vec![no_content()]);
+        let calls = runner.calls.clone();
+        let mut client = GithubClient::with_runner("o", "r", runner);
+        client.set_host("github.acme.com".to_string());
+
+        client.delete_label("bug").await.unwrap();
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec!["--include --hostname github.acme.com repos/o/r/labels/bug -X DELETE"]
+        );
+        assert_eq!(client.labels_web_url(), "https://github.acme.com/o/r/labels");
+    }
+
+    #[tokio::test]
+    async fn test_delete_retries_transient_errors() {
+        let runner = MockRunner::new(vec![failure(502), failure(503), no_content()]);
+        let calls = runner.calls.clone();
+        let client = GithubClient::with_runner("o", "r", runner);
+
+        client.delete_label("bug").await.unwrap();
+        assert_eq!(calls.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_delete_gives_up_after_attempts() {
+        let runner = MockRunner::new((0..4).map(|_| failure(500)).collect());
+        let client = GithubClient::with_runner("o", "r", runner);
+
+        let err = client.delete_label("bug").await.unwrap_err();
+        assert!(matches!(err, BiaoError::GhError { status: Some(500), .. }));
+    }
+
+    #[tokio::test]
+    async fn test_delete_does_not_retry_client_errors() {
+        let runner = MockRunner::new(vec![failure(403)]);
+        let calls = runner.calls.clone();
+        let client = GithubClient::with_runner("o", "r", runner);
+
+        assert!(client.delete_label("bug").await.is_err());
+        assert_eq!(calls.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_on_custom_statuses() {
+        let runner = MockRunner::new(vec![failure(403), no_content()]);
+        let calls = runner.calls.clone();
+        let mut client = GithubClient::with_runner("o", "r", runner);
+        client.set_retry_policy(RetryPolicy::new(&[403, 502], None));
+
+        client.delete_label("bug").await.unwrap();
+        assert_eq!(calls.lock().unwrap().len(), 2);
+
+        // 5xx statuses not listed are no longer retried
+        let runner = MockRunner::new(vec![failure(500)]);
+        let calls = runner.calls.clone();
+        let mut client = GithubClient::with_runner("o", "r", runner);
+        client.set_retry_policy(RetryPolicy::new(&[403], Some(5)));
+
+        assert!(client.delete_label("bug").await.is_err());
+        assert_eq!(calls.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_rate_limits_only() {
+        let runner = MockRunner::new(vec![failure(502)]);
+        let calls = runner.calls.clone();
+        let mut client = GithubClient::with_runner("o", "r", runner);
+        client.set_retry_policy(RetryPolicy::rate_limits_only(None));
+
+        assert!(client.delete_label("bug").await.is_err());
+        assert_eq!(calls.lock().unwrap().len(), 1);
+
+        let secondary = GhOutput {
+            success: false,
+            stdout: "HTTP/2.0 403 Forbidden\r\nRetry-After: 0\r\n\r\n{\"message\":\"You have exceeded a secondary rate limit\"}".to_string(),
+            stderr: "gh: You have exceeded a secondary rate limit (HTTP 403)".to_string(),
+        };
+        let runner = MockRunner::new(vec![secondary, no_content()]);
+        let calls = runner.calls.clone();
+        let mut client = GithubClient::with_runner("o", "r", runner);
+        client.set_retry_policy(RetryPolicy::rate_limits_only(None));
+
+        client.delete_label("bug").await.unwrap();
+        assert_eq!(calls.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_rate_limit_wait() {
+        let secondary = BiaoError::SecondaryRateLimited {
+            retry_after: Duration::from_secs(37),
+        };
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.rate_limit_wait(&secondary, 0), Some(Duration::from_secs(37)));
+        assert_eq!(policy.rate_limit_wait(&secondary, DEFAULT_MAX_RETRIES), None);
+
+        // The primary limit waits until its reset; one in the past just needs a moment
+        let primary = BiaoError::RateLimited { reset_at: Some(0) };
+        assert_eq!(policy.rate_limit_wait(&primary, 0), Some(Duration::from_secs(1)));
+
+        let policy = RetryPolicy::default().without_waiting();
+        assert_eq!(policy.rate_limit_wait(&secondary, 0), None);
+        assert_eq!(policy.rate_limit_wait(&primary, 0), None);
+        assert!(!RetryPolicy::rate_limits_only(None).without_waiting().should_retry(&secondary, 0));
+    }
+
+    #[tokio::test]
+    async fn test_list_labels_waits_out_primary_rate_limit() {
+        let limited = || GhOutput {
+            success: false,
+            stdout: "HTTP/2.0 403 Forbidden\r\nX-RateLimit-Remaining: 0\r\nX-RateLimit-Reset: 0\r\n\r\n{\"message\":\"API rate limit exceeded\"}".to_string(),
+            stderr: "gh: API rate limit exceeded (HTTP 403)".to_string(),
+        };
+        let runner = MockRunner::new(vec![limited(), super::mock::ok("[]")]);
+        let client = GithubClient::with_runner("o", "r", runner);
+        assert!(client.list_labels().await.unwrap().is_empty());
+
+        let runner = MockRunner::new(vec![limited()]);
+        let calls = runner.calls.clone();
+        let mut client = GithubClient::with_runner("o", "r", runner);
+        client.set_retry_policy(RetryPolicy::default().without_waiting());
+        assert!(matches!(client.list_labels().await, Err(BiaoError::RateLimited { reset_at: Some(0) })));
+        assert_eq!(calls.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_retry_policy_limits_retries() {
+        let error = BiaoError::GhError {
+            message: String::new(),
+            status: Some(503),
+            code: None,
+        };
+        let policy = RetryPolicy::new(&[], Some(1));
+        assert!(policy.should_retry(&error, 0));
+        assert!(!policy.should_retry(&error, 1));
+        assert!(!RetryPolicy::default().should_retry(&BiaoError::InvalidInput(String::new()), 0));
+    }
+
+    #[test]
+    fn test_retry_classification() {
+        let policy = RetryPolicy::default();
+        let gh_error = |status: Option<u16>, message: &str| BiaoError::GhError {
+            message: message.to_string(),
+            status,
+            code: None,
+        };
+
+        for status in [500, 502, 503] {
+            assert!(policy.should_retry(&gh_error(Some(status), ""), 0));
+        }
+        assert!(policy.should_retry(&gh_error(None, "error connecting to api.github.com"), 0));
+        assert!(policy.should_retry(&gh_error(None, "curl: (28) Operation timed out"), 2));
+        assert!(!policy.should_retry(&gh_error(None, "error connecting to api.github.com"), 3));
+
+        // Client errors fail fast
+        assert!(!policy.should_retry(&gh_error(None, "unknown flag"), 0));
+        assert!(!policy.should_retry(&gh_error(Some(422), ""), 0));
+        assert!(!policy.should_retry(&BiaoError::AlreadyExists { name: "bug".to_string() }, 0));
+        let not_found = BiaoError::NotFound {
+            resource: "Label 'bug'".to_string(),
+        };
+        assert!(!policy.should_retry(&not_found, 0));
+    }
+
+    #[tokio::test]
+    async fn test_get_label_retries_connection_errors() {
+        let runner = MockRunner::new(vec![
+            GhOutput {
+                success: false,
+                stdout: String::new(),
+                stderr: "error connecting to api.github.com".to_string(),
+            },
+            super::mock::ok(r#"{"name":"bug","color":"d73a49","description":null,"url":"u","id":1,"node_id":"n","default":false}"#),
+        ]);
+        let calls = runner.calls.clone();
+        let client = GithubClient::with_runner("o", "r", runner);
+
+        assert_eq!(client.get_label("bug").await.unwrap().name, "bug");
+        assert_eq!(calls.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_create_detects_already_exists() {
+        let client = GithubClient::with_runner("o", "r", MockRunner::new(vec![already_exists()]));
+        let request = CreateLabelRequest {
+            name: "bug".to_string(),
+            color: "d73a49".to_string(),
+            description: None,
+        };
+
+        let err = client.create_label(&request).await.unwrap_err();
+        assert!(matches!(&err, BiaoError::AlreadyExists { name } if name == "bug"));
+        assert_eq!(err.status(), Some(422));
+
+        // Other validation failures are not mistaken for duplicates
+        let client = GithubClient::with_runner("o", "r", MockRunner::new(vec![failure(422)]));
+        assert!(matches!(
+            client.create_label(&request).await.unwrap_err(),
+            BiaoError::GhError { status: Some(422), .. }
+        ));
+    }
+
+    fn classify(stdout: &str, stderr: &str, path: &str) -> BiaoError {
+        classify_failure(&GhResponse::parse(stdout), stderr.to_string(), path, None)
+    }
+
+    #[test]
+    fn test_classify_failures() {
+        let not_found = "HTTP/2.0 404 Not Found\r\n\r\n{\"message\":\"Not Found\"}";
+        assert!(matches!(
+            classify(not_found, "gh: Not Found (HTTP 404)", "repos/o/r/labels/bug"),
+            BiaoError::NotFound { resource } if resource == "Label 'bug'"
+        ));
+        assert!(matches!(
+            classify(not_found, "gh: Not Found (HTTP 404)", "repos/o/missing/labels"),
+            BiaoError::RepoNotFound { owner, repo } if owner == "o" && repo == "missing"
+        ));
+
+        assert!(matches!(
+            classify(
+                "HTTP/2.0 401 Unauthorized\r\n\r\n{\"message\":\"Bad credentials\"}",
+                "gh: Bad credentials (HTTP 401)",
+                "repos/o/r/labels"
+            ),
+            BiaoError::Unauthorized
+        ));
+
+        let rate_limited = "HTTP/2.0 403 Forbidden\r\nX-Ratelimit-Remaining: 0\r\nX-Ratelimit-Reset: 1700000000\r\n\r\n\
+                            {\"message\":\"API rate limit exceeded for user ID 1.\"}";
+        assert!(matches!(
+            classify(rate_limited, "gh: API rate limit exceeded (HTTP 403)", "repos/o/r/labels"),
+            BiaoError::RateLimited {
+                reset_at: Some(1700000000)
+            }
+        ));
+
+        // A plain 403 stays a generic error, and so does a failure without output
+        assert!(matches!(
+            classify("HTTP/2.0 403 Forbidden\r\n\r\n{}", "gh: Forbidden (HTTP 403)", "repos/o/r/labels"),
+            BiaoError::GhError { status: Some(403), .. }
+        ));
+        assert!(matches!(
+            classify("", "error connecting to api.github.com", "repos/o/r/labels"),
+            BiaoError::GhError { status: None, .. }
+        ));
+    }
+
+    #[test]
+    fn test_error_code_from_body() {
+        let response = GhResponse::parse(&already_exists().stdout);
+        assert_eq!(response.status, Some(422));
+        assert_eq!(response.error_code().as_deref(), Some("already_exists"));
+        assert_eq!(GhResponse::parse("{\"message\":\"Not Found\"}").error_code(), None);
+    }
+
+    #[test]
+    fn test_http_status_from_stderr() {
+        let response = GhResponse::parse("");
+        assert_eq!(response.http_status("gh: Not Found (HTTP 404)"), Some(404));
+        assert_eq!(response.http_status("connection refused"), None);
+    }
+
+    #[test]
+    fn test_typed_field_args() {
+        assert_eq!(
+            Field::new("name", FieldValue::String("a=b true".to_string())).to_args(),
+            ["-f".to_string(), "name=a=b true".to_string()]
+        );
+        assert_eq!(
+            Field::new("private", FieldValue::Bool(false)).to_args(),
+            ["-F".to_string(), "private=false".to_string()]
+        );
+        assert_eq!(
+            Field::new("per_page", FieldValue::Number(100)).to_args(),
+            ["-F".to_string(), "per_page=100".to_string()]
+        );
+        assert_eq!(
+            Field::new("description", FieldValue::Null).to_args(),
+            ["-F".to_string(), "description=null".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_requests_typed_page_size() {
+        let runner = MockRunner::new(vec![super::mock::ok("[]")]);
+        let calls = runner.calls.clone();
+        let client = GithubClient::with_runner("o", "r", runner);
+
+        assert!(client.list_labels().await.unwrap().is_empty());
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec!["--include --method GET repos/o/r/labels -F per_page=100 -F page=1"]
+        );
+    }
+
+    #[test]
+    fn test_curl_config_from_gh_args() {
+        let config = curl_config(
+            &["--include", "--method", "GET", "repos/o/r/labels", "-F", "per_page=100", "-F", "page=2"],
+            None,
+            "t0ken",
+        );
+        assert_eq!(
+            config,
+            "silent\nshow-error\ninclude\nrequest = \"GET\"\n\
+             url = \"https://api.github.com/repos/o/r/labels?per_page=100&page=2\"\n\
+             header = \"Authorization: Bearer t0ken\"\n\
+             header = \"Accept: application/vnd.github+json\"\n\
+             header = \"X-GitHub-Api-Version: 2022-11-28\"\n"
+        );
+
+        // Enterprise host, a label name needing escapes and a JSON body
+        let config = curl_config(
+            &["--include", "--hostname", "ghe.corp", "repos/o/r/labels/good%20first%2Fissue", "-X", "PATCH", "--input", "-"],
+            Some(r#"{"description":"say \"hi\""}"#),
+            "t",
+        );
+        assert!(config.contains("request = \"PATCH\"\n"));
+        assert!(config.contains("url = \"https://ghe.corp/api/v3/repos/o/r/labels/good%20first%2Fissue\"\n"));
+        assert!(config.contains(r#"data-raw = "{\"description\":\"say \\\"hi\\\"\"}""#));
+    }
+
+    #[test]
+    fn test_strip_interim_responses() {
+        let raw = "HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 201 Created\r\nX-A: 1\r\n\r\n{}";
+        assert_eq!(strip_interim_responses(raw), "HTTP/1.1 201 Created\r\nX-A: 1\r\n\r\n{}");
+        assert_eq!(GhResponse::parse(strip_interim_responses(raw)).status, Some(201));
+        assert_eq!(strip_interim_responses("HTTP/2 200\r\n\r\n[]"), "HTTP/2 200\r\n\r\n[]");
+    }
+
+    #[tokio::test]
+    async fn test_list_labels_merges_pages() {
+        let label = |i: usize| {
+            format!(
+                r#"{{"name":"l{0}","color":"ededed","description":null,"url":"u","id":{0},"node_id":"n","default":false}}"#,
+                i
+            )
+        };
+        let full_page: Vec<String> = (0..LABELS_PER_PAGE).map(label).collect();
+        let runner = MockRunner::new(vec![
+            super::mock::ok(&format!("[{}]", full_page.join(","))),
+            super::mock::ok(&format!("[{},{}]", label(LABELS_PER_PAGE), label(LABELS_PER_PAGE + 1))),
+        ]);
+        let calls = runner.calls.clone();
+        let client = GithubClient::with_runner("o", "r", runner);
+
+        let labels = client.list_labels().await.unwrap();
+        assert_eq!(labels.len(), LABELS_PER_PAGE + 2);
+        assert_eq!(labels[LABELS_PER_PAGE + 1].name, "l101");
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec![
+                "--include --method GET repos/o/r/labels -F per_page=100 -F page=1",
+                "--include --method GET repos/o/r/labels -F per_page=100 -F page=2",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_org_repos_paginates() {
+        let repo = |i: usize| format!(r#"{{"name":"r{0}","full_name":"org/r{0}","archived":false,"id":{0}}}"#, i);
+        let full_page: Vec<String> = (0..REPOS_PER_PAGE).map(repo).collect();
+        let runner = MockRunner::new(vec![
+            super::mock::ok(&format!("[{}]", full_page.join(","))),
+            failure(502),
+            super::mock::ok(&format!("[{}]", repo(REPOS_PER_PAGE))),
+        ]);
+        let calls = runner.calls.clone();
+        let client = GithubClient::with_runner("org", "", runner);
+
+        let repos = client.list_org_repos("org").await.unwrap();
+        assert_eq!(repos.len(), REPOS_PER_PAGE + 1);
+        assert_eq!(repos[REPOS_PER_PAGE].full_name, "org/r100");
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec![
+                "--include --method GET orgs/org/repos -F per_page=100 -F page=1",
+                "--include --method GET orgs/org/repos -F per_page=100 -F page=2",
+                "--include --method GET orgs/org/repos -F per_page=100 -F page=2",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_profiling_records_requests() {
+        let runner = MockRunner::new(vec![super::mock::ok("[]"), super::mock::ok("")]);
+        let mut client = GithubClient::with_runner("o", "r", runner);
+        client.enable_profiling();
+
+        client.list_labels().await.unwrap();
+        client.delete_label("old").await.unwrap();
+
+        let report = client.profile().unwrap().report();
+        let requests: Vec<(Phase, &str)> = report
+            .slowest
+            .iter()
+            .map(|t| (t.phase, t.request.as_str()))
+            .collect();
+        assert_eq!(report.calls, 2);
+        assert!(requests.contains(&(Phase::List, "GET repos/o/r/labels")));
+        assert!(requests.contains(&(Phase::Delete, "DELETE repos/o/r/labels/old")));
+    }
+
+    #[tokio::test]
+    async fn test_repo_permissions() {
+        let runner = MockRunner::new(vec![super::mock::ok(
+            r#"{"full_name":"o/r","permissions":{"admin":false,"maintain":false,"push":true,"triage":true,"pull":true}}"#,
+        )]);
+        let calls = runner.calls.clone();
+        let client = GithubClient::with_runner("o", "r", runner);
+
+        let permissions = client.repo_permissions().await.unwrap();
+        assert!(permissions.can_manage_labels());
+        assert_eq!(permissions.role(), "write");
+        assert_eq!(*calls.lock().unwrap(), vec!["--include repos/o/r"]);
+    }
+
+    #[test]
+    fn test_web_urls() {
+        assert_eq!(repo_web_url("github.com", "o", "r"), "https://github.com/o/r");
+        assert_eq!(
+            label_web_url("github.com", "o", "r", "good first issue"),
+            "https://github.com/o/r/labels/good%20first%20issue"
+        );
+        assert_eq!(
+            label_web_url("ghe.example.com", "o", "r", "type/bug"),
+            "https://ghe.example.com/o/r/labels/type%2Fbug"
+        );
+        assert_eq!(
+            GithubClient::with_runner("o", "r", MockRunner::new(vec![])).label_web_url("A-cli"),
+            "https://github.com/o/r/labels/A-cli"
+        );
+    }
+
+    #[test]
+    fn test_dump_create_request() {
+        let client = GithubClient::with_runner("o", "r", MockRunner::new(vec![]));
+        let request = LabelRequest::Create(CreateLabelRequest {
+            name: "bug".to_string(),
+            color: "d73a49".to_string(),
+            description: Some("Something isn't working".to_string()),
+        });
+
+        assert_eq!(
+            client.dump_request(&request).unwrap(),
+            "POST repos/o/r/labels\n{\"name\":\"bug\",\"color\":\"d73a49\",\"description\":\"Something isn't working\"}\n"
+        );
+        assert_eq!(
+            client
+                .dump_request(&LabelRequest::Delete {
+                    name: "old".to_string()
+                })
+                .unwrap(),
+            "DELETE repos/o/r/labels/old\n"
+        );
+    }
+
+    #[test]
+    fn test_issues_web_url() {
+        assert_eq!(
+            issues_web_url("github.com", "o", "r", "good first issue"),
+            "https://github.com/o/r/issues?q=label%3A%22good%20first%20issue%22"
+        );
+        assert_eq!(
+            issues_web_url("ghe.example.com", "o", "r", "type/bug"),
+            "https://ghe.example.com/o/r/issues?q=label%3A%22type%2Fbug%22"
+        );
+    }
+
+    #[test]
+    fn test_parse_plain_output() {
+        let response = GhResponse::parse("[]");
+        assert_eq!(response.status, None);
+        assert_eq!(response.body, "[]");
+        assert!(!response.is_secondary_rate_limit("gh: Not Found (HTTP 404)"));
     }
 }
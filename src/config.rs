@@ -1,17 +1,48 @@
 use crate::error::{BiaoError, Result};
+use crate::models::{CreateLabelRequest, GithubLabel, UpdateLabelRequest};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::Path;
 
-#[derive(Debug, Deserialize, Serialize)]
+/// Longest label name GitHub accepts, in characters
+pub const MAX_NAME_LENGTH: usize = 50;
+
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct LabelConfig {
+    /// Optional title of the config, shown by `apply` as "Applying: <name>"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Color of labels (and label groups) that don't set one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_color: Option<String>,
+    /// Description of labels (and label groups) that don't set one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub delete: Vec<String>,
     #[serde(default)]
     pub labels: Vec<Label>,
+    /// Shorthand for several labels sharing a prefix and color, expanded into `labels` on parse
+    #[serde(default, rename = "label_group", skip_serializing_if = "Vec::is_empty")]
+    pub label_groups: Vec<LabelGroup>,
+}
+
+/// A `[[label_group]]` entry, e.g. `prefix = "area/"` with `names = ["api", "cli"]`
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct LabelGroup {
     #[serde(default)]
-    pub delete: Vec<String>,
+    pub prefix: String,
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub names: Vec<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
 pub struct Label {
     pub name: String,
     /// Color is required for new labels, optional for updates
@@ -20,33 +51,432 @@ pub struct Label {
     #[serde(default)]
     pub description: Option<String>,
     /// List of existing label names to update/rename to this label's name
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub update_if_match: Vec<String>,
     /// If true, skip if label already exists. If false (default), fail on existing labels.
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
     pub skip_if_exists: bool,
     /// If true, update the label if it already exists instead of failing/skipping.
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
     pub update_if_exists: bool,
 }
 
+/// How serious a problem `LabelConfig::lint` found is
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// `apply` would fail or do something unintended
+    Error,
+    Warning,
+}
+
+/// Syntax of a config file, picked with `--input-format` or from the file extension
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ConfigFormat {
+    #[default]
+    Toml,
+    Json,
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// Format implied by a path's extension; `None` when it doesn't say (including stdin)
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        match ext.as_str() {
+            "toml" => Some(ConfigFormat::Toml),
+            "json" => Some(ConfigFormat::Json),
+            "yaml" | "yml" => Some(ConfigFormat::Yaml),
+            _ => None,
+        }
+    }
+}
+
+impl std::str::FromStr for LabelConfig {
+    type Err = BiaoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse_as(s, ConfigFormat::Toml)
+    }
+}
+
+impl From<&GithubLabel> for Label {
+    fn from(label: &GithubLabel) -> Self {
+        Label {
+            name: label.name.clone(),
+            color: Some(label.color.clone()),
+            description: label.description.clone().filter(|d| !d.is_empty()),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<&Label> for CreateLabelRequest {
+    type Error = BiaoError;
+
+    fn try_from(label: &Label) -> Result<Self> {
+        let color = label.color.clone().ok_or_else(|| {
+            BiaoError::InvalidInput(format!("Label '{}' needs a color to be created", label.name))
+        })?;
+
+        Ok(CreateLabelRequest {
+            name: label.name.clone(),
+            color,
+            description: label.description.clone(),
+        })
+    }
+}
+
+impl From<&Label> for UpdateLabelRequest {
+    fn from(label: &Label) -> Self {
+        UpdateLabelRequest {
+            name: None,
+            color: label.color.clone(),
+            description: label.description.clone(),
+        }
+    }
+}
+
 impl LabelConfig {
-    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let content = fs::read_to_string(path.as_ref()).map_err(|e| {
+    /// Parse a config written in `format`, expanding label groups and defaults
+    pub fn parse_as(s: &str, format: ConfigFormat) -> Result<Self> {
+        let mut config: LabelConfig = match format {
+            ConfigFormat::Toml => toml::from_str(s).map_err(|e| {
+                BiaoError::InvalidInput(format!("Failed to parse TOML config: {}", e))
+            })?,
+            ConfigFormat::Json => serde_json::from_str(s).map_err(|e| {
+                BiaoError::InvalidInput(format!("Failed to parse JSON config: {}", e))
+            })?,
+            ConfigFormat::Yaml => crate::yaml::parse(s)
+                .and_then(|value| serde_json::from_value(value).map_err(|e| e.to_string()))
+                .map_err(|e| BiaoError::InvalidInput(format!("Failed to parse YAML config: {}", e)))?,
+        };
+        config.expand_groups();
+        config.apply_defaults();
+        Ok(config)
+    }
+
+    /// Parse a config in `format`, or when unknown as TOML and failing that as YAML
+    pub fn parse(s: &str, format: Option<ConfigFormat>) -> Result<Self> {
+        if let Some(format) = format {
+            return Self::parse_as(s, format);
+        }
+        match Self::parse_as(s, ConfigFormat::Toml) {
+            Ok(config) => Ok(config),
+            Err(toml_error) => Self::parse_as(s, ConfigFormat::Yaml).map_err(|yaml_error| {
+                BiaoError::InvalidInput(format!(
+                    "Config is neither TOML nor YAML (pass --input-format to pick one):\n  {}\n  {}",
+                    toml_error, yaml_error
+                ))
+            }),
+        }
+    }
+
+    /// Parse a config as written, keeping label groups and defaults unexpanded
+    pub fn parse_unexpanded(s: &str) -> Result<Self> {
+        toml::from_str(s).map_err(|e| BiaoError::InvalidInput(format!("Failed to parse TOML config: {}", e)))
+    }
+
+    /// Canonical ordering for committed configs: labels and groups sorted by
+    /// name (case-insensitively), the delete list alphabetically, and colors
+    /// normalized to lowercase hex without `#`
+    pub fn canonicalize(&mut self) -> Result<()> {
+        let normalize = |color: &mut Option<String>| -> Result<()> {
+            if let Some(c) = color {
+                *c = crate::cli::normalize_color(c)?;
+            }
+            Ok(())
+        };
+
+        normalize(&mut self.default_color)?;
+        for label in &mut self.labels {
+            normalize(&mut label.color)?;
+        }
+        for group in &mut self.label_groups {
+            normalize(&mut group.color)?;
+            group.names.sort_by_key(|name| (name.to_lowercase(), name.clone()));
+        }
+
+        self.labels.sort_by_key(|label| (label.name.to_lowercase(), label.name.clone()));
+        self.label_groups
+            .sort_by_key(|group| (group.prefix.to_lowercase(), group.prefix.clone()));
+        self.delete.sort_by_key(|name| (name.to_lowercase(), name.clone()));
+        Ok(())
+    }
+
+    /// Read a config from `path`, or stdin for `-`, in `format` or the one its
+    /// extension implies, guessing between TOML and YAML otherwise
+    pub fn from_file_as<P: AsRef<Path>>(path: P, format: Option<ConfigFormat>) -> Result<Self> {
+        let path = path.as_ref();
+        if path == Path::new("-") {
+            return Self::from_reader(std::io::stdin(), format);
+        }
+        let content = fs::read_to_string(path).map_err(|e| {
             BiaoError::Io(std::io::Error::new(
                 std::io::ErrorKind::Other,
                 format!("Failed to read config file: {}", e),
             ))
         })?;
 
-        toml::from_str(&content).map_err(|e| {
-            BiaoError::InvalidInput(format!("Failed to parse TOML config: {}", e))
-        })
+        Self::parse(&content, format.or_else(|| ConfigFormat::from_path(path)))
+    }
+
+    /// Read a whole config from `reader`, like stdin, in `format` or guessing
+    /// between TOML and YAML
+    pub fn from_reader<R: std::io::Read>(reader: R, format: Option<ConfigFormat>) -> Result<Self> {
+        let content = std::io::read_to_string(reader)
+            .map_err(|e| BiaoError::Io(std::io::Error::other(format!("Failed to read config: {}", e))))?;
+
+        Self::parse(&content, format)
+    }
+
+    /// Turn every `[[label_group]]` into individual labels
+    fn expand_groups(&mut self) {
+        for group in self.label_groups.drain(..) {
+            for name in &group.names {
+                self.labels.push(Label {
+                    name: format!("{}{}", group.prefix, name),
+                    color: group.color.clone(),
+                    description: group.description.clone(),
+                    ..Default::default()
+                });
+            }
+        }
+    }
+
+    /// Fill in `default_color` and `default_description` where labels omit them
+    fn apply_defaults(&mut self) {
+        for label in &mut self.labels {
+            if label.color.is_none() {
+                label.color = self.default_color.clone();
+            }
+            if label.description.is_none() {
+                label.description = self.default_description.clone();
+            }
+        }
+    }
+
+    /// Reject labels GitHub would refuse, labels both skipped and updated when
+    /// they exist, and aliases whose target would depend on label order.
+    ///
+    /// Label names must be unique, and an `update_if_match` entry may only
+    /// appear under one label and must not name a different label of the
+    /// config. Names compare case-insensitively, like on GitHub.
+    pub fn validate(&self) -> Result<()> {
+        let too_long = self.too_long_names();
+        if !too_long.is_empty() {
+            return Err(BiaoError::InvalidInput(format!(
+                "GitHub limits label names to {} characters:\n  {}\nShorten them or pass --truncate-names.",
+                MAX_NAME_LENGTH,
+                too_long.join("\n  ")
+            )));
+        }
+
+        let duplicates = self.duplicate_names();
+        if !duplicates.is_empty() {
+            return Err(BiaoError::InvalidInput(format!(
+                "GitHub label names are case-insensitive, so these labels would collide:\n  {}",
+                duplicates.join("\n  ")
+            )));
+        }
+
+        let conflicting = self.conflicting_flags();
+        if !conflicting.is_empty() {
+            return Err(BiaoError::InvalidInput(conflicting.join("\n")));
+        }
+
+        let problems = self.alias_conflicts();
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(BiaoError::InvalidInput(format!(
+                "Conflicting update_if_match entries:\n  {}",
+                problems.join("\n  ")
+            )))
+        }
+    }
+
+    /// Every problem of the config, for `biao validate`: what `validate`
+    /// rejects, invalid colors and labels also listed under `delete` are
+    /// errors; blank `update_if_match` entries are warnings
+    pub fn lint(&self) -> Vec<(Severity, String)> {
+        let mut problems = Vec::new();
+        let mut error = |message: String| problems.push((Severity::Error, message));
+
+        for label in &self.labels {
+            if let Some(color) = &label.color {
+                if let Err(e) = crate::cli::normalize_color(color) {
+                    let reason = match e {
+                        BiaoError::InvalidInput(message) => message,
+                        e => e.to_string(),
+                    };
+                    error(format!("'{}' has an invalid color '{}': {}", label.name, color, reason));
+                }
+            }
+        }
+        for name in self.too_long_names() {
+            error(format!("{} is longer than GitHub's {}-character limit", name, MAX_NAME_LENGTH));
+        }
+        for pair in self.duplicate_names() {
+            error(format!("{} collide, as GitHub label names are case-insensitive", pair));
+        }
+        for conflict in self.conflicting_flags() {
+            error(conflict);
+        }
+        for conflict in self.alias_conflicts() {
+            error(conflict);
+        }
+
+        let defined: HashSet<String> = self.labels.iter().map(|l| l.name.to_lowercase()).collect();
+        for name in &self.delete {
+            if defined.contains(&name.to_lowercase()) {
+                error(format!("'{}' is both defined in labels and listed in delete", name));
+            }
+        }
+
+        for label in &self.labels {
+            if label.update_if_match.iter().any(|alias| alias.trim().is_empty()) {
+                problems.push((
+                    Severity::Warning,
+                    format!("'{}' has an empty update_if_match entry", label.name),
+                ));
+            }
+        }
+        problems
+    }
+
+    /// Labels with names GitHub would refuse as too long, e.g. `'x' (51 characters)`
+    fn too_long_names(&self) -> Vec<String> {
+        self.labels
+            .iter()
+            .filter(|l| l.name.chars().count() > MAX_NAME_LENGTH)
+            .map(|l| format!("'{}' ({} characters)", l.name, l.name.chars().count()))
+            .collect()
+    }
+
+    /// Pairs of labels whose names differ only in case, e.g. `'Bug' and 'bug'`
+    fn duplicate_names(&self) -> Vec<String> {
+        let mut names: HashMap<String, &str> = HashMap::new();
+        let mut duplicates = Vec::new();
+        for label in &self.labels {
+            if let Some(first) = names.insert(label.name.to_lowercase(), &label.name) {
+                duplicates.push(format!("'{}' and '{}'", first, label.name));
+            }
+        }
+        duplicates
+    }
+
+    /// Labels setting both `skip_if_exists` and `update_if_exists`, which ask
+    /// for opposite things when the label already exists
+    fn conflicting_flags(&self) -> Vec<String> {
+        self.labels
+            .iter()
+            .filter(|l| l.skip_if_exists && l.update_if_exists)
+            .map(|l| {
+                format!(
+                    "'{}' sets both skip_if_exists and update_if_exists; keep only one",
+                    l.name
+                )
+            })
+            .collect()
+    }
+
+    /// `update_if_match` entries naming another label or shared between labels
+    fn alias_conflicts(&self) -> Vec<String> {
+        let names: HashMap<String, &str> = self.labels.iter().map(|l| (l.name.to_lowercase(), l.name.as_str())).collect();
+        let mut owners: HashMap<String, &str> = HashMap::new();
+        let mut problems = Vec::new();
+
+        for label in &self.labels {
+            let own_name = label.name.to_lowercase();
+            let mut seen = Vec::new();
+            for alias in &label.update_if_match {
+                let key = alias.to_lowercase();
+                if key == own_name || seen.contains(&key) {
+                    continue;
+                }
+                seen.push(key.clone());
+
+                if let Some(other) = names.get(&key) {
+                    problems.push(format!(
+                        "'{}' in update_if_match of '{}' is also the label '{}'",
+                        alias, label.name, other
+                    ));
+                }
+                if let Some(first) = owners.insert(key, &label.name) {
+                    problems.push(format!(
+                        "'{}' is in update_if_match of both '{}' and '{}'",
+                        alias, first, label.name
+                    ));
+                }
+            }
+        }
+        problems
     }
 
     pub fn has_actions(&self) -> bool {
         !self.labels.is_empty() || !self.delete.is_empty()
     }
+
+    /// Lowercased names of labels that are new or defined differently than
+    /// in `base`, plus `delete` entries that `base` did not have
+    pub fn changed_since(&self, base: &LabelConfig) -> HashSet<String> {
+        let before: HashMap<String, &Label> = base.labels.iter().map(|l| (l.name.to_lowercase(), l)).collect();
+        let deleted_before: HashSet<String> = base.delete.iter().map(|n| n.to_lowercase()).collect();
+
+        let labels = self
+            .labels
+            .iter()
+            .filter(|label| before.get(&label.name.to_lowercase()) != Some(label))
+            .map(|label| label.name.to_lowercase());
+        let deletions = self
+            .delete
+            .iter()
+            .map(|name| name.to_lowercase())
+            .filter(|name| !deleted_before.contains(name));
+
+        labels.chain(deletions).collect()
+    }
+
+    /// Keep only the labels and deletions whose lowercased name is in `names`
+    pub fn retain_names(&mut self, names: &HashSet<String>) {
+        self.labels.retain(|label| names.contains(&label.name.to_lowercase()));
+        self.delete.retain(|name| names.contains(&name.to_lowercase()));
+    }
+
+    /// Cut label names longer than GitHub's limit down to [`MAX_NAME_LENGTH`]
+    /// characters, returning the `(original, truncated)` pairs for warnings
+    pub fn truncate_names(&mut self) -> Vec<(String, String)> {
+        let mut truncated = Vec::new();
+        for label in &mut self.labels {
+            if label.name.chars().count() > MAX_NAME_LENGTH {
+                let short: String = label.name.chars().take(MAX_NAME_LENGTH).collect();
+                let short = short.trim_end().to_string();
+                truncated.push((std::mem::replace(&mut label.name, short.clone()), short));
+            }
+        }
+        truncated
+    }
+
+    /// Lowercase every label name and `update_if_match` target.
+    ///
+    /// GitHub label names are unique case-insensitively, so aliases that only
+    /// differ from the label name by case are dropped instead of producing a
+    /// rename of the label onto itself, and aliases that collapse to the same
+    /// lowercase name are deduplicated.
+    pub fn lowercase_names(&mut self) {
+        for label in &mut self.labels {
+            label.name = label.name.to_lowercase();
+
+            let mut aliases: Vec<String> = Vec::new();
+            for alias in label.update_if_match.drain(..) {
+                let alias = alias.to_lowercase();
+                if alias != label.name && !aliases.contains(&alias) {
+                    aliases.push(alias);
+                }
+            }
+            label.update_if_match = aliases;
+        }
+    }
 }
 
 #[cfg(test)]
@@ -123,6 +553,25 @@ description = "Updated description"
         assert_eq!(config.delete.len(), 1);
     }
 
+    #[test]
+    fn test_parse_metadata() {
+        let toml = r#"
+name = "Standard GitHub Labels"
+description = "Labels shared by all our repositories"
+
+[[labels]]
+name = "bug"
+color = "d73a49"
+"#;
+
+        let config: LabelConfig = toml::from_str(toml).unwrap();
+        assert_eq!(config.name.as_deref(), Some("Standard GitHub Labels"));
+        assert_eq!(config.description.as_deref(), Some("Labels shared by all our repositories"));
+
+        let config: LabelConfig = toml::from_str("[[labels]]\nname = \"bug\"\n").unwrap();
+        assert!(config.name.is_none() && config.description.is_none());
+    }
+
     #[test]
     fn test_parse_with_conflict_flags() {
         let toml = r#"
@@ -168,4 +617,483 @@ description = "Extra attention needed"
         assert_eq!(config.labels[0].update_if_match[0], "help wanted");
         assert_eq!(config.labels[0].update_if_match[1], "help-needed");
     }
+
+    #[test]
+    fn test_label_group_expansion() {
+        let toml = r#"
+[[labels]]
+name = "bug"
+color = "d73a49"
+
+[[label_group]]
+prefix = "area/"
+color = "0075ca"
+names = ["api", "cli", "docs"]
+"#;
+
+        let config: LabelConfig = toml.parse().unwrap();
+        assert_eq!(config.labels.len(), 4);
+        assert!(config.label_groups.is_empty());
+
+        let names: Vec<&str> = config.labels[1..].iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(names, vec!["area/api", "area/cli", "area/docs"]);
+        assert!(config.labels[1..]
+            .iter()
+            .all(|l| l.color.as_deref() == Some("0075ca")));
+    }
+
+    #[test]
+    fn test_label_from_github_label() {
+        let remote = GithubLabel {
+            name: "bug".to_string(),
+            color: "d73a49".to_string(),
+            description: Some(String::new()),
+            url: String::new(),
+            id: 1,
+            node_id: String::new(),
+            default: true,
+        };
+
+        let label = Label::from(&remote);
+        assert_eq!(label.name, "bug");
+        assert_eq!(label.color, Some("d73a49".to_string()));
+        assert_eq!(label.description, None);
+        assert!(label.update_if_match.is_empty());
+    }
+
+    #[test]
+    fn test_label_into_requests() {
+        let label = Label {
+            name: "bug".to_string(),
+            color: Some("d73a49".to_string()),
+            description: Some("Broken".to_string()),
+            ..Default::default()
+        };
+
+        let create = CreateLabelRequest::try_from(&label).unwrap();
+        assert_eq!(create.name, "bug");
+        assert_eq!(create.color, "d73a49");
+        assert_eq!(create.description, Some("Broken".to_string()));
+
+        let update = UpdateLabelRequest::from(&label);
+        assert_eq!(update.name, None);
+        assert_eq!(update.color, Some("d73a49".to_string()));
+
+        let colorless = Label {
+            name: "docs".to_string(),
+            ..Default::default()
+        };
+        assert!(CreateLabelRequest::try_from(&colorless).is_err());
+        assert_eq!(UpdateLabelRequest::from(&colorless).color, None);
+    }
+
+    #[test]
+    fn test_parse_forced_formats() {
+        let toml = r#"
+delete = ["wontfix"]
+
+[[labels]]
+name = "bug"
+color = "d73a49"
+update_if_exists = true
+"#;
+        let json = r#"{
+            "delete": ["wontfix"],
+            "labels": [{"name": "bug", "color": "d73a49", "update_if_exists": true}]
+        }"#;
+
+        let from_toml = LabelConfig::parse_as(toml, ConfigFormat::Toml).unwrap();
+        let from_json = LabelConfig::parse_as(json, ConfigFormat::Json).unwrap();
+        assert_eq!(from_toml.labels, from_json.labels);
+        assert_eq!(from_toml.delete, from_json.delete);
+        assert!(from_json.labels[0].update_if_exists);
+
+        // The wrong format is an error, not a silently empty config
+        assert!(LabelConfig::parse_as(json, ConfigFormat::Toml).is_err());
+        assert!(LabelConfig::parse_as(toml, ConfigFormat::Json).is_err());
+    }
+
+    #[test]
+    fn test_from_reader_matches_file() {
+        let toml = r#"
+delete = ["wontfix"]
+
+[[labels]]
+name = "bug"
+color = "d73a49"
+skip_if_exists = true
+"#;
+        let path = std::env::temp_dir().join(format!("biao-reader-{}.toml", std::process::id()));
+        fs::write(&path, toml).unwrap();
+        let from_file = LabelConfig::from_file_as(&path, None).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let from_reader = LabelConfig::from_reader(toml.as_bytes(), None).unwrap();
+        assert_eq!(from_reader.labels, from_file.labels);
+        assert_eq!(from_reader.delete, from_file.delete);
+        assert!(from_reader.labels[0].skip_if_exists);
+    }
+
+    #[test]
+    fn test_yaml_matches_toml() {
+        let toml = r#"
+name = "Core"
+default_color = "ededed"
+delete = ["wontfix"]
+
+[[labels]]
+name = "bug"
+color = "d73a49"
+description = "Something isn't working"
+update_if_exists = true
+
+[[labels]]
+name = "feature"
+update_if_match = ["enhancement", "Feature Request"]
+
+[[labels]]
+name = "2024"
+color = "000000"
+
+[[label_group]]
+prefix = "A-"
+color = "c2e0c6"
+names = ["parser", "cli"]
+"#;
+        let yaml = r#"
+name: Core
+default_color: ededed
+delete: [wontfix]
+labels:
+  - name: bug
+    color: d73a49
+    description: "Something isn't working"
+    update_if_exists: true
+  - name: feature
+    update_if_match:
+      - enhancement
+      - Feature Request
+  - name: 2024
+    color: 000000
+label_group:
+  - prefix: A-
+    color: c2e0c6
+    names: [parser, cli]
+"#;
+        let from_toml = LabelConfig::parse_as(toml, ConfigFormat::Toml).unwrap();
+        let from_yaml = LabelConfig::parse_as(yaml, ConfigFormat::Yaml).unwrap();
+        assert_eq!(from_yaml.name, from_toml.name);
+        assert_eq!(from_yaml.delete, from_toml.delete);
+        assert_eq!(from_yaml.labels, from_toml.labels);
+        assert_eq!(from_yaml.labels.len(), 5);
+
+        // Without a format, TOML is tried first, then YAML
+        assert_eq!(LabelConfig::parse(yaml, None).unwrap().labels, from_toml.labels);
+        assert_eq!(LabelConfig::parse(toml, None).unwrap().labels, from_toml.labels);
+        let err = LabelConfig::parse("labels = [", None).unwrap_err().to_string();
+        assert!(err.contains("neither TOML nor YAML"));
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(ConfigFormat::from_path(Path::new("labels.json")), Some(ConfigFormat::Json));
+        assert_eq!(ConfigFormat::from_path(Path::new("labels.toml")), Some(ConfigFormat::Toml));
+        assert_eq!(ConfigFormat::from_path(Path::new("labels.YML")), Some(ConfigFormat::Yaml));
+        assert_eq!(ConfigFormat::from_path(Path::new("labels")), None);
+        assert_eq!(ConfigFormat::from_path(Path::new("-")), None);
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        let toml = r##"
+delete = ["wontfix", "Duplicate"]
+default_color = "#EDEDED"
+
+[[labels]]
+name = "enhancement"
+color = "#A2EEEF"
+
+[[labels]]
+name = "Bug"
+color = "d73a49"
+
+[[labels]]
+name = "docs"
+
+[[label_group]]
+prefix = "type/"
+names = ["refactor", "chore"]
+
+[[label_group]]
+prefix = "area/"
+color = "0075CA"
+names = ["cli"]
+"##;
+        let mut config = LabelConfig::parse_unexpanded(toml).unwrap();
+        config.canonicalize().unwrap();
+
+        let names: Vec<&str> = config.labels.iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(names, vec!["Bug", "docs", "enhancement"]);
+        assert_eq!(config.labels[2].color.as_deref(), Some("a2eeef"));
+        // Defaults and groups stay as written, only tidied
+        assert_eq!(config.labels[1].color, None);
+        assert_eq!(config.default_color.as_deref(), Some("ededed"));
+        assert_eq!(config.delete, vec!["Duplicate", "wontfix"]);
+        assert_eq!(config.label_groups[0].prefix, "area/");
+        assert_eq!(config.label_groups[0].color.as_deref(), Some("0075ca"));
+        assert_eq!(config.label_groups[1].names, vec!["chore", "refactor"]);
+
+        // The output parses back to the same labels
+        let written = toml::to_string_pretty(&config).unwrap();
+        let reparsed: LabelConfig = written.parse().unwrap();
+        assert_eq!(reparsed.labels.len(), 6);
+    }
+
+    #[test]
+    fn test_default_color_and_description() {
+        let toml = r#"
+default_color = "0075ca"
+default_description = "Area of the codebase"
+
+[[labels]]
+name = "area/api"
+
+[[labels]]
+name = "bug"
+color = "d73a49"
+description = "Something isn't working"
+
+[[label_group]]
+prefix = "area/"
+names = ["cli"]
+"#;
+        let config: LabelConfig = toml.parse().unwrap();
+        assert_eq!(config.labels[0].color.as_deref(), Some("0075ca"));
+        assert_eq!(config.labels[0].description.as_deref(), Some("Area of the codebase"));
+        // Explicit values win over the defaults
+        assert_eq!(config.labels[1].color.as_deref(), Some("d73a49"));
+        assert_eq!(config.labels[1].description.as_deref(), Some("Something isn't working"));
+        assert_eq!(config.labels[2].name, "area/cli");
+        assert_eq!(config.labels[2].color.as_deref(), Some("0075ca"));
+
+        let config: LabelConfig = "[[labels]]\nname = \"bug\"\n".parse().unwrap();
+        assert_eq!(config.labels[0].color, None);
+    }
+
+    #[test]
+    fn test_validate_case_insensitive_duplicates() {
+        let toml = r#"
+[[labels]]
+name = "Bug"
+color = "d73a49"
+
+[[labels]]
+name = "bug"
+color = "ee0701"
+"#;
+        let config: LabelConfig = toml.parse().unwrap();
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("would collide"));
+        assert!(err.contains("'Bug' and 'bug'"));
+
+        let config: LabelConfig = "[[labels]]\nname = \"Bug\"\n\n[[labels]]\nname = \"bugs\"\n"
+            .parse()
+            .unwrap();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_alias_collisions() {
+        let toml = r#"
+[[labels]]
+name = "bug"
+update_if_match = ["Bug", "defect"]
+
+[[labels]]
+name = "regression"
+update_if_match = ["Defect"]
+"#;
+        let config: LabelConfig = toml.parse().unwrap();
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("'Defect' is in update_if_match of both 'bug' and 'regression'"));
+
+        let toml = r#"
+[[labels]]
+name = "bug"
+
+[[labels]]
+name = "defect"
+update_if_match = ["BUG"]
+"#;
+        let config: LabelConfig = toml.parse().unwrap();
+        assert!(config.validate().unwrap_err().to_string().contains("is also the label 'bug'"));
+
+        let toml = r#"
+[[labels]]
+name = "bug"
+
+[[labels]]
+name = "defect"
+update_if_match = ["Bug"]
+"#;
+        let config: LabelConfig = toml.parse().unwrap();
+        assert!(config.validate().unwrap_err().to_string().contains("'Bug' in update_if_match of 'defect'"));
+
+        // An alias naming its own label is harmless
+        let config: LabelConfig = "[[labels]]\nname = \"bug\"\nupdate_if_match = [\"bug\", \"Bug\"]\n"
+            .parse()
+            .unwrap();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_conflicting_exists_flags() {
+        let toml = r#"
+[[labels]]
+name = "bug"
+color = "d73a49"
+skip_if_exists = true
+update_if_exists = true
+"#;
+        let config: LabelConfig = toml.parse().unwrap();
+        let err = config.validate().unwrap_err();
+        assert!(matches!(err, BiaoError::InvalidInput(_)));
+        assert!(err
+            .to_string()
+            .contains("'bug' sets both skip_if_exists and update_if_exists"));
+    }
+
+    #[test]
+    fn test_lint_reports_every_problem() {
+        let toml = r#"
+delete = ["wontfix", "Stale"]
+
+[[labels]]
+name = "Bug"
+color = "not-a-color"
+
+[[labels]]
+name = "bug"
+color = "d73a49"
+
+[[labels]]
+name = "stale"
+color = "ededed"
+update_if_match = [" "]
+"#;
+        let config: LabelConfig = toml.parse().unwrap();
+        let problems = config.lint();
+        let errors: Vec<&str> = problems
+            .iter()
+            .filter(|(severity, _)| *severity == Severity::Error)
+            .map(|(_, message)| message.as_str())
+            .collect();
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+        assert!(errors[0].starts_with("'Bug' has an invalid color 'not-a-color'"));
+        assert_eq!(errors[1], "'Bug' and 'bug' collide, as GitHub label names are case-insensitive");
+        assert_eq!(errors[2], "'Stale' is both defined in labels and listed in delete");
+        assert!(problems.contains(&(Severity::Warning, "'stale' has an empty update_if_match entry".to_string())));
+
+        let config: LabelConfig = "[[labels]]\nname = \"bug\"\ncolor = \"#D73A49\"\n".parse().unwrap();
+        assert!(config.lint().is_empty());
+    }
+
+    #[test]
+    fn test_changed_since() {
+        let base: LabelConfig = r#"
+delete = ["wontfix"]
+
+[[labels]]
+name = "bug"
+color = "d73a49"
+
+[[labels]]
+name = "feature"
+color = "a2eeef"
+
+[[labels]]
+name = "removed"
+color = "ffffff"
+"#
+        .parse()
+        .unwrap();
+        let mut head: LabelConfig = r#"
+delete = ["wontfix", "removed"]
+
+[[labels]]
+name = "Bug"
+color = "d73a49"
+
+[[labels]]
+name = "feature"
+color = "a2eeef"
+description = "New feature"
+
+[[labels]]
+name = "docs"
+color = "0075ca"
+"#
+        .parse()
+        .unwrap();
+
+        let changed = head.changed_since(&base);
+        let mut names: Vec<&str> = changed.iter().map(String::as_str).collect();
+        names.sort();
+        // "Bug" only changed case, which is a change of its definition too
+        assert_eq!(names, vec!["bug", "docs", "feature", "removed"]);
+
+        let unchanged: LabelConfig = toml::to_string(&base).unwrap().parse().unwrap();
+        assert!(unchanged.changed_since(&base).is_empty());
+
+        head.retain_names(&changed);
+        assert_eq!(head.labels.len(), 3);
+        assert_eq!(head.delete, vec!["removed"]);
+    }
+
+    #[test]
+    fn test_name_length_limit() {
+        let config_with = |name: &str| -> LabelConfig {
+            format!("[[labels]]\nname = \"{}\"\ncolor = \"d73a49\"\n", name).parse().unwrap()
+        };
+
+        assert!(config_with(&"a".repeat(MAX_NAME_LENGTH)).validate().is_ok());
+        // The limit counts characters, not bytes
+        assert!(config_with(&"é".repeat(MAX_NAME_LENGTH)).validate().is_ok());
+
+        let err = config_with(&"a".repeat(MAX_NAME_LENGTH + 1)).validate().unwrap_err();
+        assert!(err.to_string().contains("(51 characters)"));
+    }
+
+    #[test]
+    fn test_truncate_names() {
+        let long = format!("{} tail", "x".repeat(MAX_NAME_LENGTH - 1));
+        let mut config: LabelConfig =
+            format!("[[labels]]\nname = \"{}\"\n\n[[labels]]\nname = \"bug\"\n", long).parse().unwrap();
+
+        let truncated = config.truncate_names();
+        // The cut lands right after a space, which is trimmed
+        assert_eq!(truncated, vec![(long, "x".repeat(MAX_NAME_LENGTH - 1))]);
+        assert_eq!(config.labels[1].name, "bug");
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_lowercase_names() {
+        let toml = r#"
+[[labels]]
+name = "Needs-Help"
+update_if_match = ["Help Wanted", "help wanted", "NEEDS-HELP"]
+color = "008672"
+
+[[labels]]
+name = "Bug"
+color = "d73a49"
+"#;
+
+        let mut config: LabelConfig = toml::from_str(toml).unwrap();
+        config.lowercase_names();
+        assert_eq!(config.labels[0].name, "needs-help");
+        assert_eq!(config.labels[0].update_if_match, vec!["help wanted".to_string()]);
+        assert_eq!(config.labels[1].name, "bug");
+    }
 }
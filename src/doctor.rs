@@ -0,0 +1,374 @@
+use crate::auth::AuthStatus;
+use crate::client::GithubClient;
+use crate::error::{BiaoError, Result};
+use crate::models::RepoPermissions;
+use colored::Colorize;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Outcome of a single `biao doctor` check
+#[derive(Debug, PartialEq, Eq)]
+pub enum Status {
+    Pass,
+    Fail,
+    /// Not run because a check it depends on failed
+    Skip,
+}
+
+#[derive(Debug)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub detail: String,
+    /// How to fix a failed check
+    pub hint: Option<&'static str>,
+    /// Whether biao can't work when this check fails; other failures only
+    /// limit what it can do and don't fail `doctor`
+    pub critical: bool,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Check {
+            name,
+            status: Status::Pass,
+            detail: detail.into(),
+            hint: None,
+            critical: true,
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, hint: &'static str) -> Self {
+        Check {
+            name,
+            status: Status::Fail,
+            detail: detail.into(),
+            hint: Some(hint),
+            critical: true,
+        }
+    }
+
+    fn skip(name: &'static str) -> Self {
+        Check {
+            name,
+            status: Status::Skip,
+            detail: "skipped".to_string(),
+            hint: None,
+            critical: true,
+        }
+    }
+
+    /// The same check, not failing `doctor`
+    fn non_critical(self) -> Self {
+        Check { critical: false, ..self }
+    }
+
+    fn passed(&self) -> bool {
+        self.status == Status::Pass
+    }
+}
+
+/// Version from the first line of `gh --version` / `git --version`, e.g. `gh version 2.40.1 (2023-12-13)`
+pub fn parse_version(output: &str) -> Option<String> {
+    output
+        .lines()
+        .next()?
+        .split_whitespace()
+        .find(|word| word.starts_with(|c: char| c.is_ascii_digit()))
+        .map(str::to_string)
+}
+
+pub fn check_gh(version_output: Option<&str>) -> Check {
+    match version_output.and_then(parse_version) {
+        Some(version) => Check::pass("gh installed", format!("gh {}", version)),
+        None => Check::fail(
+            "gh installed",
+            "gh not found",
+            "Install GitHub CLI from https://cli.github.com/",
+        ),
+    }
+}
+
+pub fn check_auth(status: &AuthStatus) -> Check {
+    if !status.authenticated {
+        return Check::fail("gh authenticated", "not logged in", "Run `biao auth login`");
+    }
+
+    let detail = match (&status.login, &status.host) {
+        (Some(login), Some(host)) => format!("{} on {}", login, host),
+        (Some(login), None) => login.clone(),
+        _ => "logged in".to_string(),
+    };
+    Check::pass("gh authenticated", detail)
+}
+
+pub fn check_git(version_output: Option<&str>) -> Check {
+    match version_output.and_then(parse_version) {
+        Some(version) => Check::pass("git installed", format!("git {}", version)),
+        None => Check::fail("git installed", "git not found", "Install git from https://git-scm.com/"),
+    }
+}
+
+pub fn check_repository(root: &Result<PathBuf>) -> Check {
+    match root {
+        Ok(root) => Check::pass("inside a git repository", root.display().to_string()),
+        Err(_) => Check::fail(
+            "inside a git repository",
+            "not a git repository",
+            "Run biao from within a clone of your GitHub repository",
+        ),
+    }
+}
+
+pub fn check_remote(repo: &Result<(String, String, String)>) -> Check {
+    match repo {
+        Ok((host, _, _)) if crate::provider::is_gitlab_host(host) => Check::fail(
+            "origin is a GitHub remote",
+            format!("{} is a GitLab host", host),
+            "doctor checks GitHub setups; GitLab projects only need GITLAB_TOKEN",
+        ),
+        Ok((host, owner, repo)) if host == crate::client::DEFAULT_HOST => {
+            Check::pass("origin is a GitHub remote", format!("{}/{}", owner, repo))
+        }
+        Ok((host, owner, repo)) => {
+            Check::pass("origin is a GitHub remote", format!("{}/{} on {}", owner, repo, host))
+        }
+        Err(e) => Check::fail(
+            "origin is a GitHub remote",
+            e.to_string().lines().next().unwrap_or_default().to_string(),
+            "Point origin at GitHub: git remote set-url origin https://github.com/<owner>/<repo>",
+        ),
+    }
+}
+
+pub fn check_permissions(permissions: &Result<RepoPermissions>) -> Check {
+    match permissions {
+        Ok(p) if p.can_manage_labels() => Check::pass("write access", p.role()),
+        // Listing and exporting labels still work
+        Ok(p) => Check::fail(
+            "write access",
+            format!("{} access only", p.role()),
+            "Managing labels needs write access; ask a maintainer to grant it",
+        )
+        .non_critical(),
+        Err(e) => Check::fail(
+            "write access",
+            e.to_string(),
+            "Check that the repository exists and your gh account can see it",
+        ),
+    }
+}
+
+/// Stdout of a command, if it ran successfully
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// `biao version` output; verbose adds the gh and git versions
+pub fn version_report(verbose: bool) -> String {
+    if !verbose {
+        return format!("biao {}\n", env!("CARGO_PKG_VERSION"));
+    }
+    format_versions(
+        command_output("gh", &["--version"]).as_deref(),
+        command_output("git", &["--version"]).as_deref(),
+    )
+}
+
+/// biao, gh and git versions, from the output of `gh --version` and `git --version`
+pub fn format_versions(gh_output: Option<&str>, git_output: Option<&str>) -> String {
+    let version = |output: Option<&str>| {
+        output
+            .and_then(parse_version)
+            .unwrap_or_else(|| "not found".to_string())
+    };
+    format!(
+        "biao {}\ngh   {}\ngit  {}\nos   {} ({})\n",
+        env!("CARGO_PKG_VERSION"),
+        version(gh_output),
+        version(git_output),
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    )
+}
+
+/// Run every check, skipping the ones whose prerequisites failed
+pub async fn run_checks() -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    let gh = check_gh(command_output("gh", &["--version"]).as_deref());
+    let gh_ok = gh.passed();
+    checks.push(gh);
+
+    let auth = if gh_ok {
+        match crate::auth::auth_status() {
+            Ok(status) => check_auth(&status),
+            Err(e) => Check::fail("gh authenticated", e.to_string(), "Run `biao auth login`"),
+        }
+    } else {
+        Check::skip("gh authenticated")
+    };
+    let auth_ok = auth.passed();
+    checks.push(auth);
+
+    let git = check_git(command_output("git", &["--version"]).as_deref());
+    let git_ok = git.passed();
+    checks.push(git);
+
+    let mut repo = None;
+    if git_ok {
+        let root = check_repository(&crate::git::find_git_root());
+        let root_ok = root.passed();
+        checks.push(root);
+
+        if root_ok {
+            let info = crate::git::get_repo_info();
+            let remote = check_remote(&info);
+            if remote.passed() {
+                repo = info.ok();
+            }
+            checks.push(remote);
+        } else {
+            checks.push(Check::skip("origin is a GitHub remote"));
+        }
+    } else {
+        checks.push(Check::skip("inside a git repository"));
+        checks.push(Check::skip("origin is a GitHub remote"));
+    }
+
+    match repo {
+        Some((host, owner, repo)) if auth_ok => {
+            let mut client = GithubClient::new(owner, repo);
+            client.set_host(host);
+            checks.push(check_permissions(&client.repo_permissions().await));
+        }
+        _ => checks.push(Check::skip("write access")),
+    }
+
+    checks
+}
+
+/// Print the checklist; fails if any check failed
+pub fn report(checks: &[Check]) -> Result<()> {
+    for check in checks {
+        match check.status {
+            Status::Pass => println!("{} {} ({})", "✓".green(), check.name, check.detail.dimmed()),
+            Status::Fail if check.critical => println!("{} {}: {}", "✗".red(), check.name, check.detail),
+            Status::Fail => println!("{} {}: {}", "!".yellow(), check.name, check.detail),
+            Status::Skip => println!("{} {} ({})", "-".dimmed(), check.name.dimmed(), check.detail.dimmed()),
+        }
+        if let (Status::Fail, Some(hint)) = (&check.status, check.hint) {
+            println!("    {}", hint.yellow());
+        }
+    }
+
+    let failed = checks.iter().filter(|c| c.status == Status::Fail && c.critical).count();
+    if failed == 0 {
+        Ok(())
+    } else {
+        Err(BiaoError::InvalidInput(format!("{} check(s) failed", failed)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(
+            parse_version("gh version 2.40.1 (2023-12-13)\nhttps://github.com/cli/cli/releases/tag/v2.40.1\n"),
+            Some("2.40.1".to_string())
+        );
+        assert_eq!(parse_version("git version 2.43.0"), Some("2.43.0".to_string()));
+        assert_eq!(parse_version(""), None);
+    }
+
+    #[test]
+    fn test_format_versions() {
+        let report = format_versions(Some("gh version 2.40.1 (2023-12-13)\nhttps://github.com/cli/cli\n"), None);
+        assert!(report.starts_with(&format!("biao {}\n", env!("CARGO_PKG_VERSION"))));
+        assert!(report.contains("gh   2.40.1\n"));
+        assert!(report.contains("git  not found\n"));
+    }
+
+    #[test]
+    fn test_check_tools() {
+        assert!(check_gh(Some("gh version 2.40.1 (2023-12-13)")).passed());
+        assert!(!check_gh(None).passed());
+        assert!(check_git(Some("git version 2.43.0")).passed());
+        assert_eq!(check_git(None).status, Status::Fail);
+    }
+
+    #[test]
+    fn test_check_auth() {
+        let status = AuthStatus {
+            authenticated: true,
+            login: Some("octocat".to_string()),
+            host: Some("github.com".to_string()),
+            scopes: vec![],
+        };
+        let check = check_auth(&status);
+        assert!(check.passed());
+        assert_eq!(check.detail, "octocat on github.com");
+
+        let check = check_auth(&AuthStatus::default());
+        assert!(!check.passed());
+        assert!(check.hint.unwrap().contains("biao auth login"));
+    }
+
+    #[test]
+    fn test_check_repository_and_remote() {
+        assert!(check_repository(&Ok(PathBuf::from("/src/biao"))).passed());
+        assert!(!check_repository(&Err(BiaoError::InvalidInput("no".to_string()))).passed());
+
+        let remote = |host: &str| Ok((host.to_string(), "o".to_string(), "r".to_string()));
+        assert_eq!(check_remote(&remote("github.com")).detail, "o/r");
+        assert_eq!(check_remote(&remote("github.acme.com")).detail, "o/r on github.acme.com");
+        assert!(!check_remote(&remote("gitlab.com")).passed());
+        let check = check_remote(&Err(BiaoError::InvalidInput(
+            "git.example.org is not a GitHub host.\nRemote URL: https://git.example.org/o/r".to_string(),
+        )));
+        assert_eq!(check.detail, "Invalid input: git.example.org is not a GitHub host.");
+    }
+
+    #[test]
+    fn test_check_permissions() {
+        let write = RepoPermissions {
+            push: true,
+            pull: true,
+            ..Default::default()
+        };
+        assert!(check_permissions(&Ok(write)).passed());
+
+        let read = RepoPermissions {
+            pull: true,
+            ..Default::default()
+        };
+        let check = check_permissions(&Ok(read));
+        assert!(!check.passed());
+        assert_eq!(check.detail, "read access only");
+    }
+
+    #[test]
+    fn test_report_fails_on_failed_check() {
+        let checks = vec![check_gh(Some("gh version 2.40.1")), Check::skip("gh authenticated")];
+        assert!(report(&checks).is_ok());
+
+        let checks = vec![check_gh(None), Check::skip("gh authenticated")];
+        assert!(report(&checks).is_err());
+
+        // Read-only access is reported but doesn't fail doctor
+        let read = RepoPermissions {
+            pull: true,
+            ..Default::default()
+        };
+        let checks = vec![check_gh(Some("gh version 2.40.1")), check_permissions(&Ok(read))];
+        assert!(!checks[1].critical);
+        assert!(report(&checks).is_ok());
+    }
+}
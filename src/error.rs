@@ -6,7 +6,43 @@ pub enum BiaoError {
     GhNotFound { message: String },
     
     #[error("gh CLI error: {message}")]
-    GhError { message: String },
+    GhError {
+        message: String,
+        /// HTTP status of the failed request, when gh reported one
+        status: Option<u16>,
+        /// Machine-readable error code from the response body, e.g. `already_exists`
+        code: Option<String>,
+    },
+    
+    #[error("GitLab API error: {message}")]
+    GitlabError {
+        message: String,
+        status: Option<u16>,
+    },
+
+    #[error("GitHub secondary rate limit exceeded, retry after {}s", retry_after.as_secs())]
+    SecondaryRateLimited { retry_after: std::time::Duration },
+
+    #[error("GitHub API rate limit exceeded{}", reset_at.map(|t| format!(", resets at {} (Unix time)", t)).unwrap_or_default())]
+    RateLimited {
+        /// `X-RateLimit-Reset` of the response, in seconds since the Unix epoch
+        reset_at: Option<u64>,
+    },
+
+    #[error("{resource} not found")]
+    NotFound { resource: String },
+
+    #[error("Label '{name}' already exists")]
+    AlreadyExists { name: String },
+
+    #[error("Repository {owner}/{repo} not found, or you don't have access to it")]
+    RepoNotFound { owner: String, repo: String },
+
+    #[error("Not authenticated with GitHub. Run `biao auth login`.")]
+    Unauthorized,
+
+    #[error("{failed} operation(s) failed")]
+    PartialFailure { failed: usize },
     
     #[error("JSON parse error: {message}")]
     ParseError { message: String },
@@ -14,8 +50,69 @@ pub enum BiaoError {
     #[error("Invalid input: {0}")]
     InvalidInput(String),
     
+    #[error("{0} difference(s) between the config and the repository")]
+    Drift(usize),
+
+    #[error("--verify: {0} label(s) don't match the config after applying")]
+    Unverified(usize),
+    
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
 
+/// Some label operations failed, or `--exit-code` found differences
+pub const EXIT_FAILED_OPERATIONS: u8 = 1;
+/// Invalid arguments or config; clap exits with the same code for usage errors
+pub const EXIT_USAGE: u8 = 2;
+/// Nothing could be done: GitHub, authentication, network or file errors
+pub const EXIT_ERROR: u8 = 3;
+
+/// Messages of gh and curl failing to reach GitHub at all
+const CONNECTION_ERRORS: &[&str] = &[
+    "error connecting to",
+    "could not resolve host",
+    "failed to connect",
+    "connection refused",
+    "connection reset",
+    "timed out",
+    "timeout",
+    "tls handshake",
+    "unexpected eof",
+];
+
+impl BiaoError {
+    /// Whether the request never got an HTTP response (network blip, DNS, timeout)
+    pub fn is_connection_error(&self) -> bool {
+        match self {
+            BiaoError::GhError { status: None, message, .. } => {
+                let message = message.to_lowercase();
+                CONNECTION_ERRORS.iter().any(|marker| message.contains(marker))
+            }
+            _ => false,
+        }
+    }
+
+    /// Process exit code for this error, as listed under "Exit codes" in `biao --help`
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            BiaoError::PartialFailure { .. } | BiaoError::Drift(_) | BiaoError::Unverified(_) => {
+                EXIT_FAILED_OPERATIONS
+            }
+            BiaoError::InvalidInput(_) => EXIT_USAGE,
+            _ => EXIT_ERROR,
+        }
+    }
+
+    /// HTTP status behind a GitHub API error, if it came from one
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            BiaoError::GhError { status, .. } | BiaoError::GitlabError { status, .. } => *status,
+            BiaoError::NotFound { .. } | BiaoError::RepoNotFound { .. } => Some(404),
+            BiaoError::AlreadyExists { .. } => Some(422),
+            BiaoError::Unauthorized => Some(401),
+            _ => None,
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, BiaoError>;
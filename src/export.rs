@@ -0,0 +1,255 @@
+use crate::config::{Label, LabelConfig};
+use crate::models::GithubLabel;
+use std::collections::HashMap;
+
+/// Build a config reproducing the given repository labels.
+///
+/// In template mode, labels that look like variants of each other (see
+/// [`similarity_key`]) are folded into a single label whose `update_if_match`
+/// lists the other names, so applying the template elsewhere consolidates them.
+pub fn export_config(labels: &[GithubLabel], template: bool) -> LabelConfig {
+    let mut exported: Vec<Label> = Vec::new();
+    let mut groups: HashMap<String, usize> = HashMap::new();
+
+    for label in labels {
+        if template {
+            let key = similarity_key(&label.name);
+            if let Some(&index) = groups.get(&key) {
+                exported[index].update_if_match.push(label.name.clone());
+                continue;
+            }
+            groups.insert(key, exported.len());
+        }
+
+        exported.push(Label::from(label));
+    }
+
+    LabelConfig {
+        labels: exported,
+        ..Default::default()
+    }
+}
+
+/// Carry over to an export what the hand-written config it replaces adds to
+/// the labels themselves: title, defaults, delete list, label groups and the
+/// `update_if_match`, `skip_if_exists` and `update_if_exists` of labels still
+/// there. Labels a kept group defines aren't exported a second time.
+pub fn keep_hand_written(exported: &mut LabelConfig, existing: LabelConfig) {
+    let grouped: Vec<String> = existing
+        .label_groups
+        .iter()
+        .flat_map(|group| group.names.iter().map(move |name| format!("{}{}", group.prefix, name)))
+        .collect();
+    exported
+        .labels
+        .retain(|label| !grouped.iter().any(|name| name.eq_ignore_ascii_case(&label.name)));
+
+    for label in &mut exported.labels {
+        let Some(written) = existing.labels.iter().find(|w| w.name.eq_ignore_ascii_case(&label.name)) else {
+            continue;
+        };
+        if label.update_if_match.is_empty() {
+            label.update_if_match = written.update_if_match.clone();
+        }
+        label.skip_if_exists = written.skip_if_exists;
+        label.update_if_exists = written.update_if_exists;
+    }
+
+    exported.name = existing.name;
+    exported.description = existing.description;
+    exported.default_color = existing.default_color;
+    exported.default_description = existing.default_description;
+    exported.delete = existing.delete;
+    exported.label_groups = existing.label_groups;
+}
+
+/// Config giving a repository the labels of `source` merged with its own `target` labels.
+///
+/// Labels are matched by name case-insensitively. Where both define a label
+/// differently, the source's color and description win and a warning
+/// describing the conflict is returned. Every label updates an existing one.
+pub fn union_config(source: &[GithubLabel], target: &[GithubLabel]) -> (LabelConfig, Vec<String>) {
+    let mut config = export_config(source, false);
+    let mut conflicts = Vec::new();
+
+    for label in target {
+        match source.iter().find(|s| s.name.eq_ignore_ascii_case(&label.name)) {
+            Some(kept) if !same_label(kept, label) => conflicts.push(format!(
+                "'{}' differs between the repositories (#{} vs #{}), keeping #{} from the source",
+                kept.name, kept.color, label.color, kept.color
+            )),
+            Some(_) => {}
+            None => config.labels.push(Label::from(label)),
+        }
+    }
+
+    for label in &mut config.labels {
+        label.update_if_exists = true;
+    }
+    (config, conflicts)
+}
+
+/// Config making a repository with `target` labels match the `source` repository.
+///
+/// Source labels update existing ones, or are left alone with `skip_existing`.
+/// With `prune`, target labels the source doesn't have are deleted.
+pub fn sync_config(source: &[GithubLabel], target: &[GithubLabel], skip_existing: bool, prune: bool) -> LabelConfig {
+    let mut config = export_config(source, false);
+    for label in &mut config.labels {
+        label.update_if_exists = !skip_existing;
+        label.skip_if_exists = skip_existing;
+    }
+    if prune {
+        config.delete = crate::plan::prunable(&config, target, false)
+            .into_iter()
+            .map(|label| label.name.clone())
+            .collect();
+    }
+    config
+}
+
+fn same_label(a: &GithubLabel, b: &GithubLabel) -> bool {
+    a.color.eq_ignore_ascii_case(&b.color)
+        && a.description.as_deref().unwrap_or("") == b.description.as_deref().unwrap_or("")
+}
+
+/// Fuzzy-matching key for a label name.
+///
+/// Names that only differ by case, separators (`-`, `_`, space), a scoped
+/// prefix (`type/bug`) or a short Rust-style prefix (`C-bug`, `T-bug`) map to
+/// the same key.
+pub fn similarity_key(name: &str) -> String {
+    let name = name.trim().to_lowercase();
+
+    let name = match name.rsplit_once('/') {
+        Some((_, rest)) if !rest.is_empty() => rest.to_string(),
+        _ => name,
+    };
+
+    let name = match name.split_once('-') {
+        Some((prefix, rest))
+            if !rest.is_empty() && prefix.len() <= 2 && prefix.chars().all(|c| c.is_ascii_alphabetic()) =>
+        {
+            rest.to_string()
+        }
+        _ => name,
+    };
+
+    name.chars()
+        .filter(|c| !matches!(c, '-' | '_' | ' '))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remote(name: &str) -> GithubLabel {
+        GithubLabel {
+            name: name.to_string(),
+            color: "d73a49".to_string(),
+            description: None,
+            url: String::new(),
+            id: 1,
+            node_id: String::new(),
+            default: false,
+        }
+    }
+
+    #[test]
+    fn test_keep_hand_written() {
+        let existing = LabelConfig::parse_unexpanded(
+            r#"
+name = "Core"
+delete = ["wontfix"]
+
+[[labels]]
+name = "bug"
+color = "ffffff"
+update_if_exists = true
+update_if_match = ["defect"]
+
+[[label_group]]
+prefix = "A-"
+names = ["parser"]
+"#,
+        )
+        .unwrap();
+        let mut exported = export_config(&[remote("Bug"), remote("A-parser"), remote("docs")], false);
+
+        keep_hand_written(&mut exported, existing);
+        let names: Vec<&str> = exported.labels.iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(names, vec!["Bug", "docs"]);
+        assert!(exported.labels[0].update_if_exists);
+        assert_eq!(exported.labels[0].update_if_match, vec!["defect"]);
+        assert_eq!(exported.labels[0].color.as_deref(), Some("d73a49"));
+        assert!(!exported.labels[1].update_if_exists);
+        assert_eq!(exported.name.as_deref(), Some("Core"));
+        assert_eq!(exported.delete, vec!["wontfix"]);
+        assert_eq!(exported.label_groups.len(), 1);
+    }
+
+    #[test]
+    fn test_similarity_key() {
+        assert_eq!(similarity_key("Bug"), "bug");
+        assert_eq!(similarity_key("type/bug"), "bug");
+        assert_eq!(similarity_key("C-bug"), "bug");
+        assert_eq!(similarity_key("good first issue"), similarity_key("good-first-issue"));
+        assert_ne!(similarity_key("help wanted"), similarity_key("bug"));
+    }
+
+    #[test]
+    fn test_template_groups_case_variants() {
+        let labels = vec![remote("bug"), remote("Bug"), remote("BUG"), remote("feature")];
+
+        let config = export_config(&labels, true);
+        assert_eq!(config.labels.len(), 2);
+        assert_eq!(config.labels[0].name, "bug");
+        assert_eq!(config.labels[0].update_if_match, vec!["Bug".to_string(), "BUG".to_string()]);
+        assert!(config.labels[1].update_if_match.is_empty());
+
+        let plain = export_config(&labels, false);
+        assert_eq!(plain.labels.len(), 4);
+    }
+
+    #[test]
+    fn test_union_prefers_source_on_conflict() {
+        let source = vec![remote("bug"), remote("feature")];
+        let mut target_bug = remote("Bug");
+        target_bug.color = "ee0701".to_string();
+        let target = vec![target_bug, remote("docs")];
+
+        let (config, conflicts) = union_config(&source, &target);
+        let names: Vec<&str> = config.labels.iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(names, vec!["bug", "feature", "docs"]);
+        assert_eq!(config.labels[0].color.as_deref(), Some("d73a49"));
+        assert!(config.labels.iter().all(|l| l.update_if_exists));
+        assert_eq!(conflicts.len(), 1);
+        assert!(conflicts[0].contains("'bug'") && conflicts[0].contains("#ee0701"));
+    }
+
+    #[test]
+    fn test_sync_config() {
+        let source = vec![remote("bug"), remote("feature")];
+        let target = vec![remote("Bug"), remote("wontfix")];
+
+        let config = sync_config(&source, &target, false, false);
+        assert!(config.labels.iter().all(|l| l.update_if_exists && !l.skip_if_exists));
+        assert!(config.delete.is_empty());
+
+        let config = sync_config(&source, &target, true, true);
+        assert!(config.labels.iter().all(|l| l.skip_if_exists && !l.update_if_exists));
+        assert_eq!(config.delete, vec!["wontfix".to_string()]);
+    }
+
+    #[test]
+    fn test_export_serializes_to_config_toml() {
+        let labels = vec![remote("bug"), remote("Bug")];
+        let content = toml::to_string_pretty(&export_config(&labels, true)).unwrap();
+        assert!(content.contains("update_if_match"));
+        assert!(!content.contains("skip_if_exists"));
+
+        let parsed: LabelConfig = toml::from_str(&content).unwrap();
+        assert_eq!(parsed.labels[0].update_if_match, vec!["Bug".to_string()]);
+    }
+}
@@ -0,0 +1,150 @@
+use crate::config::LabelConfig;
+
+/// Match `text` against a glob pattern where `*` matches any run of
+/// characters and `?` a single character. Label names are compared
+/// case-insensitively, like on GitHub.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text index it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                // Let the last `*` swallow one more character
+                Some((star, start)) => {
+                    p = star + 1;
+                    t = start + 1;
+                    backtrack = Some((star, start + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Whether a label matches a `list --filter` query: a glob (with `*` or `?`)
+/// against its name, otherwise a case-insensitive substring of its name or description
+pub fn query_match(query: &str, name: &str, description: Option<&str>) -> bool {
+    if query.contains(['*', '?']) {
+        return glob_match(query, name);
+    }
+    let query = query.to_lowercase();
+    name.to_lowercase().contains(&query)
+        || description.is_some_and(|description| description.to_lowercase().contains(&query))
+}
+
+/// Include/exclude globs selecting which labels a command touches
+#[derive(Debug, Clone, Default)]
+pub struct LabelFilter {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl LabelFilter {
+    /// A name is selected if it matches any include (or there are none) and no exclude
+    pub fn matches(&self, name: &str) -> bool {
+        let included = self.include.is_empty() || self.include.iter().any(|g| glob_match(g, name));
+        included && !self.exclude.iter().any(|g| glob_match(g, name))
+    }
+
+    /// Drop labels and deletions the filter does not select
+    pub fn apply(&self, config: &mut LabelConfig) {
+        config.labels.retain(|label| self.matches(&label.name));
+        config.delete.retain(|name| self.matches(name));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan::Plan;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("A-*", "A-cli"));
+        assert!(glob_match("a-*", "A-cli"));
+        assert!(glob_match("*bug*", "type/bug-report"));
+        assert!(glob_match("P-?", "P-1"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("A-*", "area/cli"));
+        assert!(!glob_match("P-?", "P-10"));
+        assert!(!glob_match("bug", "bugs"));
+    }
+
+    #[test]
+    fn test_query_match() {
+        assert!(query_match("bug", "type/Bug-report", None));
+        assert!(query_match("BROKEN", "bug", Some("Something is broken")));
+        assert!(!query_match("docs", "bug", Some("Something is broken")));
+        // Globs only look at the name
+        assert!(query_match("A-*", "A-cli", None));
+        assert!(!query_match("*broken*", "bug", Some("Something is broken")));
+    }
+
+    fn planned_names(filter: &LabelFilter) -> Vec<String> {
+        let mut config: LabelConfig = r#"
+delete = ["A-legacy", "wontfix"]
+
+[[labels]]
+name = "A-cli"
+color = "0075ca"
+
+[[labels]]
+name = "A-docs"
+color = "0075ca"
+
+[[labels]]
+name = "bug"
+color = "d73a49"
+"#
+        .parse()
+        .unwrap();
+        filter.apply(&mut config);
+
+        Plan::build(&config, &[], false)
+            .unwrap()
+            .operations
+            .into_iter()
+            .map(|op| op.name)
+            .collect()
+    }
+
+    fn filter(include: &[&str], exclude: &[&str]) -> LabelFilter {
+        LabelFilter {
+            include: include.iter().map(|g| g.to_string()).collect(),
+            exclude: exclude.iter().map(|g| g.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_include_only() {
+        assert_eq!(planned_names(&filter(&["A-*"], &[])), vec!["A-cli", "A-docs", "A-legacy"]);
+    }
+
+    #[test]
+    fn test_exclude_only() {
+        assert_eq!(planned_names(&filter(&[], &["A-*"])), vec!["bug", "wontfix"]);
+    }
+
+    #[test]
+    fn test_include_and_exclude() {
+        assert_eq!(
+            planned_names(&filter(&["A-*", "bug"], &["*-docs", "*legacy"])),
+            vec!["A-cli", "bug"]
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use crate::error::{BiaoError, Result};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 /// Find the root of the git repository by searching up from current directory
@@ -31,13 +31,83 @@ pub fn find_git_root() -> Result<PathBuf> {
     Ok(PathBuf::from(path))
 }
 
-/// Extract owner and repo from git remote URL
-/// Supports:
+/// Check whether a file inside the repository has uncommitted changes
+/// (modified, staged, or untracked) using `git status --porcelain`
+pub fn has_uncommitted_changes(path: &Path) -> Result<bool> {
+    let root = find_git_root()?;
+    let path = path.canonicalize()?;
+    let root = root.canonicalize()?;
+    if !path.starts_with(&root) {
+        return Err(BiaoError::InvalidInput(format!(
+            "{} is outside the repository; --require-clean only applies to files inside it",
+            path.display()
+        )));
+    }
+
+    let output = Command::new("git")
+        .args(["status", "--porcelain", "--"])
+        .arg(&path)
+        .current_dir(&root)
+        .output()
+        .map_err(BiaoError::Io)?;
+
+    if !output.status.success() {
+        return Err(BiaoError::InvalidInput(format!(
+            "git status failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(porcelain_is_dirty(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Content of `path` as of `git_ref`, or `None` if the file did not exist there
+pub fn file_at_ref(git_ref: &str, path: &Path) -> Result<Option<String>> {
+    let root = find_git_root()?.canonicalize()?;
+    let path = path.canonicalize()?;
+    let relative = path.strip_prefix(&root).map_err(|_| {
+        BiaoError::InvalidInput(format!(
+            "{} is outside the repository; --since-commit only applies to files inside it",
+            path.display()
+        ))
+    })?;
+
+    // Resolve the ref on its own first so a typo is an error, not a missing file
+    let resolved = Command::new("git")
+        .args(["rev-parse", "--verify", "--quiet"])
+        .arg(format!("{}^{{commit}}", git_ref))
+        .current_dir(&root)
+        .output()
+        .map_err(BiaoError::Io)?;
+    if !resolved.status.success() {
+        return Err(BiaoError::InvalidInput(format!("Unknown git ref '{}'", git_ref)));
+    }
+
+    let spec = format!("{}:{}", git_ref, relative.to_string_lossy().replace('\\', "/"));
+    let output = Command::new("git")
+        .args(["show", &spec])
+        .current_dir(&root)
+        .output()
+        .map_err(BiaoError::Io)?;
+
+    if !output.status.success() {
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8_lossy(&output.stdout).into_owned()))
+}
+
+/// Any entry in porcelain output means the path differs from HEAD
+fn porcelain_is_dirty(output: &str) -> bool {
+    output.lines().any(|line| !line.trim().is_empty())
+}
+
+/// Extract host, owner and repo from git remote URL
+/// Supports, on github.com or a GitHub Enterprise host:
 /// - https://github.com/owner/repo.git
 /// - git@github.com:owner/repo.git
 /// - https://github.com/owner/repo
 /// - git@github.com:owner/repo
-pub fn get_repo_info() -> Result<(String, String)> {
+pub fn get_repo_info() -> Result<(String, String, String)> {
     let output = Command::new("git")
         .args(["config", "--get", "remote.origin.url"])
         .output()
@@ -54,26 +124,121 @@ pub fn get_repo_info() -> Result<(String, String)> {
         .trim()
         .to_string();
 
-    parse_github_url(&url)
+    parse_remote_url(&url)
 }
 
-fn parse_github_url(url: &str) -> Result<(String, String)> {
-    // Handle https://github.com/owner/repo.git
-    if let Some(path) = url.strip_prefix("https://github.com/") {
-        return extract_owner_repo(path);
+/// Parse an `owner/name` repository argument.
+///
+/// Values pasted from a browser or `git remote` are accepted too: a trailing
+/// slash, a `.git` suffix, or a full HTTPS/SSH URL on the host gh is
+/// configured for.
+pub fn parse_repo_slug(slug: &str) -> Result<(String, String)> {
+    let trimmed = slug.trim().trim_end_matches('/');
+    if is_url(trimmed) {
+        let (host, owner, repo) = parse_remote_url(trimmed)?;
+        let configured = crate::client::configured_host();
+        if host != configured {
+            return Err(BiaoError::InvalidInput(format!(
+                "'{}' is on {}, but gh is set up for {}. Set GH_HOST={} to work with it.",
+                slug, host, configured, host
+            )));
+        }
+        return Ok((owner, repo));
     }
 
-    // Handle git@github.com:owner/repo.git
-    if let Some(path) = url.strip_prefix("git@github.com:") {
-        return extract_owner_repo(path);
+    let trimmed = trimmed.strip_suffix(".git").unwrap_or(trimmed);
+    match trimmed.split_once('/') {
+        Some((owner, repo)) if !owner.is_empty() && !repo.is_empty() && !repo.contains('/') => {
+            Ok((owner.to_string(), repo.to_string()))
+        }
+        _ => Err(BiaoError::InvalidInput(format!(
+            "Invalid repository '{}'. Expected the form owner/name.",
+            slug
+        ))),
     }
+}
+
+/// Parse a `--repo` argument like [`parse_repo_slug`], except that a pasted
+/// URL keeps its host, so the repository is looked up where it lives
+pub fn parse_repo_arg(slug: &str) -> Result<(Option<String>, String, String)> {
+    let trimmed = slug.trim().trim_end_matches('/');
+    if is_url(trimmed) {
+        let (host, owner, repo) = parse_remote_url(trimmed)?;
+        return Ok((Some(host), owner, repo));
+    }
+    parse_repo_slug(slug).map(|(owner, repo)| (None, owner, repo))
+}
 
-    Err(BiaoError::InvalidInput(
-        format!(
+fn is_url(text: &str) -> bool {
+    text.contains("://") || text.starts_with("git@")
+}
+
+/// Host, owner and repo of a remote URL on a host biao can talk to: GitHub
+/// (see [`is_github_host`]) or GitLab
+fn parse_remote_url(url: &str) -> Result<(String, String, String)> {
+    let (host, owner, repo) = parse_github_url(url)?;
+    if is_github_host(&host) || crate::provider::is_gitlab_host(&host) {
+        return Ok((host, owner, repo));
+    }
+    Err(BiaoError::InvalidInput(format!(
+        "{} is not a GitHub host. For GitHub Enterprise, log in with `gh auth login --hostname {}` or set GH_HOST={}.\nRemote URL: {}",
+        host, host, host, url
+    )))
+}
+
+/// Whether `host` is GitHub: github.com, `GH_HOST`, or a host gh is logged in to
+pub fn is_github_host(host: &str) -> bool {
+    host.eq_ignore_ascii_case(crate::client::DEFAULT_HOST)
+        || host.eq_ignore_ascii_case(&crate::client::configured_host())
+        || gh_hosts().iter().any(|known| known.eq_ignore_ascii_case(host))
+}
+
+/// Hosts in gh's `hosts.yml`, i.e. those `gh auth login` was run for
+fn gh_hosts() -> Vec<String> {
+    let dir = std::env::var_os("GH_CONFIG_DIR")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("XDG_CONFIG_HOME").map(|dir| PathBuf::from(dir).join("gh")))
+        .or_else(|| std::env::var_os("HOME").map(|dir| PathBuf::from(dir).join(".config").join("gh")));
+    dir.and_then(|dir| std::fs::read_to_string(dir.join("hosts.yml")).ok())
+        .map(|content| parse_gh_hosts(&content))
+        .unwrap_or_default()
+}
+
+/// Top-level keys of a `hosts.yml`, one per host
+fn parse_gh_hosts(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter(|line| !line.starts_with([' ', '\t', '#', '-']))
+        .filter_map(|line| line.trim_end().strip_suffix(':'))
+        .map(|host| host.trim_matches(['"', '\'']).to_string())
+        .collect()
+}
+
+/// Host, owner and repo of a `https://<host>/<owner>/<repo>` or
+/// `git@<host>:<owner>/<repo>` URL, on any host
+fn parse_github_url(url: &str) -> Result<(String, String, String)> {
+    let location = url
+        .strip_prefix("https://")
+        .and_then(|rest| rest.split_once('/'))
+        .or_else(|| url.strip_prefix("git@").and_then(|rest| rest.split_once(':')));
+
+    match location {
+        Some((host, path)) if !host.is_empty() => {
+            // The project id of a GitLab subgroup project needs the whole namespace
+            if crate::provider::is_gitlab_host(host) && path.trim_end_matches(".git").split('/').count() > 2 {
+                return Err(BiaoError::InvalidInput(format!(
+                    "Projects in GitLab subgroups aren't supported yet.\nRemote URL: {}",
+                    url
+                )));
+            }
+            let (owner, repo) = extract_owner_repo(path)?;
+            Ok((host.to_string(), owner, repo))
+        }
+        _ => Err(BiaoError::InvalidInput(format!(
             "Unsupported remote URL. Only GitHub HTTPS and SSH URLs are supported.\nRemote URL: {}",
             url
-        ),
-    ))
+        ))),
+    }
 }
 
 fn extract_owner_repo(path: &str) -> Result<(String, String)> {
@@ -97,29 +262,99 @@ mod tests {
 
     #[test]
     fn test_parse_https_url() {
-        let (owner, repo) = parse_github_url("https://github.com/cli/cli.git").unwrap();
+        let (host, owner, repo) = parse_github_url("https://github.com/cli/cli.git").unwrap();
+        assert_eq!(host, "github.com");
         assert_eq!(owner, "cli");
         assert_eq!(repo, "cli");
     }
 
     #[test]
     fn test_parse_https_url_no_git() {
-        let (owner, repo) = parse_github_url("https://github.com/cli/cli").unwrap();
+        let (host, owner, repo) = parse_github_url("https://github.com/cli/cli").unwrap();
+        assert_eq!(host, "github.com");
         assert_eq!(owner, "cli");
         assert_eq!(repo, "cli");
     }
 
     #[test]
     fn test_parse_ssh_url() {
-        let (owner, repo) = parse_github_url("git@github.com:cli/cli.git").unwrap();
+        let (host, owner, repo) = parse_github_url("git@github.com:cli/cli.git").unwrap();
+        assert_eq!(host, "github.com");
         assert_eq!(owner, "cli");
         assert_eq!(repo, "cli");
     }
 
     #[test]
     fn test_parse_ssh_url_no_git() {
-        let (owner, repo) = parse_github_url("git@github.com:cli/cli").unwrap();
+        let (host, owner, repo) = parse_github_url("git@github.com:cli/cli").unwrap();
+        assert_eq!(host, "github.com");
         assert_eq!(owner, "cli");
         assert_eq!(repo, "cli");
     }
+
+    #[test]
+    fn test_parse_enterprise_urls() {
+        let expected = ("github.acme.com".to_string(), "team".to_string(), "tool".to_string());
+        assert_eq!(parse_github_url("https://github.acme.com/team/tool.git").unwrap(), expected);
+        assert_eq!(parse_github_url("https://github.acme.com/team/tool").unwrap(), expected);
+        assert_eq!(parse_github_url("git@github.acme.com:team/tool.git").unwrap(), expected);
+        assert_eq!(parse_github_url("git@github.acme.com:team/tool").unwrap(), expected);
+        assert!(parse_github_url("https://github.acme.com/team").is_err());
+        let err = parse_github_url("git@gitlab.com:group/subgroup/project.git").unwrap_err();
+        assert!(err.to_string().contains("subgroups aren't supported"));
+        assert!(parse_github_url("https://github.com/cli/cli/issues").is_ok());
+        assert!(parse_github_url("ftp://github.acme.com/team/tool").is_err());
+    }
+
+    #[test]
+    fn test_parse_repo_slug() {
+        assert_eq!(
+            parse_repo_slug("cli/cli").unwrap(),
+            ("cli".to_string(), "cli".to_string())
+        );
+        assert!(parse_repo_slug("cli").is_err());
+        assert!(parse_repo_slug("/cli").is_err());
+        assert!(parse_repo_slug("a/b/c").is_err());
+    }
+
+    #[test]
+    fn test_parse_pasted_repo_slug() {
+        let expected = ("cli".to_string(), "cli".to_string());
+        assert_eq!(parse_repo_slug("cli/cli/").unwrap(), expected);
+        assert_eq!(parse_repo_slug("cli/cli.git").unwrap(), expected);
+        assert_eq!(parse_repo_slug("https://github.com/cli/cli").unwrap(), expected);
+        assert_eq!(parse_repo_slug("https://github.com/cli/cli/").unwrap(), expected);
+        assert_eq!(parse_repo_slug("git@github.com:cli/cli.git").unwrap(), expected);
+        assert!(parse_repo_slug("https://gitlab.com/cli/cli").is_err());
+    }
+
+    #[test]
+    fn test_parse_repo_arg_keeps_host() {
+        assert_eq!(
+            parse_repo_arg("cli/cli").unwrap(),
+            (None, "cli".to_string(), "cli".to_string())
+        );
+        assert_eq!(
+            parse_repo_arg("https://gitlab.com/team/tool").unwrap(),
+            (Some("gitlab.com".to_string()), "team".to_string(), "tool".to_string())
+        );
+        let err = parse_repo_arg("https://git.example.org/team/tool").unwrap_err().to_string();
+        assert!(err.contains("not a GitHub host") && err.contains("--hostname git.example.org"));
+    }
+
+    #[test]
+    fn test_parse_gh_hosts() {
+        let hosts = "github.com:\n    user: octocat\n    git_protocol: https\n\"ghe.acme.com\":\n    users:\n        - hubot\n";
+        assert_eq!(parse_gh_hosts(hosts), vec!["github.com", "ghe.acme.com"]);
+        assert!(parse_gh_hosts("").is_empty());
+    }
+
+    #[test]
+    fn test_porcelain_is_dirty() {
+        assert!(!porcelain_is_dirty(""));
+        assert!(!porcelain_is_dirty("\n"));
+        assert!(porcelain_is_dirty(" M labels.toml\n"));
+        assert!(porcelain_is_dirty("M  labels.toml\n"));
+        assert!(porcelain_is_dirty("?? labels.toml\n"));
+    }
 }
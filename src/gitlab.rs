@@ -0,0 +1,358 @@
+use crate::client::{curl_quote, log_call, log_response, percent_encode, run_curl, GhOutput, GhResponse};
+use crate::error::{BiaoError, Result};
+use crate::models::{CreateLabelRequest, GithubLabel, UpdateLabelRequest};
+use crate::provider::{BoxFuture, LabelProvider};
+use serde::Deserialize;
+
+/// Page size used when listing a project's labels
+const LABELS_PER_PAGE: usize = 100;
+
+/// Sends the request described by a `curl --config`, returning `curl --include` output
+pub trait CurlRunner: Send + Sync {
+    fn run(&self, config: &str) -> std::io::Result<GhOutput>;
+}
+
+/// Runner spawning the real `curl` binary
+struct Curl;
+
+impl CurlRunner for Curl {
+    fn run(&self, config: &str) -> std::io::Result<GhOutput> {
+        run_curl(config, "to talk to GitLab")
+    }
+}
+
+/// A label as `/api/v4/projects/:id/labels` returns it
+#[derive(Debug, Deserialize)]
+struct GitlabLabel {
+    id: u64,
+    name: String,
+    /// `#rrggbb`
+    color: String,
+    description: Option<String>,
+}
+
+/// Client for the labels of one GitLab project, authenticated with `GITLAB_TOKEN`
+pub struct GitlabClient {
+    host: String,
+    /// `namespace/project`
+    project: String,
+    token: String,
+    runner: Box<dyn CurlRunner>,
+}
+
+impl GitlabClient {
+    /// Client for `owner/repo` on `host`, with the token from `GITLAB_TOKEN`
+    pub fn new(host: &str, owner: &str, repo: &str) -> Result<Self> {
+        let token = std::env::var("GITLAB_TOKEN")
+            .ok()
+            .filter(|token| !token.is_empty())
+            .ok_or_else(|| {
+                BiaoError::InvalidInput(format!("{} is a GitLab host; set GITLAB_TOKEN to a personal access token with the api scope", host))
+            })?;
+        Ok(Self {
+            host: host.to_string(),
+            project: format!("{}/{}", owner, repo),
+            token,
+            runner: Box::new(Curl),
+        })
+    }
+
+    /// Client driven by a custom runner
+    #[cfg(test)]
+    pub(crate) fn with_runner(host: &str, project: &str, runner: impl CurlRunner + 'static) -> Self {
+        Self {
+            host: host.to_string(),
+            project: project.to_string(),
+            token: "token".to_string(),
+            runner: Box::new(runner),
+        }
+    }
+
+    /// `.../labels`, or `.../labels/:name` for one label
+    fn labels_url(&self, name: Option<&str>) -> String {
+        let url = format!("https://{}/api/v4/projects/{}/labels", self.host, percent_encode(&self.project));
+        match name {
+            Some(name) => format!("{}/{}", url, percent_encode(name)),
+            None => url,
+        }
+    }
+
+    /// Send `method url` with an optional JSON body; `label` names the label the request is about
+    fn send(&self, method: &str, url: &str, body: Option<&serde_json::Value>, label: Option<&str>) -> Result<String> {
+        let mut config = vec![
+            "silent".to_string(),
+            "show-error".to_string(),
+            "include".to_string(),
+            format!("request = {}", curl_quote(method)),
+            format!("url = {}", curl_quote(url)),
+            format!("header = {}", curl_quote(&format!("PRIVATE-TOKEN: {}", self.token))),
+        ];
+        if let Some(body) = body {
+            config.push(format!("header = {}", curl_quote("Content-Type: application/json")));
+            config.push(format!("data-raw = {}", curl_quote(&body.to_string())));
+        }
+        let config = config.join("\n") + "\n";
+
+        let verbose = crate::output::is_verbose();
+        if verbose {
+            log_call(&format!("curl -X {} {}", method, url), body.map(|b| b.to_string()).as_deref());
+        }
+        let output = self.runner.run(&config).map_err(|e| BiaoError::GitlabError {
+            message: format!("Failed to send request: {}", e),
+            status: None,
+        })?;
+        if verbose {
+            log_response(&output.stdout, &output.stderr);
+        }
+        let response = GhResponse::parse(&output.stdout);
+        if !output.success {
+            return Err(self.classify_failure(&response, output.stderr, label));
+        }
+        Ok(response.body.trim().to_string())
+    }
+
+    fn classify_failure(&self, response: &GhResponse, stderr: String, label: Option<&str>) -> BiaoError {
+        match (response.status, label) {
+            (Some(401), _) => BiaoError::GitlabError {
+                message: "GITLAB_TOKEN was rejected".to_string(),
+                status: Some(401),
+            },
+            (Some(404), Some(name)) => BiaoError::NotFound {
+                resource: format!("Label '{}'", name),
+            },
+            (Some(404), None) => {
+                let (owner, repo) = self.project.split_once('/').unwrap_or((&self.project, ""));
+                BiaoError::RepoNotFound {
+                    owner: owner.to_string(),
+                    repo: repo.to_string(),
+                }
+            }
+            (Some(409), Some(name)) => BiaoError::AlreadyExists { name: name.to_string() },
+            (status, _) => {
+                // GitLab errors are `{"message": ...}`, a string or a map of field errors
+                let message = serde_json::from_str::<serde_json::Value>(&response.body)
+                    .ok()
+                    .and_then(|body| body.get("message").cloned())
+                    .map(|message| match message {
+                        serde_json::Value::String(s) => s,
+                        other => other.to_string(),
+                    })
+                    .unwrap_or(stderr);
+                BiaoError::GitlabError { message, status }
+            }
+        }
+    }
+
+    fn parse_label(&self, body: &str) -> Result<GithubLabel> {
+        let label: GitlabLabel = serde_json::from_str(body).map_err(|e| BiaoError::ParseError {
+            message: format!("Failed to parse label: {}", e),
+        })?;
+        Ok(self.to_label(label))
+    }
+
+    /// GitLab label in biao's model: color without `#`, lowercased
+    fn to_label(&self, label: GitlabLabel) -> GithubLabel {
+        GithubLabel {
+            url: self.label_page(&label.name),
+            color: label.color.trim_start_matches('#').to_ascii_lowercase(),
+            name: label.name,
+            description: label.description.filter(|d| !d.is_empty()),
+            id: label.id,
+            node_id: String::new(),
+            default: false,
+        }
+    }
+
+    /// GitLab has no page per label; this is the label list searched for it
+    fn label_page(&self, name: &str) -> String {
+        format!("https://{}/{}/-/labels?search={}", self.host, self.project, percent_encode(name))
+    }
+
+    fn list(&self) -> Result<Vec<GithubLabel>> {
+        let mut labels = Vec::new();
+        for page in 1.. {
+            let url = format!("{}?per_page={}&page={}", self.labels_url(None), LABELS_PER_PAGE, page);
+            let body = self.send("GET", &url, None, None)?;
+            let batch: Vec<GitlabLabel> = serde_json::from_str(&body).map_err(|e| BiaoError::ParseError {
+                message: format!("Failed to parse labels: {}", e),
+            })?;
+            let last = batch.len() < LABELS_PER_PAGE;
+            labels.extend(batch.into_iter().map(|label| self.to_label(label)));
+            if last {
+                break;
+            }
+        }
+        Ok(labels)
+    }
+
+    fn get(&self, name: &str) -> Result<GithubLabel> {
+        let body = self.send("GET", &self.labels_url(Some(name)), None, Some(name))?;
+        self.parse_label(&body)
+    }
+
+    fn create(&self, label: &CreateLabelRequest) -> Result<GithubLabel> {
+        let body = serde_json::json!({
+            "name": label.name,
+            "color": format!("#{}", label.color),
+            "description": label.description.clone().unwrap_or_default(),
+        });
+        let response = self.send("POST", &self.labels_url(None), Some(&body), Some(&label.name))?;
+        self.parse_label(&response)
+    }
+
+    fn update(&self, name: &str, label: &UpdateLabelRequest) -> Result<GithubLabel> {
+        let mut body = serde_json::Map::new();
+        if let Some(new_name) = &label.name {
+            body.insert("new_name".to_string(), new_name.clone().into());
+        }
+        if let Some(color) = &label.color {
+            body.insert("color".to_string(), format!("#{}", color).into());
+        }
+        if let Some(description) = &label.description {
+            body.insert("description".to_string(), description.clone().into());
+        }
+        let body = serde_json::Value::Object(body);
+        let response = self.send("PUT", &self.labels_url(Some(name)), Some(&body), Some(name))?;
+        self.parse_label(&response)
+    }
+
+    fn delete(&self, name: &str) -> Result<()> {
+        match self.send("DELETE", &self.labels_url(Some(name)), None, Some(name)) {
+            Ok(_) | Err(BiaoError::NotFound { .. }) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl LabelProvider for GitlabClient {
+    fn repo_url(&self) -> String {
+        self.project.clone()
+    }
+
+    fn label_web_url(&self, name: &str) -> String {
+        self.label_page(name)
+    }
+
+    fn list_labels(&self) -> BoxFuture<'_, Result<Vec<GithubLabel>>> {
+        Box::pin(async move { self.list() })
+    }
+
+    fn get_label<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<GithubLabel>> {
+        Box::pin(async move { self.get(name) })
+    }
+
+    fn create_label<'a>(&'a self, label: &'a CreateLabelRequest) -> BoxFuture<'a, Result<GithubLabel>> {
+        Box::pin(async move { self.create(label) })
+    }
+
+    fn update_label<'a>(&'a self, name: &'a str, label: &'a UpdateLabelRequest) -> BoxFuture<'a, Result<GithubLabel>> {
+        Box::pin(async move { self.update(name, label) })
+    }
+
+    fn delete_label<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move { self.delete(name) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// Runner replaying canned `(status, body)` responses and recording the configs it receives
+    struct MockCurl {
+        responses: Mutex<Vec<(u16, String)>>,
+        configs: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl CurlRunner for MockCurl {
+        fn run(&self, config: &str) -> std::io::Result<GhOutput> {
+            self.configs.lock().unwrap().push(config.to_string());
+            let (status, body) = self.responses.lock().unwrap().remove(0);
+            Ok(GhOutput {
+                success: (200..300).contains(&status),
+                stdout: format!("HTTP/2 {} X\r\n\r\n{}", status, body),
+                stderr: String::new(),
+            })
+        }
+    }
+
+    fn client(responses: Vec<(u16, &str)>) -> (GitlabClient, Arc<Mutex<Vec<String>>>) {
+        let configs = Arc::new(Mutex::new(Vec::new()));
+        let runner = MockCurl {
+            responses: Mutex::new(responses.into_iter().map(|(s, b)| (s, b.to_string())).collect()),
+            configs: configs.clone(),
+        };
+        (GitlabClient::with_runner("gitlab.com", "group/app", runner), configs)
+    }
+
+    #[tokio::test]
+    async fn test_gitlab_label_crud() {
+        let bug = r##"{"id": 7, "name": "bug", "color": "#D73A4A", "description": ""}"##;
+        let (client, configs) = client(vec![
+            (200, &format!("[{}]", bug)),
+            (201, bug),
+            (200, r##"{"id": 7, "name": "defect", "color": "#d73a4a", "description": "Broken"}"##),
+            (204, ""),
+        ]);
+        let provider: &dyn LabelProvider = &client;
+
+        let labels = provider.list_labels().await.unwrap();
+        assert_eq!(labels[0].name, "bug");
+        assert_eq!(labels[0].color, "d73a4a");
+        assert_eq!(labels[0].description, None);
+
+        let create = CreateLabelRequest {
+            name: "bug".to_string(),
+            color: "d73a4a".to_string(),
+            description: None,
+        };
+        provider.create_label(&create).await.unwrap();
+
+        let update = UpdateLabelRequest {
+            name: Some("defect".to_string()),
+            color: None,
+            description: Some("Broken".to_string()),
+        };
+        let updated = provider.update_label("bug", &update).await.unwrap();
+        assert_eq!(updated.name, "defect");
+
+        provider.delete_label("defect").await.unwrap();
+
+        let configs = configs.lock().unwrap();
+        assert!(configs[0].contains("url = \"https://gitlab.com/api/v4/projects/group%2Fapp/labels?per_page=100&page=1\""));
+        assert!(configs[0].contains("PRIVATE-TOKEN: token"));
+        assert!(configs[1].contains("request = \"POST\""));
+        assert!(configs[1].contains(r##"\"color\":\"#d73a4a\""##));
+        assert!(configs[2].contains("request = \"PUT\""));
+        assert!(configs[2].contains("projects/group%2Fapp/labels/bug\""));
+        assert!(configs[2].contains(r#"\"new_name\":\"defect\""#));
+        assert!(configs[3].contains("request = \"DELETE\""));
+    }
+
+    #[tokio::test]
+    async fn test_gitlab_errors() {
+        let (client, _) = client(vec![
+            (404, r#"{"message": "404 Label Not Found"}"#),
+            (409, r#"{"message": "Label already exists"}"#),
+            (404, r#"{"message": "404 Project Not Found"}"#),
+            (400, r#"{"message": {"color": ["must be a valid color code"]}}"#),
+        ]);
+
+        assert!(matches!(client.get("nope"), Err(BiaoError::NotFound { .. })));
+        let create = CreateLabelRequest {
+            name: "bug".to_string(),
+            color: "zzzzzz".to_string(),
+            description: None,
+        };
+        assert!(matches!(client.create(&create), Err(BiaoError::AlreadyExists { name }) if name == "bug"));
+        assert!(matches!(client.list(), Err(BiaoError::RepoNotFound { .. })));
+        match client.create(&create) {
+            Err(BiaoError::GitlabError { message, status }) => {
+                assert_eq!(status, Some(400));
+                assert!(message.contains("must be a valid color code"));
+            }
+            other => panic!("unexpected {:?}", other.map(|l| l.name)),
+        }
+    }
+}
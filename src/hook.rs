@@ -0,0 +1,82 @@
+use crate::error::{BiaoError, Result};
+use crate::plan::Summary;
+use std::process::Command;
+
+/// Environment of an `apply --pre-hook` command
+pub fn pre_env(repository: &str, dry_run: bool) -> Vec<(&'static str, String)> {
+    vec![
+        ("BIAO_REPO", repository.to_string()),
+        ("BIAO_DRY_RUN", if dry_run { "1" } else { "0" }.to_string()),
+    ]
+}
+
+/// Environment of an `apply --post-hook` command: the pre-hook's plus the summary counts
+pub fn post_env(repository: &str, dry_run: bool, summary: &Summary) -> Vec<(&'static str, String)> {
+    let mut env = pre_env(repository, dry_run);
+    env.extend([
+        ("BIAO_CREATED", summary.created.to_string()),
+        ("BIAO_UPDATED", summary.updated.to_string()),
+        ("BIAO_RENAMED", summary.renamed.to_string()),
+        ("BIAO_DELETED", summary.deleted.to_string()),
+        ("BIAO_UNCHANGED", summary.unchanged.to_string()),
+        ("BIAO_SKIPPED", summary.skipped.to_string()),
+        ("BIAO_FAILED", summary.failed.to_string()),
+        ("BIAO_UNVERIFIED", summary.unverified.to_string()),
+    ]);
+    env
+}
+
+/// `hook` run by the platform shell with `env` added to the environment
+pub fn command(hook: &str, env: &[(&'static str, String)]) -> Command {
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.args(["/C", hook]);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.args(["-c", hook]);
+        command
+    };
+    command.envs(env.iter().map(|(key, value)| (*key, value)));
+    command
+}
+
+/// Run a hook, failing if it can't be started or exits unsuccessfully
+pub fn run(kind: &str, hook: &str, env: &[(&'static str, String)]) -> Result<()> {
+    let status = command(hook, env).status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(BiaoError::InvalidInput(format!("{} `{}` failed ({})", kind, hook, status)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_post_hook_sees_summary_counts() {
+        let summary = Summary {
+            created: 2,
+            deleted: 1,
+            failed: 3,
+            ..Default::default()
+        };
+        let env = post_env("o/r", false, &summary);
+
+        let output = command("echo $BIAO_REPO $BIAO_CREATED $BIAO_DELETED $BIAO_FAILED $BIAO_DRY_RUN", &env)
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "o/r 2 1 3 0\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_failing_hook_is_an_error() {
+        assert!(run("--pre-hook", "true", &pre_env("o/r", true)).is_ok());
+        let err = run("--pre-hook", "exit 3", &pre_env("o/r", true)).unwrap_err();
+        assert!(err.to_string().contains("--pre-hook `exit 3` failed"));
+    }
+}
@@ -1,16 +1,39 @@
+mod auth;
 mod cli;
 mod client;
 mod models;
+mod output;
 mod error;
 mod git;
+mod gitlab;
 mod config;
+mod doctor;
+mod export;
+mod filter;
+mod hook;
+mod migrate;
+mod plan;
+mod profile;
+mod provider;
+mod rewrite;
+mod settings;
+mod snapshot;
+mod stats;
 mod templates;
+mod theme;
+mod yaml;
 
-use anyhow::Result;
 use clap::Parser;
+use std::process::ExitCode;
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
     let args = cli::Args::parse();
-    cli::execute(args).await.map_err(|e| anyhow::anyhow!(e))
+    match cli::execute(args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(e.exit_code())
+        }
+    }
 }
@@ -0,0 +1,259 @@
+use crate::config::{Label, LabelConfig};
+use crate::error::{BiaoError, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Label names of a scheme, keyed by the concept they stand for (`bug`, `high`, `api`, ...)
+pub type Scheme = HashMap<String, String>;
+
+/// User-defined schemes extending the built-in ones.
+///
+/// ```toml
+/// [schemes.kind]
+/// bug = "kind/bug"
+/// feature = "kind/feature"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+pub struct SchemeMapping {
+    #[serde(default)]
+    schemes: HashMap<String, Scheme>,
+}
+
+impl SchemeMapping {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content = fs::read_to_string(path.as_ref()).map_err(|e| {
+            BiaoError::Io(std::io::Error::other(format!(
+                "Failed to read scheme mapping: {}",
+                e
+            )))
+        })?;
+
+        toml::from_str(&content)
+            .map_err(|e| BiaoError::InvalidInput(format!("Failed to parse scheme mapping: {}", e)))
+    }
+
+    /// Look up a scheme, preferring user-defined schemes over built-ins
+    pub fn scheme(&self, name: &str) -> Result<Scheme> {
+        if let Some(scheme) = self.schemes.get(name) {
+            return Ok(scheme.clone());
+        }
+
+        builtin_scheme(name).ok_or_else(|| {
+            let mut known: Vec<&str> = BUILTIN_SCHEMES.iter().map(|(name, _)| *name).collect();
+            known.extend(self.schemes.keys().map(String::as_str));
+            known.sort();
+            BiaoError::InvalidInput(format!(
+                "Unknown label scheme '{}'. Known schemes: {}",
+                name,
+                known.join(", ")
+            ))
+        })
+    }
+}
+
+/// Built-in schemes, matching the families of the built-in templates
+const BUILTIN_SCHEMES: &[(&str, &[(&str, &str)])] = &[
+    (
+        "standard",
+        &[
+            ("bug", "bug"),
+            ("feature", "feature"),
+            ("docs", "documentation"),
+            ("question", "question"),
+        ],
+    ),
+    (
+        "semantic",
+        &[
+            ("bug", "bugfix"),
+            ("feature", "feature"),
+            ("docs", "docs"),
+            ("refactor", "refactor"),
+            ("test", "test"),
+            ("chore", "chore"),
+        ],
+    ),
+    (
+        "type",
+        &[
+            ("bug", "type/bug"),
+            ("feature", "type/feature"),
+            ("enhancement", "type/enhancement"),
+            ("docs", "type/docs"),
+            ("question", "type/question"),
+            ("test", "type/test"),
+            ("refactor", "type/refactor"),
+            ("chore", "type/chore"),
+        ],
+    ),
+    (
+        "T",
+        &[
+            ("bug", "T-bug"),
+            ("feature", "T-feature"),
+            ("enhancement", "T-enhancement"),
+            ("docs", "T-docs"),
+            ("question", "T-question"),
+            ("test", "T-test"),
+            ("refactor", "T-refactor"),
+            ("chore", "T-chore"),
+        ],
+    ),
+    (
+        "priority",
+        &[
+            ("critical", "priority/critical"),
+            ("high", "priority/high"),
+            ("medium", "priority/medium"),
+            ("low", "priority/low"),
+            ("backlog", "priority/backlog"),
+        ],
+    ),
+    (
+        "P",
+        &[
+            ("critical", "P-critical"),
+            ("high", "P-high"),
+            ("medium", "P-medium"),
+            ("low", "P-low"),
+            ("backlog", "P-backlog"),
+        ],
+    ),
+    (
+        "area",
+        &[
+            ("api", "area/api"),
+            ("cli", "area/cli"),
+            ("docs", "area/docs"),
+            ("core", "area/core"),
+            ("testing", "area/testing"),
+            ("ci", "area/ci"),
+            ("performance", "area/performance"),
+            ("security", "area/security"),
+        ],
+    ),
+    (
+        "A",
+        &[
+            ("api", "A-api"),
+            ("cli", "A-cli"),
+            ("docs", "A-docs"),
+            ("core", "A-core"),
+            ("testing", "A-testing"),
+            ("ci", "A-ci"),
+            ("performance", "A-performance"),
+            ("security", "A-security"),
+        ],
+    ),
+];
+
+fn builtin_scheme(name: &str) -> Option<Scheme> {
+    BUILTIN_SCHEMES
+        .iter()
+        .find(|(scheme, _)| *scheme == name)
+        .map(|(_, labels)| {
+            labels
+                .iter()
+                .map(|(concept, label)| (concept.to_string(), label.to_string()))
+                .collect()
+        })
+}
+
+/// Config renaming every label of `from` to the label for the same concept in `to`.
+///
+/// Labels carry no color, so only labels that exist in the `from` scheme are
+/// renamed and keep their current color and description.
+pub fn migration_config(from: &Scheme, to: &Scheme) -> LabelConfig {
+    let mut concepts: Vec<&String> = from.keys().filter(|c| to.contains_key(*c)).collect();
+    concepts.sort();
+
+    let labels = concepts
+        .into_iter()
+        .filter(|concept| from[*concept] != to[*concept])
+        .map(|concept| Label {
+            name: to[concept].clone(),
+            update_if_match: vec![from[concept].clone()],
+            ..Default::default()
+        })
+        .collect();
+
+    LabelConfig {
+        labels,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::GithubLabel;
+    use crate::plan::{OperationKind, Plan};
+
+    fn remote(name: &str) -> GithubLabel {
+        GithubLabel {
+            name: name.to_string(),
+            color: "d73a49".to_string(),
+            description: None,
+            url: String::new(),
+            id: 1,
+            node_id: "node".to_string(),
+            default: false,
+        }
+    }
+
+    #[test]
+    fn test_migrate_type_to_semantic() {
+        let mapping = SchemeMapping::default();
+        let config = migration_config(
+            &mapping.scheme("type").unwrap(),
+            &mapping.scheme("semantic").unwrap(),
+        );
+        let existing = vec![remote("type/bug"), remote("type/docs"), remote("wontfix")];
+
+        let plan = Plan::build(&config, &existing, false).unwrap();
+        let renames: Vec<(String, String)> = plan
+            .operations
+            .iter()
+            .filter_map(|op| match &op.kind {
+                OperationKind::Rename { from } => Some((from.clone(), op.name.clone())),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            renames,
+            vec![
+                ("type/bug".to_string(), "bugfix".to_string()),
+                ("type/docs".to_string(), "docs".to_string()),
+            ]
+        );
+        // Concepts without a label in the repository are left alone
+        assert!(plan
+            .operations
+            .iter()
+            .all(|op| matches!(op.kind, OperationKind::Rename { .. } | OperationKind::Skip { .. })));
+        assert!(plan.operations.iter().all(|op| op.color.is_none()));
+    }
+
+    #[test]
+    fn test_user_scheme_overrides_builtin() {
+        let mapping: SchemeMapping = toml::from_str(
+            r#"
+[schemes.kind]
+bug = "kind/bug"
+
+[schemes.type]
+bug = "type: bug"
+"#,
+        )
+        .unwrap();
+
+        let config = migration_config(&mapping.scheme("type").unwrap(), &mapping.scheme("kind").unwrap());
+        assert_eq!(config.labels.len(), 1);
+        assert_eq!(config.labels[0].name, "kind/bug");
+        assert_eq!(config.labels[0].update_if_match, vec!["type: bug"]);
+        assert!(mapping.scheme("nope").is_err());
+    }
+}
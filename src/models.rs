@@ -1,14 +1,17 @@
+use crate::config::{Label, MAX_NAME_LENGTH};
+use crate::error::{BiaoError, Result};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Label {
-    pub name: String,
-    pub color: String,
-    #[serde(default)]
-    pub description: Option<String>,
-}
+/// GitHub rejects label descriptions longer than this many characters
+pub const MAX_DESCRIPTION_LENGTH: usize = 100;
+
+/// Invisible characters that make two identical-looking names differ.
+///
+/// The zero-width (non-)joiners are left out: emoji sequences and some
+/// scripts need them.
+const ZERO_WIDTH_CHARS: &[char] = &['\u{200B}', '\u{2060}', '\u{FEFF}'];
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GithubLabel {
     pub name: String,
     pub color: String,
@@ -36,3 +39,231 @@ pub struct UpdateLabelRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
 }
+
+/// A repository from `GET orgs/{org}/repos`
+#[derive(Debug, Clone, Deserialize)]
+pub struct OrgRepository {
+    pub name: String,
+    /// `owner/name`
+    pub full_name: String,
+    #[serde(default)]
+    pub archived: bool,
+}
+
+/// The caller's `permissions` on a repository, from `GET repos/{owner}/{repo}`
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct RepoPermissions {
+    #[serde(default)]
+    pub admin: bool,
+    #[serde(default)]
+    pub maintain: bool,
+    #[serde(default)]
+    pub push: bool,
+    #[serde(default)]
+    pub triage: bool,
+    #[serde(default)]
+    pub pull: bool,
+}
+
+impl RepoPermissions {
+    /// Creating, editing and deleting labels needs write access
+    pub fn can_manage_labels(&self) -> bool {
+        self.admin || self.maintain || self.push
+    }
+
+    /// Name of the highest role these permissions amount to
+    pub fn role(&self) -> &'static str {
+        if self.admin {
+            "admin"
+        } else if self.maintain {
+            "maintain"
+        } else if self.push {
+            "write"
+        } else if self.triage {
+            "triage"
+        } else if self.pull {
+            "read"
+        } else {
+            "no"
+        }
+    }
+}
+
+/// A label name trimmed of surrounding whitespace, checked against GitHub's
+/// rules: not empty, at most [`MAX_NAME_LENGTH`] characters and free of
+/// control characters. Emoji are fine.
+pub fn normalize_name(name: &str) -> Result<String> {
+    let name = trim_name(name)?;
+    let length = name.chars().count();
+    if length > MAX_NAME_LENGTH {
+        return Err(BiaoError::InvalidInput(format!(
+            "Label name '{}' has {} characters; GitHub allows at most {}",
+            name, length, MAX_NAME_LENGTH
+        )));
+    }
+    Ok(name)
+}
+
+/// `name` trimmed of surrounding whitespace, rejecting empty names and control characters
+fn trim_name(name: &str) -> Result<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(BiaoError::InvalidInput("Label names cannot be empty".to_string()));
+    }
+    if let Some(c) = name.chars().find(|c| c.is_control()) {
+        return Err(BiaoError::InvalidInput(format!(
+            "Label name '{}' contains the control character U+{:04X}, which GitHub doesn't allow",
+            name.escape_debug(),
+            c as u32
+        )));
+    }
+    Ok(name.to_string())
+}
+
+/// Strip control characters from a label's name and description, trim the
+/// name and check the description length. Over-long names are left to
+/// [`LabelConfig::validate`](crate::config::LabelConfig::validate), which lists them all.
+///
+/// Returns warnings for what was stripped and for zero-width characters,
+/// which are kept but produce labels that only look identical.
+pub fn sanitize_label(label: &mut Label) -> Result<Vec<String>> {
+    let mut warnings = Vec::new();
+
+    if strip_control_chars(&mut label.name) {
+        warnings.push(format!("removed control characters from label name '{}'", label.name));
+    }
+    label.name = trim_name(&label.name)?;
+    if label.name.contains(ZERO_WIDTH_CHARS) {
+        warnings.push(format!(
+            "label name '{}' contains zero-width characters",
+            reveal_zero_width(&label.name)
+        ));
+    }
+
+    if let Some(description) = &mut label.description {
+        if strip_control_chars(description) {
+            warnings.push(format!("removed control characters from the description of '{}'", label.name));
+        }
+        if description.contains(ZERO_WIDTH_CHARS) {
+            warnings.push(format!("the description of '{}' contains zero-width characters", label.name));
+        }
+        let length = description.chars().count();
+        if length > MAX_DESCRIPTION_LENGTH {
+            return Err(BiaoError::InvalidInput(format!(
+                "The description of '{}' has {} characters; GitHub allows at most {}",
+                label.name, length, MAX_DESCRIPTION_LENGTH
+            )));
+        }
+    }
+
+    Ok(warnings)
+}
+
+/// `name` with its zero-width characters spelled out, e.g. `bug\u{200b}`
+fn reveal_zero_width(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if ZERO_WIDTH_CHARS.contains(&c) {
+                c.escape_unicode().to_string()
+            } else {
+                c.to_string()
+            }
+        })
+        .collect()
+}
+
+/// Replace runs of whitespace control characters (tabs, newlines) with one
+/// space and remove the other control characters in place; returns whether
+/// any were found
+fn strip_control_chars(text: &mut String) -> bool {
+    if !text.contains(char::is_control) {
+        return false;
+    }
+    let mut cleaned = String::with_capacity(text.len());
+    let mut in_break = false;
+    for c in text.chars() {
+        if !c.is_control() {
+            cleaned.push(c);
+            in_break = false;
+        } else if c.is_whitespace() && !in_break {
+            cleaned.push(' ');
+            in_break = true;
+        }
+    }
+    *text = cleaned;
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label(name: &str, description: Option<&str>) -> Label {
+        Label {
+            name: name.to_string(),
+            description: description.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sanitize_zero_width_name() {
+        let mut bug = label("bug\u{200B}", None);
+        let warnings = sanitize_label(&mut bug).unwrap();
+        assert_eq!(bug.name, "bug\u{200B}");
+        assert_eq!(warnings, vec!["label name 'bug\\u{200b}' contains zero-width characters"]);
+
+        // Emoji sequences rely on the zero-width joiner
+        let mut family = label("👨\u{200D}👩\u{200D}👧 community", Some("🎉 welcome"));
+        assert!(sanitize_label(&mut family).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_sanitize_control_characters() {
+        let mut bug = label("b\u{7}ug\n", Some("Something\tbroke"));
+        let warnings = sanitize_label(&mut bug).unwrap();
+        assert_eq!(bug.name, "bug");
+        assert_eq!(bug.description.as_deref(), Some("Something broke"));
+        assert_eq!(warnings.len(), 2);
+
+        assert!(sanitize_label(&mut label("\u{1b}", None)).is_err());
+
+        // Line breaks become one space, other control characters vanish
+        let mut wrapped = label("crash", Some("Panics\r\non\u{0}\tstartup"));
+        sanitize_label(&mut wrapped).unwrap();
+        assert_eq!(wrapped.description.as_deref(), Some("Panics on startup"));
+
+        let mut padded = label(" bug  ", None);
+        assert!(sanitize_label(&mut padded).unwrap().is_empty());
+        assert_eq!(padded.name, "bug");
+    }
+
+    #[test]
+    fn test_normalize_name() {
+        assert_eq!(normalize_name("🐛 bug").unwrap(), "🐛 bug");
+        assert_eq!(normalize_name("  good first issue \t").unwrap(), "good first issue");
+
+        let max = "x".repeat(MAX_NAME_LENGTH);
+        assert_eq!(normalize_name(&max).unwrap(), max);
+        let err = normalize_name(&format!("{}y", max)).unwrap_err().to_string();
+        assert!(err.contains("51 characters") && err.contains("at most 50"));
+
+        assert!(normalize_name("   ").unwrap_err().to_string().contains("cannot be empty"));
+        assert!(normalize_name("b\nug").unwrap_err().to_string().contains("U+000A"));
+
+        // Configs report all long names at once in validate
+        let mut long = label(&format!(" {}y", max), None);
+        assert!(sanitize_label(&mut long).is_ok());
+        assert_eq!(long.name, format!("{}y", max));
+    }
+
+    #[test]
+    fn test_sanitize_description_length() {
+        let long = "x".repeat(MAX_DESCRIPTION_LENGTH + 1);
+        let err = sanitize_label(&mut label("bug", Some(&long))).unwrap_err();
+        assert!(err.to_string().contains("at most 100"));
+
+        let max = "x".repeat(MAX_DESCRIPTION_LENGTH);
+        assert!(sanitize_label(&mut label("bug", Some(&max))).is_ok());
+    }
+}
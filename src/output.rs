@@ -0,0 +1,939 @@
+use crate::error::{BiaoError, Result};
+use crate::models::GithubLabel;
+use crate::plan::{DiffField, Operation, OperationKind, Plan, Summary};
+use clap::ValueEnum;
+use colored::Colorize;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether commands print the `Repository:` header, turned off by `--no-banner`
+static SHOW_BANNER: AtomicBool = AtomicBool::new(true);
+
+/// `--quiet`: only requested data and errors are printed
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// `--verbose`: every API call and its response is echoed on stderr
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+/// Output format selected with the global `--output` flag
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Colored, human-readable text
+    #[default]
+    Human,
+    /// Pretty-printed JSON
+    Json,
+    /// Comma-separated values with a header row
+    Csv,
+    /// GitHub Actions workflow commands, plus a job summary when run in Actions
+    GithubActions,
+    /// A label config that `biao apply` accepts
+    Toml,
+    /// Human-readable, but `apply` only shows its per-label log when an operation failed
+    Smart,
+    /// One JSON object per line, printed by `apply` as each operation completes
+    Ndjson,
+}
+
+impl OutputFormat {
+    /// Error returned by commands that cannot render this format
+    pub fn unsupported(self, command: &str) -> BiaoError {
+        let name = self
+            .to_possible_value()
+            .map(|v| v.get_name().to_string())
+            .unwrap_or_default();
+        BiaoError::InvalidInput(format!("`{}` does not support --output {}", command, name))
+    }
+}
+
+/// When to color output, selected with the global `--color` flag
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Color when stdout is a terminal and `NO_COLOR` is unset
+    #[default]
+    Auto,
+    /// Always color, e.g. for CI logs that render ANSI escapes
+    Always,
+    /// Never color
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether to colorize, given the terminal and `NO_COLOR` state auto mode looks at
+    fn should_colorize(self, is_terminal: bool, no_color_env: bool) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => is_terminal && !no_color_env,
+        }
+    }
+
+    /// Override `colored`'s own detection for the rest of the process
+    pub fn apply(self) {
+        use std::io::IsTerminal;
+
+        colored::control::set_override(
+            self.should_colorize(std::io::stdout().is_terminal(), std::env::var_os("NO_COLOR").is_some()),
+        );
+    }
+}
+
+/// Outcome of a single operation during `apply`
+#[derive(Debug, Serialize)]
+pub struct OperationReport {
+    pub name: String,
+    pub action: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    pub result: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Machine-readable result of an `apply` run
+#[derive(Debug, Serialize)]
+pub struct ApplyReport {
+    pub repository: String,
+    pub dry_run: bool,
+    /// Fingerprint of the computed plan, reported for dry runs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plan_hash: Option<String>,
+    pub operations: Vec<OperationReport>,
+    pub summary: Summary,
+}
+
+/// Render a single label in the given format; `web_url` is its page on GitHub
+pub fn render_label(label: &GithubLabel, format: OutputFormat, web_url: &str) -> Result<String> {
+    match format {
+        OutputFormat::Human => Ok(format_label(label, web_url)),
+        OutputFormat::Json => to_json(label),
+        OutputFormat::Csv => Ok(render_csv(std::slice::from_ref(label))),
+        OutputFormat::Toml => to_config_toml(std::slice::from_ref(label)),
+        OutputFormat::GithubActions | OutputFormat::Smart | OutputFormat::Ndjson => {
+            Err(format.unsupported("get"))
+        }
+    }
+}
+
+/// Render a list of labels in the given format; `web_url` maps a label name to its page
+pub fn render_labels(
+    labels: &[GithubLabel],
+    format: OutputFormat,
+    web_url: &dyn Fn(&str) -> String,
+) -> Result<String> {
+    match format {
+        OutputFormat::Human => Ok(labels
+            .iter()
+            .map(|label| format_label(label, &web_url(&label.name)))
+            .collect()),
+        OutputFormat::Json => to_json(labels),
+        OutputFormat::Csv => Ok(render_csv(labels)),
+        OutputFormat::Toml => to_config_toml(labels),
+        OutputFormat::GithubActions | OutputFormat::Smart | OutputFormat::Ndjson => {
+            Err(format.unsupported("list"))
+        }
+    }
+}
+
+/// JSON with only the fields that define a label (`list --output json --slim`)
+pub fn render_slim_labels(labels: &[GithubLabel]) -> Result<String> {
+    let slim: Vec<crate::config::Label> = labels.iter().map(crate::config::Label::from).collect();
+    to_json(&slim)
+}
+
+/// Render an `apply` report as JSON or GitHub Actions workflow commands
+pub fn render_apply_report(report: &ApplyReport, format: OutputFormat) -> Result<String> {
+    match format {
+        OutputFormat::Json => to_json(report),
+        OutputFormat::GithubActions => Ok(format_workflow_commands(report)),
+        // Each operation was already streamed as it completed
+        OutputFormat::Ndjson => Ok(String::new()),
+        OutputFormat::Human | OutputFormat::Csv | OutputFormat::Toml | OutputFormat::Smart => {
+            Err(format.unsupported("apply"))
+        }
+    }
+}
+
+/// One line of `--output ndjson`
+pub fn ndjson_record<T: Serialize>(record: &T) -> Result<String> {
+    serde_json::to_string(record)
+        .map(|json| json + "\n")
+        .map_err(|e| BiaoError::ParseError {
+            message: format!("Failed to serialize JSON: {}", e),
+        })
+}
+
+/// Print one `--output ndjson` line right away, so consumers see each record as it completes
+pub fn print_ndjson<T: Serialize>(record: &T) -> Result<()> {
+    use std::io::Write;
+
+    let line = ndjson_record(record)?;
+    #[cfg(test)]
+    PRINTED_NDJSON.with(|printed| printed.borrow_mut().push_str(&line));
+    print!("{}", line);
+    std::io::stdout().flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Everything [`print_ndjson`] printed on this thread, for tests to read back
+    pub static PRINTED_NDJSON: std::cell::RefCell<String> = Default::default();
+}
+
+/// A GitHub Actions workflow command such as `::error title=...::message`
+fn workflow_command(command: &str, title: &str, message: &str) -> String {
+    let escape_data = |s: &str| s.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A");
+    let escape_property = |s: &str| escape_data(s).replace(':', "%3A").replace(',', "%2C");
+    format!("::{} title={}::{}", command, escape_property(title), escape_data(message))
+}
+
+/// One `::error::` per failed operation and a `::notice::` with the totals
+fn format_workflow_commands(report: &ApplyReport) -> String {
+    let mut out = String::new();
+    for op in &report.operations {
+        if let Some(error) = &op.error {
+            let title = format!("biao: failed to {} label", op.action);
+            out.push_str(&workflow_command("error", &title, &format!("'{}': {}", op.name, error)));
+            out.push('\n');
+        }
+    }
+
+    let totals: Vec<String> = report
+        .summary
+        .rows(false)
+        .iter()
+        .map(|(label, count)| format!("{} {}", label.trim_end_matches(':'), count))
+        .collect();
+    let totals = if totals.is_empty() { "No changes".to_string() } else { totals.join(", ") };
+    let title = if report.dry_run { "biao apply (dry run)" } else { "biao apply" };
+    out.push_str(&workflow_command("notice", title, &format!("{}: {}", report.repository, totals)));
+    out.push('\n');
+    out
+}
+
+/// `::error::` for every repository of a multi-repo run that failed or had failed operations
+pub fn format_repo_workflow_commands(results: &[RepoResult]) -> String {
+    let mut out = String::new();
+    for result in results {
+        let message = match (&result.error, &result.summary) {
+            (Some(error), _) => error.clone(),
+            (None, Some(summary)) if summary.failed > 0 => {
+                format!("{} operation(s) failed", summary.failed)
+            }
+            _ => continue,
+        };
+        let title = format!("biao apply: {}", result.repository);
+        out.push_str(&workflow_command("error", &title, &message));
+        out.push('\n');
+    }
+    out
+}
+
+/// Markdown job summary for `$GITHUB_STEP_SUMMARY`, with a table of the operations
+pub fn format_step_summary(report: &ApplyReport) -> String {
+    let mut out = format!("## biao apply: {}\n\n", report.repository);
+    if report.dry_run {
+        out.push_str("_Dry run, no changes were made._\n\n");
+    }
+
+    if report.operations.is_empty() {
+        out.push_str("No changes.\n");
+    } else {
+        out.push_str("| Action | Label | Result |\n|---|---|---|\n");
+        for op in &report.operations {
+            let name = match &op.from {
+                Some(from) => format!("{} → {}", from, op.name),
+                None => op.name.clone(),
+            };
+            let result = match &op.error {
+                Some(error) => format!("failed: {}", error.replace('\n', " ")),
+                None => op.result.to_string(),
+            };
+            out.push_str(&format!(
+                "| {} | {} | {} |\n",
+                op.action,
+                name.replace('|', "\\|"),
+                result.replace('|', "\\|")
+            ));
+        }
+    }
+
+    out.push('\n');
+    for (label, count) in report.summary.rows(false) {
+        out.push_str(&format!("- **{}** {}\n", label, count));
+    }
+    out
+}
+
+/// One line per planned change, in the style of a unified diff:
+/// `+` create, `~` update, `>` rename, `-` delete. Unchanged and skipped labels are omitted.
+pub fn format_diff(plan: &Plan, existing: &[GithubLabel], ignore: &[DiffField]) -> String {
+    let current = |name: &str| existing.iter().find(|l| l.name.eq_ignore_ascii_case(name));
+    let mut out = String::new();
+
+    for op in plan.changes() {
+        let line = match &op.kind {
+            OperationKind::Exists => format!(
+                "! {} already exists (set update_if_exists to update it)",
+                op.name
+            ),
+            OperationKind::Create => format!(
+                "+ {} #{}",
+                op.name,
+                op.color.as_deref().unwrap_or_default()
+            )
+            .green()
+            .to_string(),
+            OperationKind::Update => {
+                let changes = current(&op.name)
+                    .map(|label| field_changes(label, op, ignore))
+                    .unwrap_or_default();
+                format!("~ {} {}", op.name, changes.join(", ")).yellow().to_string()
+            }
+            OperationKind::Rename { from } => {
+                let mut line = format!("> {} → {}", from, op.name);
+                if let Some(label) = current(from) {
+                    for change in field_changes(label, op, ignore) {
+                        line.push_str(&format!(", {}", change));
+                    }
+                }
+                line.blue().to_string()
+            }
+            OperationKind::Delete => format!("- {}", op.name).red().to_string(),
+            OperationKind::Noop | OperationKind::Skip { .. } => continue,
+        };
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+/// Counts of a diff by outcome, then the repository labels the config doesn't mention
+pub fn format_diff_summary(summary: &Summary, unmanaged: &[&GithubLabel]) -> String {
+    let mut out = format!(
+        "{}, {}, {}, {} unchanged\n",
+        format!("{} to create", summary.created).green(),
+        format!("{} to update", summary.updated + summary.renamed).yellow(),
+        format!("{} to delete", summary.deleted).red(),
+        summary.unchanged
+    );
+    if !unmanaged.is_empty() {
+        let names: Vec<&str> = unmanaged.iter().map(|label| label.name.as_str()).collect();
+        out.push_str(&format!("Only in the repository: {}\n", names.join(", ")));
+    }
+    out
+}
+
+/// `field: old → new` for each field the operation would change
+fn field_changes(label: &GithubLabel, op: &Operation, ignore: &[DiffField]) -> Vec<String> {
+    let mut changes = Vec::new();
+    if let Some(color) = &op.color {
+        if !ignore.contains(&DiffField::Color) && !color.eq_ignore_ascii_case(&label.color) {
+            changes.push(format!("color: #{} → #{}", label.color, color));
+        }
+    }
+    if let Some(description) = &op.description {
+        let old = label.description.as_deref().unwrap_or("");
+        if !ignore.contains(&DiffField::Description) && old != description {
+            changes.push(format!("description: {:?} → {:?}", old, description));
+        }
+    }
+    changes
+}
+
+pub fn set_banner(enabled: bool) {
+    SHOW_BANNER.store(enabled, Ordering::Relaxed);
+}
+
+pub fn set_quiet(enabled: bool) {
+    QUIET.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+pub fn set_verbose(enabled: bool) {
+    VERBOSE.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_verbose() -> bool {
+    VERBOSE.load(Ordering::Relaxed)
+}
+
+/// The `Repository: owner/repo` header line, if enabled
+fn format_banner(repo: &str, enabled: bool) -> Option<String> {
+    enabled.then(|| format!("Repository: {}", repo.cyan()))
+}
+
+/// Print the `Repository:` header unless `--no-banner` or `--quiet` was given
+pub fn print_banner(repo: &str) {
+    if let Some(line) = format_banner(repo, SHOW_BANNER.load(Ordering::Relaxed) && !is_quiet()) {
+        println!("{}", line);
+    }
+}
+
+/// Per-label log of `apply`, printed as it goes or, with `--output smart`,
+/// held back until the run turns out to have failed
+pub struct OperationLog {
+    buffered: bool,
+    lines: Vec<String>,
+}
+
+impl OperationLog {
+    pub fn new(buffered: bool) -> Self {
+        OperationLog {
+            buffered,
+            lines: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, text: String) {
+        if self.buffered {
+            self.lines.push(text);
+        } else if !is_quiet() {
+            print!("{}", text);
+        }
+    }
+
+    /// The held-back log, empty unless operations failed
+    pub fn flush(self, failed: usize) -> String {
+        if failed > 0 {
+            self.lines.concat()
+        } else {
+            String::new()
+        }
+    }
+}
+
+/// Summary of a successful `--output smart` run, e.g. `✓ o/r: 2 created, 1 updated`
+pub fn format_summary_line(repository: &str, summary: &Summary) -> String {
+    let counts: Vec<String> = summary
+        .rows(false)
+        .into_iter()
+        .map(|(label, count)| format!("{} {}", count, label.trim_end_matches(':').to_lowercase()))
+        .collect();
+    let counts = if counts.is_empty() {
+        "up to date".to_string()
+    } else {
+        counts.join(", ")
+    };
+    format!("{} {}: {}", "✓".green(), repository, counts)
+}
+
+/// Outcome of applying a config to one repository in a multi-repo run
+#[derive(Debug, Serialize)]
+pub struct RepoResult {
+    pub repository: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<Summary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Aligned table with one row per repository and its created/updated/deleted/failed counts
+pub fn format_repo_matrix(results: &[RepoResult]) -> String {
+    let header = ["Repository", "Created", "Updated", "Deleted", "Failed"];
+    let rows: Vec<[String; 5]> = results
+        .iter()
+        .map(|result| match &result.summary {
+            Some(s) => [
+                result.repository.clone(),
+                s.created.to_string(),
+                s.updated.to_string(),
+                s.deleted.to_string(),
+                s.failed.to_string(),
+            ],
+            None => [
+                result.repository.clone(),
+                "-".to_string(),
+                "-".to_string(),
+                "-".to_string(),
+                "-".to_string(),
+            ],
+        })
+        .collect();
+
+    let width = |i: usize| {
+        rows.iter()
+            .map(|row| row[i].chars().count())
+            .chain([header[i].len()])
+            .max()
+            .unwrap_or_default()
+    };
+    let widths: Vec<usize> = (0..header.len()).map(width).collect();
+    let line = |cells: [&str; 5]| {
+        let mut line = format!("{:<w$}", cells[0], w = widths[0]);
+        for (cell, w) in cells.iter().zip(&widths).skip(1) {
+            line.push_str(&format!("  {:>w$}", cell, w = w));
+        }
+        line.trim_end().to_string()
+    };
+
+    let mut out = line(header) + "\n";
+    for (row, result) in rows.iter().zip(results) {
+        let cells = [&row[0], &row[1], &row[2], &row[3], &row[4]].map(String::as_str);
+        out.push_str(&line(cells));
+        if let Some(error) = &result.error {
+            out.push_str(&format!("  error: {}", error));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Scheme prefix of a label name, e.g. `A-` for `A-cli` or `type/` for `type/bug`.
+///
+/// `/` always separates a prefix; `-` only does for short Rust-style prefixes
+/// (one or two letters), so names like `help-wanted` stay ungrouped.
+pub fn label_prefix(name: &str) -> Option<&str> {
+    if let Some(index) = name.find('/') {
+        if index > 0 {
+            return Some(&name[..=index]);
+        }
+    }
+
+    let index = name.find('-')?;
+    let prefix = &name[..index];
+    if (1..=2).contains(&prefix.len()) && prefix.chars().all(|c| c.is_ascii_alphabetic()) {
+        Some(&name[..=index])
+    } else {
+        None
+    }
+}
+
+/// Group labels by prefix, sorted by prefix then name; ungrouped labels come last
+pub fn group_by_prefix(labels: &[GithubLabel]) -> Vec<(Option<String>, Vec<&GithubLabel>)> {
+    let mut groups: std::collections::BTreeMap<Option<String>, Vec<&GithubLabel>> =
+        std::collections::BTreeMap::new();
+    for label in labels {
+        groups
+            .entry(label_prefix(&label.name).map(|p| p.to_string()))
+            .or_default()
+            .push(label);
+    }
+
+    let mut groups: Vec<_> = groups.into_iter().collect();
+    // `None` sorts first in a BTreeMap; move the ungrouped section to the end
+    if groups.first().is_some_and(|(prefix, _)| prefix.is_none()) {
+        groups.rotate_left(1);
+    }
+    for (_, labels) in &mut groups {
+        labels.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+    groups
+}
+
+/// Human-readable labels under one header per prefix
+pub fn format_grouped_labels(labels: &[GithubLabel], web_url: &dyn Fn(&str) -> String) -> String {
+    let mut out = String::new();
+    for (prefix, labels) in group_by_prefix(labels) {
+        let header = match &prefix {
+            Some(prefix) => format!("{}* ({})", prefix, labels.len()),
+            None => format!("ungrouped ({})", labels.len()),
+        };
+        out.push_str(&format!("{}\n", header.bold()));
+        for label in labels {
+            out.push_str(&format_label(label, &web_url(&label.name)));
+        }
+    }
+    out
+}
+
+/// Human-readable block describing a label, linking to its page at `web_url`
+pub fn format_label(label: &GithubLabel, web_url: &str) -> String {
+    format_label_with_count(label, web_url, None)
+}
+
+/// `format_label`, with how many issues and pull requests use the label when known
+pub fn format_label_with_count(label: &GithubLabel, web_url: &str, count: Option<u64>) -> String {
+    let mut out = String::new();
+    out.push_str(&format!("  Name:        {}\n", label.name.cyan()));
+    out.push_str(&format!("  Color:       ■ #{}\n", label.color));
+    if let Some(desc) = &label.description {
+        out.push_str(&format!("  Description: {}\n", desc));
+    }
+    if let Some(count) = count {
+        out.push_str(&format!("  Used by:     {} issue(s) and pull request(s)\n", count));
+    }
+    out.push_str(&format!("  URL:         {}\n", web_url.dimmed()));
+    out.push('\n');
+    out
+}
+
+fn to_json<T: Serialize + ?Sized>(value: &T) -> Result<String> {
+    serde_json::to_string_pretty(value).map_err(|e| BiaoError::ParseError {
+        message: format!("Failed to serialize JSON: {}", e),
+    })
+}
+
+/// Labels as a config reproducing them, the same as `biao export`
+fn to_config_toml(labels: &[GithubLabel]) -> Result<String> {
+    toml::to_string_pretty(&crate::export::export_config(labels, false))
+        .map_err(|e| BiaoError::InvalidInput(format!("Failed to serialize labels: {}", e)))
+}
+
+fn render_csv(labels: &[GithubLabel]) -> String {
+    let mut out = String::from("name,color,description,url\n");
+    for label in labels {
+        let fields = [
+            label.name.as_str(),
+            label.color.as_str(),
+            label.description.as_deref().unwrap_or(""),
+            label.url.as_str(),
+        ];
+        let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label(name: &str, description: Option<&str>) -> GithubLabel {
+        GithubLabel {
+            name: name.to_string(),
+            color: "d73a49".to_string(),
+            description: description.map(|d| d.to_string()),
+            url: format!("https://api.github.com/repos/o/r/labels/{}", name),
+            id: 1,
+            node_id: "node".to_string(),
+            default: false,
+        }
+    }
+
+    fn web_url(name: &str) -> String {
+        crate::client::label_web_url("github.com", "o", "r", name)
+    }
+
+    #[test]
+    fn test_list_formats() {
+        let labels = vec![label("bug", Some("Broken, badly")), label("docs", None)];
+
+        let json = render_labels(&labels, OutputFormat::Json, &web_url).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.as_array().unwrap().len(), 2);
+        assert_eq!(parsed[0]["name"], "bug");
+
+        let csv = render_labels(&labels, OutputFormat::Csv, &web_url).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "name,color,description,url");
+        assert!(lines[1].starts_with("bug,d73a49,\"Broken, badly\","));
+        assert!(lines[2].starts_with("docs,d73a49,,"));
+
+        let human = render_labels(&labels, OutputFormat::Human, &web_url).unwrap();
+        assert!(human.contains("Name:"));
+        assert!(human.contains("https://github.com/o/r/labels/docs"));
+        assert!(human.contains("#d73a49"));
+    }
+
+    #[test]
+    fn test_operation_log_held_back_on_success() {
+        let mut log = OperationLog::new(true);
+        log.push("  Creating 'bug'... ".to_string());
+        log.push("OK\n".to_string());
+        assert_eq!(log.flush(0), "");
+
+        let summary = Summary {
+            created: 2,
+            updated: 1,
+            unchanged: 4,
+            ..Default::default()
+        };
+        assert!(format_summary_line("o/r", &summary).ends_with("o/r: 2 created, 1 updated"));
+        assert!(format_summary_line("o/r", &Summary::default()).ends_with("o/r: up to date"));
+    }
+
+    #[test]
+    fn test_operation_log_flushed_on_failure() {
+        let mut log = OperationLog::new(true);
+        log.push("  Creating 'bug'... ".to_string());
+        log.push("OK\n".to_string());
+        log.push("  Deleting 'old'... ".to_string());
+        log.push("FAILED: boom\n".to_string());
+        assert_eq!(
+            log.flush(1),
+            "  Creating 'bug'... OK\n  Deleting 'old'... FAILED: boom\n"
+        );
+    }
+
+    #[test]
+    fn test_toml_output_round_trips() {
+        let labels = vec![
+            label("bug", Some("Something isn't working")),
+            label("area/cli", None),
+        ];
+
+        let toml = render_labels(&labels, OutputFormat::Toml, &web_url).unwrap();
+        let config: crate::config::LabelConfig = toml.parse().unwrap();
+        assert_eq!(config.labels.len(), 2);
+        assert_eq!(config.labels[0].name, "bug");
+        assert_eq!(config.labels[0].color.as_deref(), Some("d73a49"));
+        assert_eq!(config.labels[0].description.as_deref(), Some("Something isn't working"));
+        assert_eq!(config.labels[1].description, None);
+    }
+
+    #[test]
+    fn test_slim_json_omits_server_fields() {
+        let label = GithubLabel {
+            name: "bug".to_string(),
+            color: "d73a49".to_string(),
+            description: Some("Something isn't working".to_string()),
+            url: "https://api.github.com/repos/o/r/labels/bug".to_string(),
+            id: 42,
+            node_id: "MDU6TGFiZWw0Mg==".to_string(),
+            default: true,
+        };
+
+        let json: serde_json::Value = serde_json::from_str(&render_slim_labels(&[label]).unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([{"name": "bug", "color": "d73a49", "description": "Something isn't working"}])
+        );
+    }
+
+    #[test]
+    fn test_color_choice_override() {
+        for (is_terminal, no_color_env) in [(true, false), (true, true), (false, false)] {
+            assert!(ColorChoice::Always.should_colorize(is_terminal, no_color_env));
+            assert!(!ColorChoice::Never.should_colorize(is_terminal, no_color_env));
+        }
+        assert!(ColorChoice::Auto.should_colorize(true, false));
+        assert!(!ColorChoice::Auto.should_colorize(true, true));
+        assert!(!ColorChoice::Auto.should_colorize(false, false));
+    }
+
+    #[test]
+    fn test_diff_against_empty_repository() {
+        colored::control::set_override(false);
+        let config: crate::config::LabelConfig =
+            "delete = [\"wontfix\"]\n\n[[labels]]\nname = \"bug\"\ncolor = \"d73a49\"\nupdate_if_exists = true\n"
+                .parse()
+                .unwrap();
+
+        let plan = Plan::build(&config, &[], false).unwrap();
+        assert_eq!(format_diff(&plan, &[], &[]), "+ bug #d73a49\n");
+    }
+
+    #[test]
+    fn test_diff_ignore_description() {
+        colored::control::set_override(false);
+        let config: crate::config::LabelConfig = r#"
+delete = ["wontfix"]
+
+[[labels]]
+name = "bug"
+color = "d73a49"
+description = "Something isn't working"
+update_if_exists = true
+
+[[labels]]
+name = "feature"
+color = "a2eeef"
+"#
+        .parse()
+        .unwrap();
+        let existing = vec![label("bug", Some("Broken")), label("wontfix", None)];
+
+        let plan = Plan::build(&config, &existing, false).unwrap();
+        assert_eq!(
+            format_diff(&plan, &existing, &[]),
+            "~ bug description: \"Broken\" → \"Something isn't working\"\n+ feature #a2eeef\n- wontfix\n"
+        );
+
+        let ignore = [DiffField::Description];
+        let plan = Plan::build_ignoring(&config, &existing, false, &ignore).unwrap();
+        assert_eq!(format_diff(&plan, &existing, &ignore), "+ feature #a2eeef\n- wontfix\n");
+    }
+
+    #[test]
+    fn test_diff_summary() {
+        colored::control::set_override(false);
+        let config: crate::config::LabelConfig = r##"
+delete = ["wontfix"]
+
+[[labels]]
+name = "bug"
+color = "#D73A49"
+
+[[labels]]
+name = "feature"
+color = "a2eeef"
+"##
+        .parse()
+        .unwrap();
+        let existing = vec![label("bug", None), label("wontfix", None), label("question", None)];
+
+        // `#D73A49` matches the repository's `d73a49`
+        let plan = Plan::build(&config, &existing, false).unwrap();
+        assert_eq!(
+            format_diff_summary(&plan.summary(), &plan.unmanaged(&existing)),
+            "1 to create, 0 to update, 1 to delete, 1 unchanged\nOnly in the repository: question\n"
+        );
+    }
+
+    #[test]
+    fn test_repo_matrix() {
+        let results = vec![
+            RepoResult {
+                repository: "org/a".to_string(),
+                summary: Some(Summary {
+                    created: 3,
+                    updated: 1,
+                    ..Default::default()
+                }),
+                error: None,
+            },
+            RepoResult {
+                repository: "org/longer-name".to_string(),
+                summary: Some(Summary {
+                    deleted: 12,
+                    failed: 2,
+                    ..Default::default()
+                }),
+                error: None,
+            },
+            RepoResult {
+                repository: "org/gone".to_string(),
+                summary: None,
+                error: Some("Not Found".to_string()),
+            },
+        ];
+
+        assert_eq!(
+            format_repo_matrix(&results),
+            "\
+Repository       Created  Updated  Deleted  Failed
+org/a                  3        1        0       0
+org/longer-name        0        0       12       2
+org/gone               -        -        -       -  error: Not Found
+"
+        );
+    }
+
+    #[test]
+    fn test_banner_can_be_disabled() {
+        assert!(format_banner("o/r", true).unwrap().contains("o/r"));
+        assert_eq!(format_banner("o/r", false), None);
+    }
+
+    #[test]
+    fn test_label_prefix() {
+        assert_eq!(label_prefix("A-cli"), Some("A-"));
+        assert_eq!(label_prefix("WG-async"), Some("WG-"));
+        assert_eq!(label_prefix("type/bug"), Some("type/"));
+        assert_eq!(label_prefix("priority/high-ish"), Some("priority/"));
+        assert_eq!(label_prefix("help-wanted"), None);
+        assert_eq!(label_prefix("good first issue"), None);
+        assert_eq!(label_prefix("/odd"), None);
+    }
+
+    #[test]
+    fn test_group_by_prefix() {
+        let labels = vec![
+            label("bug", None),
+            label("type/feature", None),
+            label("A-cli", None),
+            label("type/bug", None),
+            label("A-api", None),
+        ];
+
+        let groups = group_by_prefix(&labels);
+        let names: Vec<(Option<&str>, Vec<&str>)> = groups
+            .iter()
+            .map(|(p, ls)| (p.as_deref(), ls.iter().map(|l| l.name.as_str()).collect()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                (Some("A-"), vec!["A-api", "A-cli"]),
+                (Some("type/"), vec!["type/bug", "type/feature"]),
+                (None, vec!["bug"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_formats() {
+        let bug = label("bug", None);
+
+        let json = render_label(&bug, OutputFormat::Json, "").unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed["name"], "bug");
+
+        let csv = render_label(&bug, OutputFormat::Csv, "").unwrap();
+        assert_eq!(csv.lines().count(), 2);
+    }
+
+    #[test]
+    fn test_apply_formats() {
+        let report = ApplyReport {
+            repository: "o/r".to_string(),
+            dry_run: true,
+            plan_hash: Some("0123456789abcdef".to_string()),
+            operations: vec![OperationReport {
+                name: "bug".to_string(),
+                action: "create",
+                from: None,
+                result: "dry_run",
+                error: None,
+            }],
+            summary: Summary {
+                created: 1,
+                ..Default::default()
+            },
+        };
+
+        let json = render_apply_report(&report, OutputFormat::Json).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed["operations"][0]["action"], "create");
+        assert_eq!(parsed["summary"]["created"], 1);
+        assert_eq!(parsed["plan_hash"], "0123456789abcdef");
+
+        assert!(render_apply_report(&report, OutputFormat::Csv).is_err());
+    }
+
+    #[test]
+    fn test_github_actions_annotations() {
+        let report = ApplyReport {
+            repository: "o/r".to_string(),
+            dry_run: false,
+            plan_hash: None,
+            operations: vec![OperationReport {
+                name: "bug".to_string(),
+                action: "create",
+                from: None,
+                result: "failed",
+                error: Some("gh CLI error: Validation Failed (HTTP 422)\n100% broken".to_string()),
+            }],
+            summary: Summary {
+                failed: 1,
+                ..Default::default()
+            },
+        };
+
+        let out = render_apply_report(&report, OutputFormat::GithubActions).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(
+            lines[0],
+            "::error title=biao%3A failed to create label::'bug': gh CLI error: Validation Failed (HTTP 422)%0A100%25 broken"
+        );
+        assert_eq!(lines[1], "::notice title=biao apply::o/r: Failed 1");
+
+        let summary = format_step_summary(&report);
+        assert!(summary.contains("| create | bug | failed: gh CLI error"));
+        assert!(summary.contains("- **Failed:** 1"));
+    }
+}
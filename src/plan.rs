@@ -0,0 +1,1119 @@
+use crate::cli::normalize_color;
+use crate::config::{Label, LabelConfig};
+use crate::error::{BiaoError, Result};
+use crate::client::LabelRequest;
+use crate::models::{CreateLabelRequest, GithubLabel, UpdateLabelRequest};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// What a planned operation does to the repository
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum OperationKind {
+    Create,
+    Update,
+    Rename { from: String },
+    Delete,
+    Noop,
+    Skip { reason: String },
+    /// Create of a label that already exists, without `skip_if_exists` or
+    /// `update_if_exists`: fails without sending a request
+    Exists,
+}
+
+impl OperationKind {
+    /// Short machine-readable name of the operation
+    pub fn action(&self) -> &'static str {
+        match self {
+            OperationKind::Create => "create",
+            OperationKind::Update => "update",
+            OperationKind::Rename { .. } => "rename",
+            OperationKind::Delete => "delete",
+            OperationKind::Noop => "noop",
+            OperationKind::Skip { .. } => "skip",
+            OperationKind::Exists => "create",
+        }
+    }
+}
+
+/// Label field that can be left out when comparing with the repository
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DiffField {
+    Color,
+    Description,
+}
+
+/// A single step of a plan, targeting the label `name`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Operation {
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: OperationKind,
+    /// Normalized color to send, if any
+    pub color: Option<String>,
+    pub description: Option<String>,
+}
+
+/// Operations needed to bring a repository in line with a config
+#[derive(Debug, Default)]
+pub struct Plan {
+    pub operations: Vec<Operation>,
+    /// `update_if_match` entries matching a repository label only by case
+    pub warnings: Vec<String>,
+}
+
+impl Operation {
+    /// The label API call carrying out this operation; none for unchanged or skipped labels
+    pub fn request(&self) -> Option<LabelRequest> {
+        match &self.kind {
+            OperationKind::Create => Some(LabelRequest::Create(CreateLabelRequest {
+                name: self.name.clone(),
+                color: self.color.clone().unwrap_or_default(),
+                description: self.description.clone(),
+            })),
+            OperationKind::Update => Some(LabelRequest::Update {
+                name: self.name.clone(),
+                body: UpdateLabelRequest {
+                    name: None,
+                    color: self.color.clone(),
+                    description: self.description.clone(),
+                },
+            }),
+            OperationKind::Rename { from } => Some(LabelRequest::Update {
+                name: from.clone(),
+                body: UpdateLabelRequest {
+                    name: Some(self.name.clone()),
+                    color: self.color.clone(),
+                    description: self.description.clone(),
+                },
+            }),
+            OperationKind::Delete => Some(LabelRequest::Delete {
+                name: self.name.clone(),
+            }),
+            OperationKind::Noop | OperationKind::Skip { .. } | OperationKind::Exists => None,
+        }
+    }
+
+    /// How `actual`, the label read back after applying this operation, differs
+    /// from what was requested; fields the operation didn't set are not compared
+    pub fn mismatches(&self, actual: &GithubLabel) -> Vec<String> {
+        let mut mismatches = Vec::new();
+        if actual.name != self.name {
+            mismatches.push(format!("name is '{}'", actual.name));
+        }
+        if let Some(color) = &self.color {
+            if !actual.color.trim_start_matches('#').eq_ignore_ascii_case(color.trim_start_matches('#')) {
+                mismatches.push(format!("color is #{}, expected #{}", actual.color, color));
+            }
+        }
+        if let Some(description) = &self.description {
+            let current = actual.description.as_deref().unwrap_or("");
+            if current != description {
+                mismatches.push(format!("description is {:?}, expected {:?}", current, description));
+            }
+        }
+        mismatches
+    }
+}
+
+/// Soft cap on the number of labels a repository should end up with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LabelLimit {
+    pub max: usize,
+    /// Fail instead of warning when the cap is exceeded
+    pub strict: bool,
+}
+
+impl Default for LabelLimit {
+    fn default() -> Self {
+        LabelLimit {
+            max: 200,
+            strict: false,
+        }
+    }
+}
+
+impl LabelLimit {
+    /// Warning to show if `count` labels exceed the cap; an error in strict mode
+    pub fn check(&self, count: usize) -> Result<Option<String>> {
+        if count <= self.max {
+            return Ok(None);
+        }
+
+        let message = format!(
+            "the repository would have {} labels, more than the limit of {}. Consider consolidating labels.",
+            count, self.max
+        );
+        if self.strict {
+            Err(BiaoError::InvalidInput(format!("{} (--strict)", message)))
+        } else {
+            Ok(Some(message))
+        }
+    }
+}
+
+/// A plan written by `apply --plan-file`, together with the state of the
+/// labels it touches at the time it was computed
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedPlan {
+    pub repository: String,
+    pub plan_hash: String,
+    /// Touched labels that existed when the plan was computed
+    pub remote: Vec<Label>,
+    pub operations: Vec<Operation>,
+}
+
+/// Number of operations per kind, plus failures recorded during execution
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct Summary {
+    pub created: usize,
+    pub updated: usize,
+    pub renamed: usize,
+    pub deleted: usize,
+    pub unchanged: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    /// Applied labels that didn't read back as requested (`apply --verify`)
+    pub unverified: usize,
+}
+
+impl Summary {
+    pub fn record(&mut self, kind: &OperationKind) {
+        match kind {
+            OperationKind::Create => self.created += 1,
+            OperationKind::Update => self.updated += 1,
+            OperationKind::Rename { .. } => self.renamed += 1,
+            OperationKind::Delete => self.deleted += 1,
+            OperationKind::Noop => self.unchanged += 1,
+            OperationKind::Skip { .. } => self.skipped += 1,
+            OperationKind::Exists => self.failed += 1,
+        }
+    }
+
+    /// Non-zero counts as `(label, count)` rows; unchanged labels are only
+    /// reported when asked for
+    pub fn rows(&self, report_unchanged: bool) -> Vec<(&'static str, usize)> {
+        let unchanged = if report_unchanged { self.unchanged } else { 0 };
+        [
+            ("Created:", self.created),
+            ("Updated:", self.updated),
+            ("Renamed:", self.renamed),
+            ("Deleted:", self.deleted),
+            ("Unchanged:", unchanged),
+            ("Skipped:", self.skipped),
+            ("Failed:", self.failed),
+            ("Unverified:", self.unverified),
+        ]
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .collect()
+    }
+}
+
+impl Plan {
+    /// Compute the operations for `config` given the labels currently in the repository
+    pub fn build(config: &LabelConfig, existing: &[GithubLabel], skip_existing: bool) -> Result<Self> {
+        Self::build_ignoring(config, existing, skip_existing, &[])
+    }
+
+    /// Like [`Plan::build`], but differences in the `ignore`d fields don't cause an update
+    pub fn build_ignoring(
+        config: &LabelConfig,
+        existing: &[GithubLabel],
+        skip_existing: bool,
+        ignore: &[DiffField],
+    ) -> Result<Self> {
+        // GitHub label names are unique case-insensitively
+        let remote: HashMap<String, &GithubLabel> = existing
+            .iter()
+            .map(|l| (l.name.to_lowercase(), l))
+            .collect();
+
+        let mut operations = Vec::new();
+        let mut warnings = Vec::new();
+
+        for label in &config.labels {
+            let color = label.color.as_deref().map(normalize_color).transpose()?;
+            let current = remote.get(&label.name.to_lowercase()).copied();
+
+            let op = |kind| Operation {
+                name: label.name.clone(),
+                kind,
+                color: color.clone(),
+                description: label.description.clone(),
+            };
+
+            let kind = match current {
+                Some(current)
+                    if Self::matches(
+                        current,
+                        color.as_deref().filter(|_| !ignore.contains(&DiffField::Color)),
+                        label
+                            .description
+                            .as_deref()
+                            .filter(|_| !ignore.contains(&DiffField::Description)),
+                    ) =>
+                {
+                    OperationKind::Noop
+                }
+                Some(_) if label.update_if_exists => OperationKind::Update,
+                // A label without color only ever updates the description
+                Some(_) if color.is_none() && label.update_if_match.is_empty() => OperationKind::Update,
+                Some(_) if !label.update_if_match.is_empty() || skip_existing || label.skip_if_exists => {
+                    OperationKind::Skip {
+                        reason: "already exists".to_string(),
+                    }
+                }
+                // Neither skip nor update requested: known to fail, so don't send it
+                Some(_) => OperationKind::Exists,
+                None => {
+                    let renamed = label
+                        .update_if_match
+                        .iter()
+                        .find_map(|alias| Some((alias, *remote.get(&alias.to_lowercase())?)));
+                    if let Some((alias, old)) = renamed.filter(|(alias, old)| **alias != old.name) {
+                        warnings.push(format!(
+                            "'{}' in update_if_match of '{}' matches the repository's '{}' only by case",
+                            alias, label.name, old.name
+                        ));
+                    }
+
+                    match renamed.map(|(_, old)| old) {
+                        Some(old) => OperationKind::Rename {
+                            from: old.name.clone(),
+                        },
+                        None if color.is_some() => OperationKind::Create,
+                        // Nothing to update, and no color to create it with
+                        None if label.update_if_match.is_empty() => OperationKind::Skip {
+                            reason: "not in the repository and has no color to create it".to_string(),
+                        },
+                        None => OperationKind::Skip {
+                            reason: "no matching label to rename".to_string(),
+                        },
+                    }
+                }
+            };
+
+            // Only send the fields that differ from the label being changed
+            let target = match &kind {
+                OperationKind::Update => current,
+                OperationKind::Rename { from } => remote.get(&from.to_lowercase()).copied(),
+                _ => None,
+            };
+            let mut operation = op(kind);
+            if let Some(target) = target {
+                operation.color = operation.color.filter(|c| !c.eq_ignore_ascii_case(&target.color));
+                operation.description = operation
+                    .description
+                    .filter(|d| target.description.as_deref().unwrap_or("") != d);
+            }
+            operations.push(operation);
+        }
+
+        for name in &config.delete {
+            let kind = match remote.get(&name.to_lowercase()) {
+                Some(_) => OperationKind::Delete,
+                None => OperationKind::Noop,
+            };
+            operations.push(Operation {
+                name: name.clone(),
+                kind,
+                color: None,
+                description: None,
+            });
+        }
+
+        Ok(Plan { operations, warnings })
+    }
+
+    /// Stable fingerprint of the plan, independent of operation order.
+    ///
+    /// Operations are sorted and serialized canonically, then hashed with
+    /// 64-bit FNV-1a, so the same repository state and config always give the
+    /// same hash and any change to an operation changes it.
+    pub fn hash(&self) -> String {
+        let mut entries: Vec<String> = self
+            .operations
+            .iter()
+            .map(|op| serde_json::to_string(op).unwrap_or_default())
+            .collect();
+        entries.sort();
+
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for byte in entries.join("\n").bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        format!("{:016x}", hash)
+    }
+
+    /// Keep only creates of labels missing from `existing` (`--only-new`).
+    ///
+    /// Updates, renames and deletes are skipped, as is a create of a label
+    /// that exists already, even if its color or description differ.
+    pub fn retain_new(&mut self, existing: &[GithubLabel]) {
+        let present: HashSet<String> = existing.iter().map(|l| l.name.to_lowercase()).collect();
+
+        for op in &mut self.operations {
+            let reason = match op.kind {
+                OperationKind::Create if !present.contains(&op.name.to_lowercase()) => continue,
+                OperationKind::Create | OperationKind::Exists => "already exists",
+                OperationKind::Update | OperationKind::Rename { .. } => "--only-new leaves existing labels alone",
+                OperationKind::Delete => "--only-new never deletes",
+                OperationKind::Noop | OperationKind::Skip { .. } => continue,
+            };
+            op.kind = OperationKind::Skip {
+                reason: reason.to_string(),
+            };
+        }
+    }
+
+    /// Turn updates, renames and deletes of labels matching a `--protect` glob
+    /// into skips; returns the blocked operations, e.g. `delete 'bug'`
+    pub fn protect(&mut self, globs: &[String]) -> Vec<String> {
+        let mut blocked = Vec::new();
+        for op in &mut self.operations {
+            let target = match &op.kind {
+                OperationKind::Update | OperationKind::Delete => &op.name,
+                OperationKind::Rename { from } => from,
+                OperationKind::Create | OperationKind::Exists | OperationKind::Noop | OperationKind::Skip { .. } => {
+                    continue
+                }
+            };
+            let Some(glob) = globs.iter().find(|g| crate::filter::glob_match(g, target)) else {
+                continue;
+            };
+
+            blocked.push(format!("{} '{}'", op.kind.action(), target));
+            op.kind = OperationKind::Skip {
+                reason: format!("protected by --protect '{}'", glob),
+            };
+        }
+        blocked
+    }
+
+    /// Number of labels after running the plan against `current` existing labels
+    pub fn label_count_after(&self, current: usize) -> usize {
+        let created = self.operations.iter().filter(|op| op.kind == OperationKind::Create).count();
+        let deleted = self.operations.iter().filter(|op| op.kind == OperationKind::Delete).count();
+        (current + created).saturating_sub(deleted)
+    }
+
+    /// Operations that would modify the repository
+    pub fn changes(&self) -> impl Iterator<Item = &Operation> {
+        self.operations
+            .iter()
+            .filter(|op| !matches!(op.kind, OperationKind::Noop | OperationKind::Skip { .. }))
+    }
+
+    /// Count the planned operations by kind
+    pub fn summary(&self) -> Summary {
+        let mut summary = Summary::default();
+        for op in &self.operations {
+            summary.record(&op.kind);
+        }
+        summary
+    }
+
+    /// Repository labels the plan never mentions, in repository order
+    pub fn unmanaged<'a>(&self, existing: &'a [GithubLabel]) -> Vec<&'a GithubLabel> {
+        let touched = self.touched_names();
+        existing
+            .iter()
+            .filter(|label| touched.binary_search(&label.name.to_lowercase()).is_err())
+            .collect()
+    }
+
+    /// Consecutive runs of operations that can be sent concurrently.
+    ///
+    /// A batch ends before an operation touching a label an earlier operation
+    /// of the batch touches, so a rename and a later delete or create of the
+    /// same name still happen in plan order. Running the batches one after
+    /// another gives the same result as applying the plan sequentially.
+    pub fn batches(&self) -> Vec<std::ops::Range<usize>> {
+        let mut batches = Vec::new();
+        let mut start = 0;
+        let mut names = HashSet::new();
+        for (index, op) in self.operations.iter().enumerate() {
+            let touched: Vec<String> = match &op.kind {
+                OperationKind::Noop | OperationKind::Skip { .. } => continue,
+                OperationKind::Rename { from } => vec![op.name.to_lowercase(), from.to_lowercase()],
+                _ => vec![op.name.to_lowercase()],
+            };
+            if touched.iter().any(|name| names.contains(name)) {
+                batches.push(start..index);
+                start = index;
+                names.clear();
+            }
+            names.extend(touched);
+        }
+        if start < self.operations.len() {
+            batches.push(start..self.operations.len());
+        }
+        batches
+    }
+
+    /// Lowercased names of every label the plan reads or writes
+    fn touched_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .operations
+            .iter()
+            .flat_map(|op| match &op.kind {
+                OperationKind::Rename { from } => vec![op.name.to_lowercase(), from.to_lowercase()],
+                _ => vec![op.name.to_lowercase()],
+            })
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    fn matches(current: &GithubLabel, color: Option<&str>, description: Option<&str>) -> bool {
+        let color_matches = color.is_none_or(|c| c.eq_ignore_ascii_case(&current.color));
+        let description_matches =
+            description.is_none_or(|d| current.description.as_deref().unwrap_or("") == d);
+        color_matches && description_matches
+    }
+}
+
+impl SavedPlan {
+    /// Capture `plan` along with the labels in `existing` that it touches
+    pub fn new(repository: String, plan: &Plan, existing: &[GithubLabel]) -> Self {
+        let touched = plan.touched_names();
+        let remote = existing
+            .iter()
+            .filter(|l| touched.contains(&l.name.to_lowercase()))
+            .map(Label::from)
+            .collect();
+
+        SavedPlan {
+            repository,
+            plan_hash: plan.hash(),
+            remote,
+            operations: plan.operations.clone(),
+        }
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            BiaoError::Io(std::io::Error::other(format!(
+                "Failed to read plan file {}: {}",
+                path.display(),
+                e
+            )))
+        })?;
+
+        serde_json::from_str(&content).map_err(|e| BiaoError::ParseError {
+            message: format!("Failed to parse plan file {}: {}", path.display(), e),
+        })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let content = serde_json::to_string_pretty(self).map_err(|e| BiaoError::ParseError {
+            message: format!("Failed to serialize plan: {}", e),
+        })?;
+        std::fs::write(path, content + "\n")?;
+        Ok(())
+    }
+
+    pub fn plan(&self) -> Plan {
+        Plan {
+            operations: self.operations.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Touched labels whose current state differs from when the plan was computed
+    pub fn stale_labels(&self, current: &[GithubLabel]) -> Vec<String> {
+        let saved: HashMap<String, &Label> = self
+            .remote
+            .iter()
+            .map(|l| (l.name.to_lowercase(), l))
+            .collect();
+        let current: HashMap<String, Label> = current
+            .iter()
+            .map(|l| (l.name.to_lowercase(), Label::from(l)))
+            .collect();
+
+        self.plan()
+            .touched_names()
+            .into_iter()
+            .filter(|name| match (saved.get(name), current.get(name)) {
+                (None, None) => false,
+                (Some(saved), Some(current)) => {
+                    saved.name != current.name
+                        || !saved
+                            .color
+                            .as_deref()
+                            .unwrap_or_default()
+                            .eq_ignore_ascii_case(current.color.as_deref().unwrap_or_default())
+                        || saved.description != current.description
+                }
+                _ => true,
+            })
+            .collect()
+    }
+}
+
+/// Repository labels `prune` removes: those the config neither defines nor
+/// renames through `update_if_match`, optionally sparing GitHub's defaults
+pub fn prunable<'a>(config: &LabelConfig, existing: &'a [GithubLabel], keep_default: bool) -> Vec<&'a GithubLabel> {
+    let kept: HashSet<String> = config
+        .labels
+        .iter()
+        .flat_map(|label| std::iter::once(&label.name).chain(&label.update_if_match))
+        .map(|name| name.to_lowercase())
+        .collect();
+
+    existing
+        .iter()
+        .filter(|label| !kept.contains(&label.name.to_lowercase()))
+        .filter(|label| !(keep_default && label.default))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remote(name: &str, color: &str, description: Option<&str>) -> GithubLabel {
+        GithubLabel {
+            name: name.to_string(),
+            color: color.to_string(),
+            description: description.map(|d| d.to_string()),
+            url: format!("https://api.github.com/repos/o/r/labels/{}", name),
+            id: 1,
+            node_id: "node".to_string(),
+            default: false,
+        }
+    }
+
+    #[test]
+    fn test_retain_new_leaves_existing_alone() {
+        let toml = r#"
+delete = ["wontfix"]
+
+[[labels]]
+name = "bug"
+color = "d73a49"
+description = "Something isn't working"
+update_if_exists = true
+
+[[labels]]
+name = "feature"
+color = "a2eeef"
+"#;
+        let config: LabelConfig = toml::from_str(toml).unwrap();
+        let existing = vec![remote("Bug", "ff0000", None), remote("wontfix", "ffffff", None)];
+
+        let mut plan = Plan::build(&config, &existing, false).unwrap();
+        assert_eq!(plan.operations[0].kind, OperationKind::Update);
+
+        plan.retain_new(&existing);
+        let changes: Vec<(&str, &OperationKind)> =
+            plan.changes().map(|op| (op.name.as_str(), &op.kind)).collect();
+        assert_eq!(changes, vec![("feature", &OperationKind::Create)]);
+        assert!(matches!(plan.operations[0].kind, OperationKind::Skip { .. }));
+        assert!(matches!(plan.operations[2].kind, OperationKind::Skip { .. }));
+    }
+
+    #[test]
+    fn test_updates_only_send_changed_fields() {
+        let toml = r#"
+[[labels]]
+name = "bug"
+color = "d73a49"
+description = "Something isn't working"
+update_if_exists = true
+
+[[labels]]
+name = "docs"
+color = "0075ca"
+description = "Documentation"
+update_if_exists = true
+
+[[labels]]
+name = "feature"
+color = "a2eeef"
+update_if_match = ["enhancement"]
+"#;
+        let config: LabelConfig = toml::from_str(toml).unwrap();
+        let existing = vec![
+            remote("bug", "D73A49", Some("Broken")),
+            remote("docs", "ffffff", Some("Documentation")),
+            remote("enhancement", "a2eeef", Some("New feature")),
+        ];
+
+        let plan = Plan::build(&config, &existing, false).unwrap();
+        let fields: Vec<(&OperationKind, Option<&str>, Option<&str>)> = plan
+            .operations
+            .iter()
+            .map(|op| (&op.kind, op.color.as_deref(), op.description.as_deref()))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                // Description-only change
+                (&OperationKind::Update, None, Some("Something isn't working")),
+                // Color-only change
+                (&OperationKind::Update, Some("0075ca"), None),
+                // A rename with nothing else to change only sends the new name
+                (
+                    &OperationKind::Rename {
+                        from: "enhancement".to_string()
+                    },
+                    None,
+                    None
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_protect_blocks_updates_and_deletes() {
+        let toml = r#"
+delete = ["ci/stale", "wontfix"]
+
+[[labels]]
+name = "ci/passing"
+color = "0e8a16"
+update_if_exists = true
+
+[[labels]]
+name = "bug"
+color = "d73a49"
+update_if_exists = true
+
+[[labels]]
+name = "ci/new"
+color = "0e8a16"
+"#;
+        let config: LabelConfig = toml::from_str(toml).unwrap();
+        let existing = vec![
+            remote("ci/passing", "ff0000", None),
+            remote("bug", "ff0000", None),
+            remote("ci/stale", "ffffff", None),
+            remote("wontfix", "ffffff", None),
+        ];
+
+        let mut plan = Plan::build(&config, &existing, false).unwrap();
+        let blocked = plan.protect(&["ci/*".to_string()]);
+        assert_eq!(blocked, vec!["update 'ci/passing'", "delete 'ci/stale'"]);
+
+        let changes: Vec<(&str, &OperationKind)> =
+            plan.changes().map(|op| (op.name.as_str(), &op.kind)).collect();
+        assert_eq!(
+            changes,
+            vec![
+                ("bug", &OperationKind::Update),
+                ("ci/new", &OperationKind::Create),
+                ("wontfix", &OperationKind::Delete),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_kind_counts() {
+        let toml = r#"
+delete = ["wontfix"]
+
+[[labels]]
+name = "bug"
+color = "d73a49"
+update_if_match = ["Bug Report"]
+
+[[labels]]
+name = "feature"
+color = "a2eeef"
+"#;
+        let config: LabelConfig = toml::from_str(toml).unwrap();
+        let existing = vec![
+            remote("bug report", "ff0000", None),
+            remote("wontfix", "ffffff", None),
+        ];
+
+        let plan = Plan::build(&config, &existing, false).unwrap();
+        assert_eq!(
+            plan.operations[0].kind,
+            OperationKind::Rename {
+                from: "bug report".to_string()
+            }
+        );
+
+        let summary = plan.summary();
+        assert_eq!(summary.renamed, 1);
+        assert_eq!(summary.created, 1);
+        assert_eq!(summary.deleted, 1);
+        assert_eq!(summary.updated, 0);
+        assert_eq!(summary.unchanged, 0);
+    }
+
+    #[test]
+    fn test_plan_hash_ignores_order_but_not_colors() {
+        let build = |toml: &str| {
+            let config: LabelConfig = toml::from_str(toml).unwrap();
+            Plan::build(&config, &[remote("wontfix", "ffffff", None)], false)
+                .unwrap()
+                .hash()
+        };
+
+        let original = build(
+            r#"
+delete = ["wontfix"]
+[[labels]]
+name = "bug"
+color = "d73a49"
+[[labels]]
+name = "feature"
+color = "a2eeef"
+"#,
+        );
+        let reordered = build(
+            r#"
+delete = ["wontfix"]
+[[labels]]
+name = "feature"
+color = "a2eeef"
+[[labels]]
+name = "bug"
+color = "d73a49"
+"#,
+        );
+        let recolored = build(
+            r#"
+delete = ["wontfix"]
+[[labels]]
+name = "bug"
+color = "b60205"
+[[labels]]
+name = "feature"
+color = "a2eeef"
+"#,
+        );
+
+        assert_eq!(original, reordered);
+        assert_ne!(original, recolored);
+        assert_eq!(original.len(), 16);
+    }
+
+    fn saved_plan(existing: &[GithubLabel]) -> SavedPlan {
+        let toml = r#"
+delete = ["wontfix"]
+
+[[labels]]
+name = "bug"
+color = "d73a49"
+update_if_match = ["defect"]
+
+[[labels]]
+name = "feature"
+color = "a2eeef"
+"#;
+        let config: LabelConfig = toml::from_str(toml).unwrap();
+        let plan = Plan::build(&config, existing, false).unwrap();
+        SavedPlan::new("o/r".to_string(), &plan, existing)
+    }
+
+    #[test]
+    fn test_saved_plan_round_trip() {
+        let existing = vec![
+            remote("defect", "ff0000", None),
+            remote("wontfix", "ffffff", Some("Not planned")),
+            remote("unrelated", "000000", None),
+        ];
+        let saved = saved_plan(&existing);
+        assert_eq!(saved.remote.len(), 2);
+
+        let json = serde_json::to_string(&saved).unwrap();
+        let loaded: SavedPlan = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.repository, "o/r");
+        assert_eq!(loaded.plan_hash, saved.plan_hash);
+        assert_eq!(loaded.plan().hash(), saved.plan_hash);
+        assert_eq!(
+            loaded.operations[0].kind,
+            OperationKind::Rename {
+                from: "defect".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_saved_plan_staleness() {
+        let existing = vec![
+            remote("defect", "ff0000", None),
+            remote("wontfix", "ffffff", None),
+            remote("unrelated", "000000", None),
+        ];
+        let saved = saved_plan(&existing);
+
+        // Unrelated changes don't invalidate the plan
+        let mut current = existing.clone();
+        current[2].color = "123456".to_string();
+        assert!(saved.stale_labels(&current).is_empty());
+
+        // A touched label was recolored, another deleted, and a planned one created
+        current[0].color = "00ff00".to_string();
+        current.remove(1);
+        current.push(remote("feature", "a2eeef", None));
+        assert_eq!(saved.stale_labels(&current), vec!["defect", "feature", "wontfix"]);
+    }
+
+    #[test]
+    fn test_label_limit_warning() {
+        let config: LabelConfig = toml::from_str(
+            r#"
+delete = ["old"]
+[[labels]]
+name = "a"
+color = "000000"
+[[labels]]
+name = "b"
+color = "000000"
+"#,
+        )
+        .unwrap();
+        let existing = vec![remote("old", "ffffff", None), remote("x", "ffffff", None)];
+        let plan = Plan::build(&config, &existing, false).unwrap();
+        assert_eq!(plan.label_count_after(existing.len()), 3);
+
+        let limit = LabelLimit { max: 3, strict: false };
+        assert_eq!(limit.check(3).unwrap(), None);
+        assert!(limit.check(4).unwrap().unwrap().contains("4 labels"));
+
+        let strict = LabelLimit { max: 2, strict: true };
+        assert!(strict.check(plan.label_count_after(existing.len())).is_err());
+    }
+
+    #[test]
+    fn test_build_ignoring_description() {
+        let config: LabelConfig = toml::from_str(
+            r#"
+[[labels]]
+name = "bug"
+color = "d73a49"
+description = "Something isn't working"
+update_if_exists = true
+"#,
+        )
+        .unwrap();
+        let existing = vec![remote("bug", "d73a49", Some("Broken"))];
+
+        let plan = Plan::build(&config, &existing, false).unwrap();
+        assert_eq!(plan.operations[0].kind, OperationKind::Update);
+
+        let plan = Plan::build_ignoring(&config, &existing, false, &[DiffField::Description]).unwrap();
+        assert_eq!(plan.operations[0].kind, OperationKind::Noop);
+
+        // Ignoring color doesn't hide a description change
+        let plan = Plan::build_ignoring(&config, &existing, false, &[DiffField::Color]).unwrap();
+        assert_eq!(plan.operations[0].kind, OperationKind::Update);
+    }
+
+    #[test]
+    fn test_alias_matching_only_by_case_warns() {
+        let config: LabelConfig = toml::from_str(
+            r#"
+[[labels]]
+name = "bug"
+color = "d73a49"
+update_if_match = ["Defect"]
+"#,
+        )
+        .unwrap();
+
+        let plan = Plan::build(&config, &[remote("defect", "d73a49", None)], false).unwrap();
+        assert_eq!(plan.operations[0].kind, OperationKind::Rename { from: "defect".to_string() });
+        assert_eq!(
+            plan.warnings,
+            vec!["'Defect' in update_if_match of 'bug' matches the repository's 'defect' only by case"]
+        );
+
+        let plan = Plan::build(&config, &[remote("Defect", "d73a49", None)], false).unwrap();
+        assert!(plan.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_verify_mismatches() {
+        let op = Operation {
+            name: "bug".to_string(),
+            kind: OperationKind::Create,
+            color: Some("d73a49".to_string()),
+            description: Some("Something isn't working".to_string()),
+        };
+        assert!(op.mismatches(&remote("bug", "D73A49", Some("Something isn't working"))).is_empty());
+        assert_eq!(
+            op.mismatches(&remote("Bug", "ff0000", Some("Something isn't working "))),
+            vec![
+                "name is 'Bug'".to_string(),
+                "color is #ff0000, expected #d73a49".to_string(),
+                "description is \"Something isn't working \", expected \"Something isn't working\"".to_string(),
+            ]
+        );
+
+        // An update that only sets the description doesn't check the color
+        let op = Operation {
+            kind: OperationKind::Update,
+            color: None,
+            ..op
+        };
+        assert!(op.mismatches(&remote("bug", "ff0000", Some("Something isn't working"))).is_empty());
+    }
+
+    #[test]
+    fn test_empty_repository_plans_only_creates() {
+        let config: LabelConfig = toml::from_str(
+            r#"
+delete = ["wontfix"]
+
+[[labels]]
+name = "bug"
+color = "d73a49"
+update_if_exists = true
+
+[[labels]]
+name = "T-bug"
+color = "d73a49"
+update_if_match = ["type/bug"]
+
+[[labels]]
+name = "docs"
+description = "Only a description"
+"#,
+        )
+        .unwrap();
+
+        let plan = Plan::build(&config, &[], false).unwrap();
+        let changes: Vec<(&str, &OperationKind)> =
+            plan.changes().map(|op| (op.name.as_str(), &op.kind)).collect();
+        assert_eq!(
+            changes,
+            vec![("bug", &OperationKind::Create), ("T-bug", &OperationKind::Create)]
+        );
+        // A colorless label can't be created, and there is nothing to delete
+        assert!(matches!(plan.operations[2].kind, OperationKind::Skip { .. }));
+        assert_eq!(plan.operations[3].kind, OperationKind::Noop);
+        assert_eq!(plan.label_count_after(0), 2);
+    }
+
+    #[test]
+    fn test_summary_rows_hide_unchanged_by_default() {
+        let summary = Summary {
+            created: 1,
+            unchanged: 3,
+            ..Default::default()
+        };
+
+        assert_eq!(summary.rows(false), vec![("Created:", 1)]);
+        assert_eq!(summary.rows(true), vec![("Created:", 1), ("Unchanged:", 3)]);
+    }
+
+    #[test]
+    fn test_plan_noop_and_skip() {
+        let toml = r##"
+delete = ["gone"]
+
+[[labels]]
+name = "bug"
+color = "#D73A49"
+description = "Something isn't working"
+
+[[labels]]
+name = "feature"
+color = "a2eeef"
+skip_if_exists = true
+
+[[labels]]
+name = "docs"
+color = "0075ca"
+update_if_exists = true
+"##;
+        let config: LabelConfig = toml::from_str(toml).unwrap();
+        let existing = vec![
+            remote("bug", "d73a49", Some("Something isn't working")),
+            remote("Feature", "000000", None),
+            remote("docs", "000000", None),
+        ];
+
+        let summary = Plan::build(&config, &existing, false).unwrap().summary();
+        assert_eq!(summary.unchanged, 2);
+        assert_eq!(summary.skipped, 1);
+        assert_eq!(summary.updated, 1);
+        assert_eq!(summary.created, 0);
+        assert_eq!(summary.deleted, 0);
+    }
+
+    #[test]
+    fn test_existing_label_without_flags_fails_without_request() {
+        let config: LabelConfig = "[[labels]]\nname = \"bug\"\ncolor = \"ff0000\"\n".parse().unwrap();
+        let existing = vec![remote("Bug", "d73a49", None)];
+
+        let plan = Plan::build(&config, &existing, false).unwrap();
+        assert_eq!(plan.operations[0].kind, OperationKind::Exists);
+        assert!(plan.operations[0].request().is_none());
+        assert_eq!(plan.summary().failed, 1);
+
+        // Either flag, or --skip-existing, settles it
+        let plan = Plan::build(&config, &existing, true).unwrap();
+        assert!(matches!(plan.operations[0].kind, OperationKind::Skip { .. }));
+    }
+
+    #[test]
+    fn test_prunable_keeps_config_labels_and_aliases() {
+        let config: LabelConfig = r#"
+[[labels]]
+name = "bug"
+color = "d73a49"
+
+[[labels]]
+name = "T-feature"
+update_if_match = ["enhancement"]
+"#
+        .parse()
+        .unwrap();
+        let mut question = remote("question", "d876e3", None);
+        question.default = true;
+        let existing = vec![
+            remote("Bug", "d73a49", None),
+            remote("enhancement", "a2eeef", None),
+            remote("stale", "ffffff", None),
+            question,
+        ];
+
+        let names = |labels: Vec<&GithubLabel>| labels.iter().map(|l| l.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(prunable(&config, &existing, false)), ["stale", "question"]);
+        assert_eq!(names(prunable(&config, &existing, true)), ["stale"]);
+    }
+
+    #[test]
+    fn test_batches_keep_same_label_in_order() {
+        let config: LabelConfig = r#"
+delete = ["old"]
+
+[[labels]]
+name = "a"
+color = "d73a49"
+
+[[labels]]
+name = "b"
+color = "a2eeef"
+update_if_match = ["old"]
+
+[[labels]]
+name = "c"
+color = "0075ca"
+"#
+        .parse()
+        .unwrap();
+        let existing = vec![remote("old", "ffffff", None)];
+
+        // Deleting `old` must wait for it to be renamed to `b`
+        let plan = Plan::build(&config, &existing, false).unwrap();
+        assert_eq!(plan.batches(), vec![0..3, 3..4]);
+
+        let plan = Plan::build(&config, &[], false).unwrap();
+        assert_eq!(plan.batches(), vec![0..4]);
+    }
+}
@@ -0,0 +1,187 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Number of slowest requests listed in a profile
+const SLOWEST_SHOWN: usize = 5;
+
+/// Kind of GitHub request, derived from its HTTP method
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Phase {
+    List,
+    Create,
+    Update,
+    Delete,
+}
+
+impl Phase {
+    pub fn from_method(method: &str) -> Self {
+        match method.to_ascii_uppercase().as_str() {
+            "POST" => Phase::Create,
+            "PATCH" => Phase::Update,
+            "DELETE" => Phase::Delete,
+            _ => Phase::List,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Phase::List => "list",
+            Phase::Create => "create",
+            Phase::Update => "update",
+            Phase::Delete => "delete",
+        }
+    }
+}
+
+/// Duration of a single `gh` call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timing {
+    pub phase: Phase,
+    /// Method and path of the request, e.g. `PATCH repos/o/r/labels/bug`
+    pub request: String,
+    pub duration: Duration,
+}
+
+/// Timings collected while `--profile` is on
+#[derive(Debug)]
+pub struct Profile {
+    started: Instant,
+    timings: Mutex<Vec<Timing>>,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Profile {
+            started: Instant::now(),
+            timings: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl Profile {
+    pub fn record(&self, phase: Phase, request: String, duration: Duration) {
+        if let Ok(mut timings) = self.timings.lock() {
+            timings.push(Timing {
+                phase,
+                request,
+                duration,
+            });
+        }
+    }
+
+    pub fn report(&self) -> ProfileReport {
+        let timings = self.timings.lock().map(|t| t.clone()).unwrap_or_default();
+        ProfileReport::new(timings, self.started.elapsed())
+    }
+}
+
+/// Aggregated timings: totals, per-phase breakdown and the slowest requests
+#[derive(Debug, PartialEq, Eq)]
+pub struct ProfileReport {
+    pub wall_time: Duration,
+    pub total: Duration,
+    pub calls: usize,
+    /// `(phase, calls, total duration)`, only for phases that ran
+    pub phases: Vec<(Phase, usize, Duration)>,
+    pub slowest: Vec<Timing>,
+}
+
+impl ProfileReport {
+    pub fn new(mut timings: Vec<Timing>, wall_time: Duration) -> Self {
+        let mut phases: Vec<(Phase, usize, Duration)> = Vec::new();
+        for timing in &timings {
+            match phases.iter_mut().find(|(phase, _, _)| *phase == timing.phase) {
+                Some((_, calls, total)) => {
+                    *calls += 1;
+                    *total += timing.duration;
+                }
+                None => phases.push((timing.phase, 1, timing.duration)),
+            }
+        }
+        phases.sort_by_key(|(phase, _, _)| *phase);
+
+        let total = timings.iter().map(|t| t.duration).sum();
+        let calls = timings.len();
+        timings.sort_by_key(|t| std::cmp::Reverse(t.duration));
+        timings.truncate(SLOWEST_SHOWN);
+
+        ProfileReport {
+            wall_time,
+            total,
+            calls,
+            phases,
+            slowest: timings,
+        }
+    }
+
+    pub fn format(&self) -> String {
+        let mut out = format!(
+            "Total: {:.2?} in {} gh call(s), {:.2?} wall time\n",
+            self.total, self.calls, self.wall_time
+        );
+
+        out.push_str("By phase:\n");
+        for (phase, calls, total) in &self.phases {
+            out.push_str(&format!("  {:<7} {:>4} call(s)  {:.2?}\n", phase.name(), calls, total));
+        }
+
+        if !self.slowest.is_empty() {
+            out.push_str("Slowest:\n");
+            for timing in &self.slowest {
+                out.push_str(&format!("  {:.2?}  {}\n", timing.duration, timing.request));
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timing(phase: Phase, request: &str, millis: u64) -> Timing {
+        Timing {
+            phase,
+            request: request.to_string(),
+            duration: Duration::from_millis(millis),
+        }
+    }
+
+    #[test]
+    fn test_profile_aggregation() {
+        let timings = vec![
+            timing(Phase::Create, "POST repos/o/r/labels", 300),
+            timing(Phase::List, "GET repos/o/r/labels", 120),
+            timing(Phase::Delete, "DELETE repos/o/r/labels/old", 80),
+            timing(Phase::Create, "POST repos/o/r/labels", 250),
+            timing(Phase::Update, "PATCH repos/o/r/labels/bug", 900),
+            timing(Phase::Create, "POST repos/o/r/labels", 50),
+            timing(Phase::Delete, "DELETE repos/o/r/labels/older", 70),
+        ];
+
+        let report = ProfileReport::new(timings, Duration::from_secs(2));
+        assert_eq!(report.calls, 7);
+        assert_eq!(report.total, Duration::from_millis(1770));
+        assert_eq!(
+            report.phases,
+            vec![
+                (Phase::List, 1, Duration::from_millis(120)),
+                (Phase::Create, 3, Duration::from_millis(600)),
+                (Phase::Update, 1, Duration::from_millis(900)),
+                (Phase::Delete, 2, Duration::from_millis(150)),
+            ]
+        );
+
+        let slowest: Vec<u128> = report.slowest.iter().map(|t| t.duration.as_millis()).collect();
+        assert_eq!(slowest, vec![900, 300, 250, 120, 80]);
+        assert!(report.format().contains("PATCH repos/o/r/labels/bug"));
+    }
+
+    #[test]
+    fn test_phase_from_method() {
+        assert_eq!(Phase::from_method("GET"), Phase::List);
+        assert_eq!(Phase::from_method("post"), Phase::Create);
+        assert_eq!(Phase::from_method("PATCH"), Phase::Update);
+        assert_eq!(Phase::from_method("DELETE"), Phase::Delete);
+    }
+}
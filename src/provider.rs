@@ -0,0 +1,88 @@
+use crate::client::GithubClient;
+use crate::error::Result;
+use crate::models::{CreateLabelRequest, GithubLabel, UpdateLabelRequest};
+use std::future::Future;
+use std::pin::Pin;
+
+/// Future returned by [`LabelProvider`] methods, boxed so the trait can be used as `dyn`
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Label CRUD on one repository, whichever forge hosts it.
+///
+/// `list`, `get`, `create`, `update` and `delete` go through this trait; the
+/// rest of biao (plans, snapshots, org-wide apply) is GitHub-only for now.
+pub trait LabelProvider: Send + Sync {
+    /// `owner/name` of the repository, as shown in banners
+    fn repo_url(&self) -> String;
+
+    /// Web page of a label
+    fn label_web_url(&self, name: &str) -> String;
+
+    fn list_labels(&self) -> BoxFuture<'_, Result<Vec<GithubLabel>>>;
+
+    fn get_label<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<GithubLabel>>;
+
+    fn create_label<'a>(&'a self, label: &'a CreateLabelRequest) -> BoxFuture<'a, Result<GithubLabel>>;
+
+    fn update_label<'a>(&'a self, name: &'a str, label: &'a UpdateLabelRequest) -> BoxFuture<'a, Result<GithubLabel>>;
+
+    fn delete_label<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<()>>;
+}
+
+impl LabelProvider for GithubClient {
+    fn repo_url(&self) -> String {
+        GithubClient::repo_url(self)
+    }
+
+    fn label_web_url(&self, name: &str) -> String {
+        GithubClient::label_web_url(self, name)
+    }
+
+    fn list_labels(&self) -> BoxFuture<'_, Result<Vec<GithubLabel>>> {
+        Box::pin(GithubClient::list_labels(self))
+    }
+
+    fn get_label<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<GithubLabel>> {
+        Box::pin(GithubClient::get_label(self, name))
+    }
+
+    fn create_label<'a>(&'a self, label: &'a CreateLabelRequest) -> BoxFuture<'a, Result<GithubLabel>> {
+        Box::pin(GithubClient::create_label(self, label))
+    }
+
+    fn update_label<'a>(&'a self, name: &'a str, label: &'a UpdateLabelRequest) -> BoxFuture<'a, Result<GithubLabel>> {
+        Box::pin(GithubClient::update_label(self, name, label))
+    }
+
+    fn delete_label<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<()>> {
+        Box::pin(GithubClient::delete_label(self, name))
+    }
+}
+
+/// Whether `host` runs GitLab: gitlab.com, or the self-managed instance named by `GITLAB_HOST`
+pub fn is_gitlab_host(host: &str) -> bool {
+    let configured = std::env::var("GITLAB_HOST").ok();
+    is_gitlab_host_with(host, configured.as_deref())
+}
+
+fn is_gitlab_host_with(host: &str, configured: Option<&str>) -> bool {
+    // GITLAB_HOST may be given as a URL, like glab accepts it
+    let configured = configured
+        .map(|c| c.trim_start_matches("https://").trim_end_matches('/'))
+        .filter(|c| !c.is_empty());
+    host.eq_ignore_ascii_case("gitlab.com") || configured.is_some_and(|c| c.eq_ignore_ascii_case(host))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_gitlab_host() {
+        assert!(is_gitlab_host_with("gitlab.com", None));
+        assert!(is_gitlab_host_with("GitLab.com", None));
+        assert!(!is_gitlab_host_with("github.com", None));
+        assert!(is_gitlab_host_with("git.example.com", Some("https://git.example.com/")));
+        assert!(!is_gitlab_host_with("git.example.com", Some("")));
+    }
+}
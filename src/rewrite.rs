@@ -0,0 +1,187 @@
+use crate::config::LabelConfig;
+use crate::error::{BiaoError, Result};
+use toml_edit::{ArrayOfTables, DocumentMut, Item, Table, Value};
+
+/// Keys identifying the entries of `[[labels]]` and `[[label_group]]` across rewrites
+const ENTRY_KEYS: &[&str] = &["name", "prefix"];
+
+/// `biao sort-config`: the config canonically ordered, comments included
+pub fn sort_config(content: &str) -> Result<String> {
+    let mut config = LabelConfig::parse_unexpanded(content)?;
+    config.canonicalize()?;
+    let sorted = toml::to_string_pretty(&config)
+        .map_err(|e| BiaoError::InvalidInput(format!("Failed to serialize config: {}", e)))?;
+    preserve_formatting(content, &sorted)
+}
+
+/// Rewrite the config file `existing` to the content of `updated`, keeping the
+/// comments and formatting of everything that is still there.
+///
+/// `[[labels]]` and `[[label_group]]` entries are matched by name (or prefix)
+/// and take the order of `updated`; a comment above an entry moves with it.
+/// Values that didn't change keep their original formatting.
+pub fn preserve_formatting(existing: &str, updated: &str) -> Result<String> {
+    let mut document = parse(existing)?;
+    let updated = parse(updated)?;
+    merge_table(document.as_table_mut(), updated.as_table());
+    renumber_tables(document.as_table_mut(), &mut 1);
+    Ok(document.to_string())
+}
+
+fn parse(content: &str) -> Result<DocumentMut> {
+    content
+        .parse()
+        .map_err(|e| BiaoError::InvalidInput(format!("Failed to parse TOML config: {}", e)))
+}
+
+/// Make `old` hold the data of `new`, reusing `old`'s entries (and their decor) where possible
+fn merge_table(old: &mut Table, new: &Table) {
+    let stale: Vec<String> = old
+        .iter()
+        .map(|(key, _)| key.to_string())
+        .filter(|key| !new.contains_key(key))
+        .collect();
+    for key in stale {
+        old.remove(&key);
+    }
+
+    for (key, new_item) in new.iter() {
+        match (old.get_mut(key), new_item) {
+            (Some(Item::Value(old_value)), Item::Value(new_value)) => {
+                if !same_value(old_value, new_value) {
+                    let decor = old_value.decor().clone();
+                    let inline = !old_value.to_string().contains('\n');
+                    *old_value = new_value.clone();
+                    *old_value.decor_mut() = decor;
+                    // Keep arrays written on one line that way
+                    match old_value {
+                        Value::Array(array) if inline => array.fmt(),
+                        _ => {}
+                    }
+                }
+            }
+            (Some(Item::Table(old_table)), Item::Table(new_table)) => merge_table(old_table, new_table),
+            (Some(Item::ArrayOfTables(old_array)), Item::ArrayOfTables(new_array)) => {
+                merge_array(old_array, new_array)
+            }
+            _ => {
+                old.insert(key, new_item.clone());
+            }
+        }
+    }
+}
+
+/// Rebuild `old` in the order of `new`, merging entries with the same identity
+fn merge_array(old: &mut ArrayOfTables, new: &ArrayOfTables) {
+    let mut previous: Vec<Option<Table>> = old.iter().cloned().map(Some).collect();
+    let mut merged = ArrayOfTables::new();
+
+    for new_table in new.iter() {
+        let found = entry_id(new_table).and_then(|id| {
+            previous
+                .iter_mut()
+                .find(|table| table.as_ref().and_then(entry_id).as_deref() == Some(id.as_str()))
+                .and_then(Option::take)
+        });
+        match found {
+            Some(mut table) => {
+                merge_table(&mut table, new_table);
+                merged.push(table);
+            }
+            None => merged.push(new_table.clone()),
+        }
+    }
+    *old = merged;
+}
+
+/// Tables are written in the order of their document position, so give them
+/// positions following their order in the merged document
+fn renumber_tables(table: &mut Table, next: &mut usize) {
+    for (_, item) in table.iter_mut() {
+        match item {
+            Item::Table(table) => {
+                table.set_position(*next);
+                *next += 1;
+                renumber_tables(table, next);
+            }
+            Item::ArrayOfTables(array) => {
+                for table in array.iter_mut() {
+                    table.set_position(*next);
+                    *next += 1;
+                    renumber_tables(table, next);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The `name` (or `prefix`) of an entry
+fn entry_id(table: &Table) -> Option<String> {
+    ENTRY_KEYS
+        .iter()
+        .find_map(|key| table.get(key).and_then(Item::as_str).map(str::to_string))
+}
+
+/// Whether two values hold the same data, however they are formatted
+fn same_value(a: &Value, b: &Value) -> bool {
+    fn data(value: &Value) -> Option<toml::Value> {
+        let mut value = value.clone();
+        value.decor_mut().clear();
+        let table: toml::Table = format!("v = {}", value).parse().ok()?;
+        table.get("v").cloned()
+    }
+    matches!((data(a), data(b)), (Some(a), Some(b)) if a == b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comments_survive_sorting() {
+        let existing = r#"# Labels shared by all repositories
+delete = ["wontfix", "duplicate"]
+
+# Managed by the release bot, keep in sync with release.yml
+[[labels]]
+name = "release"
+color = "0e8a16" # green
+
+# Triage
+[[labels]]
+name = "bug"
+color = "D73A49"
+"#;
+        let rewritten = sort_config(existing).unwrap();
+        assert_eq!(
+            rewritten,
+            r#"# Labels shared by all repositories
+delete = ["duplicate", "wontfix"]
+
+# Triage
+[[labels]]
+name = "bug"
+color = "d73a49"
+
+# Managed by the release bot, keep in sync with release.yml
+[[labels]]
+name = "release"
+color = "0e8a16" # green
+"#
+        );
+    }
+
+    #[test]
+    fn test_removed_and_added_entries() {
+        let existing = "# Old\n[[labels]]\nname = \"old\"\n\n[[labels]]\nname = \"bug\"\ndescription = \"x\" # why\n";
+        let updated = "[[labels]]\nname = \"bug\"\n\n[[labels]]\nname = \"new\"\ncolor = \"ffffff\"\n";
+
+        let rewritten = preserve_formatting(existing, updated).unwrap();
+        assert!(!rewritten.contains("old"));
+        assert!(!rewritten.contains("why"));
+        let config: LabelConfig = rewritten.parse().unwrap();
+        let names: Vec<&str> = config.labels.iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(names, vec!["bug", "new"]);
+    }
+}
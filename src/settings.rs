@@ -0,0 +1,103 @@
+use crate::error::{BiaoError, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Repository settings stored in `.biao.toml` at the git root
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Settings {
+    /// Color used by `biao create` when no color is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_color: Option<String>,
+}
+
+impl Settings {
+    pub const FILE_NAME: &'static str = ".biao.toml";
+
+    /// Path of the settings file for the repository rooted at `root`
+    pub fn path(root: &Path) -> PathBuf {
+        root.join(Self::FILE_NAME)
+    }
+
+    /// Load settings from the repository root, falling back to defaults if the file is missing
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = Self::path(root);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path).map_err(|e| {
+            BiaoError::Io(std::io::Error::other(format!(
+                "Failed to read {}: {}",
+                path.display(),
+                e
+            )))
+        })?;
+
+        toml::from_str(&content).map_err(|e| {
+            BiaoError::InvalidInput(format!("Failed to parse {}: {}", Self::FILE_NAME, e))
+        })
+    }
+
+    /// Write settings to the repository root
+    pub fn save(&self, root: &Path) -> Result<PathBuf> {
+        let path = Self::path(root);
+        let content = toml::to_string_pretty(self).map_err(|e| {
+            BiaoError::InvalidInput(format!("Failed to serialize settings: {}", e))
+        })?;
+
+        fs::write(&path, content)?;
+        Ok(path)
+    }
+
+    /// Pick the explicit color if given, otherwise the configured default
+    pub fn resolve_color(&self, color: Option<&str>) -> Result<String> {
+        color
+            .or(self.default_color.as_deref())
+            .map(|c| c.to_string())
+            .ok_or_else(|| {
+                BiaoError::InvalidInput(
+                    "No color given and no default color configured. Pass a color or run `biao set-default-color <hex>`.".to_string(),
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_color_uses_default() {
+        let settings = Settings {
+            default_color: Some("0075ca".to_string()),
+        };
+        assert_eq!(settings.resolve_color(None).unwrap(), "0075ca");
+        assert_eq!(settings.resolve_color(Some("d73a49")).unwrap(), "d73a49");
+    }
+
+    #[test]
+    fn test_resolve_color_without_default() {
+        let settings = Settings::default();
+        assert!(settings.resolve_color(None).is_err());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let temp_dir = std::env::temp_dir()
+            .join(format!("biao_settings_{}", std::process::id()));
+        fs::create_dir_all(&temp_dir).unwrap();
+
+        assert!(Settings::load(&temp_dir).unwrap().default_color.is_none());
+
+        let settings = Settings {
+            default_color: Some("ededed".to_string()),
+        };
+        settings.save(&temp_dir).unwrap();
+
+        let loaded = Settings::load(&temp_dir).unwrap();
+        assert_eq!(loaded.default_color, Some("ededed".to_string()));
+
+        fs::remove_dir_all(&temp_dir).ok();
+    }
+}
@@ -0,0 +1,109 @@
+use crate::error::{BiaoError, Result};
+use crate::models::GithubLabel;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where snapshots go: the explicit `--snapshot-dir`, else `$XDG_CACHE_HOME/biao`,
+/// else `~/.cache/biao`. `None` if neither a cache dir nor a home dir is known.
+pub fn snapshot_dir(
+    explicit: Option<&Path>,
+    xdg_cache_home: Option<&str>,
+    home: Option<&str>,
+) -> Option<PathBuf> {
+    if let Some(dir) = explicit {
+        return Some(dir.to_path_buf());
+    }
+
+    match (xdg_cache_home.filter(|d| !d.is_empty()), home.filter(|d| !d.is_empty())) {
+        (Some(cache), _) => Some(Path::new(cache).join("biao")),
+        (None, Some(home)) => Some(Path::new(home).join(".cache").join("biao")),
+        (None, None) => None,
+    }
+}
+
+/// [`snapshot_dir`] resolved against the environment
+pub fn default_snapshot_dir(explicit: Option<&Path>) -> Option<PathBuf> {
+    snapshot_dir(
+        explicit,
+        std::env::var("XDG_CACHE_HOME").ok().as_deref(),
+        std::env::var("HOME").ok().as_deref(),
+    )
+}
+
+/// Save the repository's labels before `apply` changes them.
+///
+/// The snapshot is a regular config with `update_if_exists` on every label,
+/// so `biao apply <snapshot>` restores the previous names, colors and descriptions.
+pub fn save_snapshot(dir: &Path, repository: &str, labels: &[GithubLabel]) -> Result<PathBuf> {
+    fs::create_dir_all(dir).map_err(|e| {
+        BiaoError::Io(std::io::Error::other(format!(
+            "Failed to create snapshot directory {}: {}",
+            dir.display(),
+            e
+        )))
+    })?;
+
+    let mut config = crate::export::export_config(labels, false);
+    config.name = Some(format!("Snapshot of {}", repository));
+    for label in &mut config.labels {
+        label.update_if_exists = true;
+    }
+
+    let content = toml::to_string_pretty(&config)
+        .map_err(|e| BiaoError::InvalidInput(format!("Failed to serialize snapshot: {}", e)))?;
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let path = dir.join(format!("{}-{}.toml", repository.replace('/', "-"), timestamp));
+    fs::write(&path, content)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_dir_selection() {
+        assert_eq!(
+            snapshot_dir(None, Some("/xdg/cache"), Some("/home/me")),
+            Some(PathBuf::from("/xdg/cache/biao"))
+        );
+        assert_eq!(
+            snapshot_dir(None, Some(""), Some("/home/me")),
+            Some(PathBuf::from("/home/me/.cache/biao"))
+        );
+        assert_eq!(
+            snapshot_dir(Some(Path::new("snapshots")), Some("/xdg/cache"), Some("/home/me")),
+            Some(PathBuf::from("snapshots"))
+        );
+        assert_eq!(snapshot_dir(None, None, None), None);
+    }
+
+    #[test]
+    fn test_save_snapshot_creates_dir() {
+        let dir = std::env::temp_dir()
+            .join(format!("biao-snapshot-test-{}", std::process::id()))
+            .join("nested");
+        let label = GithubLabel {
+            name: "bug".to_string(),
+            color: "d73a49".to_string(),
+            description: Some("Something isn't working".to_string()),
+            url: String::new(),
+            id: 1,
+            node_id: "node".to_string(),
+            default: false,
+        };
+
+        let path = save_snapshot(&dir, "o/r", &[label]).unwrap();
+        let config: crate::config::LabelConfig = fs::read_to_string(&path).unwrap().parse().unwrap();
+        fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+
+        assert!(path.file_name().unwrap().to_string_lossy().starts_with("o-r-"));
+        assert_eq!(config.name.as_deref(), Some("Snapshot of o/r"));
+        assert_eq!(config.labels[0].name, "bug");
+        assert!(config.labels[0].update_if_exists);
+    }
+}
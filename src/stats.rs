@@ -0,0 +1,182 @@
+use crate::models::GithubLabel;
+use colored::Colorize;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Colors listed under "Most common colors"
+pub const TOP_COLORS: usize = 5;
+
+/// Health report of a repository's labels, as `biao stats` prints it
+#[derive(Debug, Serialize)]
+pub struct LabelStats {
+    pub repository: String,
+    pub total: usize,
+    pub with_description: usize,
+    /// Labels GitHub created with the repository
+    pub default: usize,
+    /// Most used colors first, at most [`TOP_COLORS`]
+    pub colors: Vec<ColorCount>,
+    /// Distinct colors among all labels
+    pub distinct_colors: usize,
+    /// Open issues and pull requests per label, busiest first, with `--with-counts`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub open_issues: Option<Vec<LabelCount>>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct ColorCount {
+    pub color: String,
+    pub labels: usize,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct LabelCount {
+    pub name: String,
+    pub open: u64,
+}
+
+impl LabelStats {
+    /// Stats of `labels`; `open_issues`, when counted, is in the same order as
+    /// `labels`, and labels left uncounted are left out
+    pub fn new(repository: String, labels: &[GithubLabel], open_issues: Option<Vec<Option<u64>>>) -> Self {
+        let mut by_color: HashMap<String, usize> = HashMap::new();
+        for label in labels {
+            *by_color.entry(label.color.to_lowercase()).or_default() += 1;
+        }
+        let distinct_colors = by_color.len();
+        let mut colors: Vec<ColorCount> = by_color
+            .into_iter()
+            .map(|(color, labels)| ColorCount { color, labels })
+            .collect();
+        colors.sort_by(|a, b| b.labels.cmp(&a.labels).then_with(|| a.color.cmp(&b.color)));
+        colors.truncate(TOP_COLORS);
+
+        let open_issues = open_issues.map(|counts| {
+            let mut counts: Vec<LabelCount> = labels
+                .iter()
+                .zip(counts)
+                .filter_map(|(label, open)| {
+                    Some(LabelCount {
+                        name: label.name.clone(),
+                        open: open?,
+                    })
+                })
+                .collect();
+            counts.sort_by(|a, b| b.open.cmp(&a.open).then_with(|| a.name.cmp(&b.name)));
+            counts
+        });
+
+        LabelStats {
+            repository,
+            total: labels.len(),
+            with_description: labels
+                .iter()
+                .filter(|label| label.description.as_deref().is_some_and(|d| !d.is_empty()))
+                .count(),
+            default: labels.iter().filter(|label| label.default).count(),
+            colors,
+            distinct_colors,
+            open_issues,
+        }
+    }
+}
+
+/// `part` as a whole percentage of `total`
+fn percent(part: usize, total: usize) -> usize {
+    (part * 100).checked_div(total).unwrap_or(0)
+}
+
+/// A `■` drawn in `color`, where the terminal supports it
+fn swatch(color: &str) -> String {
+    let channel = |i: usize| color.get(i..i + 2).and_then(|c| u8::from_str_radix(c, 16).ok());
+    match (channel(0), channel(2), channel(4)) {
+        (Some(r), Some(g), Some(b)) => "■".truecolor(r, g, b).to_string(),
+        _ => "■".to_string(),
+    }
+}
+
+/// The human-readable report
+pub fn format_stats(stats: &LabelStats) -> String {
+    let mut out = String::new();
+    out.push_str(&format!("  {:<18}{}\n", "Labels:", stats.total.to_string().bold()));
+    out.push_str(&format!(
+        "  {:<18}{} ({}%)\n",
+        "With description:",
+        stats.with_description,
+        percent(stats.with_description, stats.total)
+    ));
+    out.push_str(&format!("  {:<18}{}\n", "GitHub defaults:", stats.default));
+    out.push_str(&format!("  {:<18}{}\n", "Distinct colors:", stats.distinct_colors));
+
+    if !stats.colors.is_empty() {
+        out.push_str(&format!("\n{}\n", "Most common colors:".bold()));
+        for color in &stats.colors {
+            out.push_str(&format!("  {} #{}  {}\n", swatch(&color.color), color.color, color.labels));
+        }
+    }
+
+    if let Some(open_issues) = &stats.open_issues {
+        out.push_str(&format!("\n{}\n", "Open issues and pull requests:".bold()));
+        let width = open_issues.iter().map(|count| count.name.chars().count()).max().unwrap_or(0);
+        for count in open_issues {
+            let line = format!("  {:<width$}  {}\n", count.name, count.open, width = width);
+            out.push_str(&if count.open == 0 { line.dimmed().to_string() } else { line });
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label(name: &str, color: &str, description: Option<&str>, default: bool) -> GithubLabel {
+        GithubLabel {
+            name: name.to_string(),
+            color: color.to_string(),
+            description: description.map(str::to_string),
+            url: String::new(),
+            id: 0,
+            node_id: String::new(),
+            default,
+        }
+    }
+
+    #[test]
+    fn test_label_stats() {
+        colored::control::set_override(false);
+        let labels = vec![
+            label("bug", "d73a4a", Some("Something isn't working"), true),
+            label("crash", "D73A4A", None, false),
+            label("docs", "0075ca", Some(""), true),
+            label("triage", "ededed", Some("Needs a look"), false),
+        ];
+
+        let stats = LabelStats::new("o/r".to_string(), &labels, Some(vec![Some(3), Some(0), Some(7), None]));
+        assert_eq!(stats.total, 4);
+        assert_eq!(stats.with_description, 2);
+        assert_eq!(stats.default, 2);
+        assert_eq!(stats.distinct_colors, 3);
+        assert_eq!(
+            stats.colors[0],
+            ColorCount {
+                color: "d73a4a".to_string(),
+                labels: 2
+            }
+        );
+        let open = stats.open_issues.as_ref().unwrap();
+        assert_eq!(open[0].name, "docs");
+        assert_eq!(open[1].name, "bug");
+        assert_eq!(open.len(), 3);
+
+        let report = format_stats(&stats);
+        assert!(report.contains("With description: 2 (50%)"));
+        assert!(report.contains("■ #d73a4a  2"));
+        assert!(report.contains("  docs   7"));
+
+        let json = serde_json::to_value(LabelStats::new("o/r".to_string(), &labels, None)).unwrap();
+        assert_eq!(json["total"], 4);
+        assert_eq!(json["colors"][0]["labels"], 2);
+        assert!(json.get("open_issues").is_none());
+    }
+}
@@ -1,4 +1,6 @@
+use crate::config::LabelConfig;
 use crate::error::{BiaoError, Result};
+use crate::theme::ColorTheme;
 use serde::Deserialize;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -9,12 +11,123 @@ pub struct TemplateInfo {
     pub name: String,
     pub description: String,
     pub path: PathBuf,
+    /// Free-form keywords for `template list --tag`
+    pub tags: Vec<String>,
+    pub category: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+impl TemplateInfo {
+    /// Whether the template is tagged `tag`, ignoring case
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// Whether the template is in `category`, ignoring case
+    pub fn in_category(&self, category: &str) -> bool {
+        self.category
+            .as_deref()
+            .is_some_and(|c| c.eq_ignore_ascii_case(category))
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
 struct TemplateFileMetadata {
     #[serde(default)]
     description: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    category: Option<String>,
+}
+
+/// A template compiled into the binary
+struct BuiltinTemplate {
+    name: &'static str,
+    description: &'static str,
+    category: &'static str,
+    tags: &'static [&'static str],
+}
+
+const BUILTIN_TEMPLATES: &[BuiltinTemplate] = &[
+    BuiltinTemplate {
+        name: "standard",
+        description: "Standard GitHub labels (bug, feature, documentation, etc.)",
+        category: "general",
+        tags: &["github", "starter"],
+    },
+    BuiltinTemplate {
+        name: "semantic",
+        description: "Semantic labels (breaking, feature, bugfix, docs, etc.)",
+        category: "release",
+        tags: &["semver", "changelog"],
+    },
+    BuiltinTemplate {
+        name: "priority",
+        description: "Priority-based labels (critical, high, medium, low)",
+        category: "priority",
+        tags: &["triage"],
+    },
+    BuiltinTemplate {
+        name: "priority-prefixed",
+        description: "Rust-style priority labels (P-critical, P-high, etc.)",
+        category: "priority",
+        tags: &["triage", "rust"],
+    },
+    BuiltinTemplate {
+        name: "rusty/priority",
+        description: "Rust-style priority labels (P-critical, P-high, etc.)",
+        category: "priority",
+        tags: &["triage", "rust"],
+    },
+    BuiltinTemplate {
+        name: "type",
+        description: "Type-based labels (type/bug, type/feature, type/docs, etc.)",
+        category: "type",
+        tags: &["triage"],
+    },
+    BuiltinTemplate {
+        name: "type/labels",
+        description: "Type-based labels (type/bug, type/feature, type/docs, etc.)",
+        category: "type",
+        tags: &["triage"],
+    },
+    BuiltinTemplate {
+        name: "area",
+        description: "Area-based labels (area/api, area/cli, area/docs, etc.)",
+        category: "area",
+        tags: &["components"],
+    },
+    BuiltinTemplate {
+        name: "rusty/area",
+        description: "Area-based labels (A-api, A-cli, A-docs, etc.)",
+        category: "area",
+        tags: &["components", "rust"],
+    },
+    BuiltinTemplate {
+        name: "operational",
+        description: "Operational labels (O-hiring, O-roadmap, etc.)",
+        category: "project",
+        tags: &["planning"],
+    },
+];
+
+/// Templates tagged `tag` and in `category`, where given
+pub fn filter(templates: Vec<TemplateInfo>, tag: Option<&str>, category: Option<&str>) -> Vec<TemplateInfo> {
+    templates
+        .into_iter()
+        .filter(|t| tag.is_none_or(|tag| t.has_tag(tag)))
+        .filter(|t| category.is_none_or(|category| t.in_category(category)))
+        .collect()
+}
+
+/// Template names by category, for `template categories`; uncategorized templates go under "other"
+pub fn categories(templates: &[TemplateInfo]) -> std::collections::BTreeMap<String, Vec<String>> {
+    let mut categories = std::collections::BTreeMap::<String, Vec<String>>::new();
+    for template in templates {
+        let category = template.category.as_deref().unwrap_or("other").to_lowercase();
+        categories.entry(category).or_default().push(template.name.clone());
+    }
+    categories
 }
 
 /// Template manager for discovering and loading templates
@@ -32,9 +145,7 @@ impl TemplateManager {
         template_dirs.push(PathBuf::from("__builtin__"));
 
         // Add user config directory: ~/.config/biao/templates
-        if let Ok(home) = std::env::var("HOME") {
-            let user_templates = PathBuf::from(home)
-                .join(".config/biao/templates");
+        if let Some(user_templates) = user_template_dir() {
             if user_templates.exists() {
                 template_dirs.push(user_templates);
             }
@@ -65,12 +176,14 @@ impl TemplateManager {
         }
 
         // 2) Built-ins (lower priority, only if not already present)
-        for (name, description) in Self::builtin_templates() {
-            let key = name.to_string();
+        for builtin in BUILTIN_TEMPLATES {
+            let key = builtin.name.to_string();
             map.entry(key.clone()).or_insert(TemplateInfo {
                 name: key,
-                description: description.to_string(),
+                description: builtin.description.to_string(),
                 path: PathBuf::from("__builtin__"),
+                tags: builtin.tags.iter().map(|tag| tag.to_string()).collect(),
+                category: Some(builtin.category.to_string()),
             });
         }
 
@@ -88,8 +201,7 @@ impl TemplateManager {
             if dir.as_os_str() == "__builtin__" {
                 continue;
             }
-            let candidate = PathBuf::from(name);
-            let path = dir.join(candidate).with_extension("toml");
+            let path = template_file(dir, name);
             if path.exists() {
                 return fs::read_to_string(&path).map_err(|e| {
                     BiaoError::Io(std::io::Error::new(
@@ -111,22 +223,6 @@ impl TemplateManager {
         )))
     }
 
-    /// Built-in templates
-    fn builtin_templates() -> Vec<(&'static str, &'static str)> {
-        vec![
-            ("standard", "Standard GitHub labels (bug, feature, documentation, etc.)"),
-            ("semantic", "Semantic labels (breaking, feature, bugfix, docs, etc.)"),
-            ("priority", "Priority-based labels (critical, high, medium, low)"),
-            ("priority-prefixed", "Rust-style priority labels (P-critical, P-high, etc.)"),
-            ("rusty/priority", "Rust-style priority labels (P-critical, P-high, etc.)"),
-            ("type", "Type-based labels (type/bug, type/feature, type/docs, etc.)"),
-            ("type/labels", "Type-based labels (type/bug, type/feature, type/docs, etc.)"),
-            ("area", "Area-based labels (area/api, area/cli, area/docs, etc.)"),
-            ("rusty/area", "Area-based labels (A-api, A-cli, A-docs, etc.)"),
-            ("operational", "Operational labels (O-hiring, O-roadmap, etc.)"),
-        ]
-    }
-
     /// Get built-in template content
     fn get_builtin_template(name: &str) -> Option<String> {
         match name {
@@ -144,10 +240,9 @@ impl TemplateManager {
         }
     }
 
-    fn read_description_from_file(path: &Path) -> Option<String> {
+    fn read_metadata_from_file(path: &Path) -> Option<TemplateFileMetadata> {
         let content = fs::read_to_string(path).ok()?;
-        let meta: TemplateFileMetadata = toml::from_str(&content).ok()?;
-        meta.description
+        toml::from_str(&content).ok()
     }
 
     fn collect_templates_in_dir(
@@ -171,12 +266,13 @@ impl TemplateManager {
                     let key = rel_without_ext
                         .to_string_lossy()
                         .replace('\\', "/");
-                    let description = Self::read_description_from_file(&path)
-                        .unwrap_or_else(|| "User template".to_string());
+                    let meta = Self::read_metadata_from_file(&path).unwrap_or_default();
                     map.entry(key.clone()).or_insert(TemplateInfo {
                         name: key,
-                        description,
+                        description: meta.description.unwrap_or_else(|| "User template".to_string()),
                         path,
+                        tags: meta.tags,
+                        category: meta.category,
                     });
                 }
             }
@@ -192,8 +288,65 @@ impl Default for TemplateManager {
     }
 }
 
+/// Directory of the user's own templates, `~/.config/biao/templates`
+pub fn user_template_dir() -> Option<PathBuf> {
+    std::env::var("HOME")
+        .ok()
+        .map(|home| PathBuf::from(home).join(".config/biao/templates"))
+}
+
+/// File of template `name` in `dir`; `.toml` is appended, so dots in the name stay
+fn template_file(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{}.toml", name))
+}
+
+/// Write a starter template called `name` into `dir`, creating the directory
+/// if needed; an existing template is only replaced with `force`
+pub fn create_template(dir: &Path, name: &str, force: bool) -> Result<PathBuf> {
+    let valid = !name.is_empty()
+        && name
+            .split('/')
+            .all(|part| !part.is_empty() && part != "." && part != ".." && !part.contains('\\'));
+    if !valid {
+        return Err(BiaoError::InvalidInput(format!(
+            "Invalid template name '{}'. Use a name like 'my-labels' or 'team/backend'.",
+            name
+        )));
+    }
+
+    let path = template_file(dir, name);
+    if path.exists() && !force {
+        return Err(BiaoError::InvalidInput(format!(
+            "Template '{}' already exists at {}. Pass --force to overwrite it.",
+            name,
+            path.display()
+        )));
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, TEMPLATE_STARTER.replace("{name}", name))?;
+    Ok(path)
+}
+
 // Built-in template definitions
 
+const TEMPLATE_STARTER: &str = r##"# {name} labels template
+# Apply it with `biao template apply {name}`
+
+description = "My labels"
+
+# [[labels]]
+# name = "bug"
+# color = "d73a49"
+# description = "Something isn't working"
+
+# [[labels]]
+# name = "enhancement"
+# color = "a2eeef"
+# description = "New feature or request"
+"##;
+
 const TEMPLATE_STANDARD: &str = r##"# Standard GitHub Labels Template
 # Common labels used in most GitHub projects
 
@@ -493,10 +646,84 @@ color = "#e4e669"
 description = "Operational: Compliance, audits, and risk"
 "##;
 
+/// Parse a `--var key=value` argument
+pub fn parse_var(arg: &str) -> std::result::Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => Ok((key.trim().to_string(), value.to_string())),
+        _ => Err(format!("expected key=value, got '{}'", arg)),
+    }
+}
+
+/// Replace `{{ key }}` placeholders with `--var` values.
+///
+/// A placeholder without a value is an error rather than being left in
+/// place, so a typo never ends up as a label name on GitHub.
+pub fn substitute_vars(content: &str, vars: &[(String, String)]) -> Result<String> {
+    let mut out = String::with_capacity(content.len());
+    let mut rest = content;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else { break };
+        let key = rest[start + 2..start + 2 + len].trim();
+        let value = vars
+            .iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+            .ok_or_else(|| {
+                BiaoError::InvalidInput(format!(
+                    "Template variable '{}' is not set. Pass --var {}=<value>.",
+                    key, key
+                ))
+            })?;
+
+        out.push_str(&rest[..start]);
+        out.push_str(value);
+        rest = &rest[start + 2 + len + 2..];
+    }
+
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Template content as `apply` would see it: variables substituted, label
+/// groups expanded and the color theme applied
+pub fn render(content: &str, vars: &[(String, String)], theme: Option<&ColorTheme>) -> Result<String> {
+    let mut config: LabelConfig = substitute_vars(content, vars)?.parse()?;
+    if let Some(theme) = theme {
+        theme.apply(&mut config)?;
+    }
+
+    toml::to_string_pretty(&config)
+        .map_err(|e| BiaoError::InvalidInput(format!("Failed to serialize template: {}", e)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_render_substitutes_vars() {
+        let content = r#"
+[[labels]]
+name = "{{ team }}/triage"
+color = "d73a49"
+description = "Needs triage by {{team}}"
+"#;
+        let vars = vec![parse_var("team=infra").unwrap()];
+        let theme: ColorTheme = "[colors]\nd73a49 = \"b91c1c\"\n".parse().unwrap();
+
+        let rendered = render(content, &vars, Some(&theme)).unwrap();
+        assert!(rendered.contains("name = \"infra/triage\""));
+        assert!(rendered.contains("Needs triage by infra"));
+        assert!(rendered.contains("b91c1c"));
+        assert!(!rendered.contains("{{"));
+
+        let err = render(content, &[], None).unwrap_err();
+        assert!(err.to_string().contains("'team' is not set"));
+        assert!(parse_var("no-equals").is_err());
+    }
+
     #[test]
     fn test_template_manager_creation() {
         let manager = TemplateManager::default();
@@ -514,6 +741,15 @@ mod tests {
         assert!(content.contains("feature"));
     }
 
+    #[test]
+    fn test_builtin_templates_validate() {
+        for builtin in BUILTIN_TEMPLATES {
+            let content = TemplateManager::get_builtin_template(builtin.name).unwrap();
+            let config: crate::config::LabelConfig = content.parse().unwrap();
+            config.validate().unwrap_or_else(|e| panic!("template {}: {}", builtin.name, e));
+        }
+    }
+
     #[test]
     fn test_get_template() {
         let manager = TemplateManager::default();
@@ -529,6 +765,36 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_create_template() {
+        let dir = std::env::temp_dir().join(format!("biao_template_new_{}", std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+
+        let path = create_template(&dir, "team/backend", false).unwrap();
+        assert_eq!(path, dir.join("team/backend.toml"));
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("biao template apply team/backend"));
+        let config: crate::config::LabelConfig = content.parse().unwrap();
+        assert_eq!(config.description.as_deref(), Some("My labels"));
+
+        let manager = TemplateManager {
+            template_dirs: vec![dir.clone()],
+        };
+        assert!(manager.list().unwrap().iter().any(|t| t.name == "team/backend"));
+
+        let err = create_template(&dir, "team/backend", false).unwrap_err();
+        assert!(err.to_string().contains("--force"));
+        assert!(create_template(&dir, "team/backend", true).is_ok());
+        assert!(create_template(&dir, "../escape", false).is_err());
+
+        // Dots are part of the name, not an extension to replace
+        let path = create_template(&dir, "v1.2", false).unwrap();
+        assert_eq!(path, dir.join("v1.2.toml"));
+        assert!(manager.get("v1.2").unwrap().contains("biao template apply v1.2"));
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
     #[test]
     fn test_template_list_reads_description() {
         let temp_dir = std::env::temp_dir()
@@ -562,4 +828,31 @@ color = "000000"
 
         fs::remove_dir_all(&temp_dir).ok();
     }
+
+    #[test]
+    fn test_filter_by_tag() {
+        let temp_dir = std::env::temp_dir().join(format!("biao_template_tags_{}", std::process::id()));
+        fs::create_dir_all(&temp_dir).unwrap();
+        fs::write(
+            temp_dir.join("infra.toml"),
+            "description = \"Infra team\"\ntags = [\"Rust\", \"ops\"]\ncategory = \"team\"\n\n[[labels]]\nname = \"x\"\ncolor = \"000000\"\n",
+        )
+        .unwrap();
+
+        let manager = TemplateManager {
+            template_dirs: vec![temp_dir.clone(), PathBuf::from("__builtin__")],
+        };
+        let names = |templates: Vec<TemplateInfo>| templates.into_iter().map(|t| t.name).collect::<Vec<_>>();
+
+        let rust = filter(manager.list().unwrap(), Some("rust"), None);
+        assert_eq!(names(rust), vec!["infra", "priority-prefixed", "rusty/area", "rusty/priority"]);
+        assert_eq!(names(filter(manager.list().unwrap(), Some("ops"), None)), vec!["infra"]);
+        assert_eq!(
+            names(filter(manager.list().unwrap(), Some("triage"), Some("type"))),
+            vec!["type", "type/labels"]
+        );
+        assert!(filter(manager.list().unwrap(), Some("nonexistent"), None).is_empty());
+
+        fs::remove_dir_all(&temp_dir).ok();
+    }
 }
@@ -0,0 +1,101 @@
+use crate::cli::normalize_color;
+use crate::config::LabelConfig;
+use crate::error::{BiaoError, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Color palette remapping applied to a config after parsing.
+///
+/// ```toml
+/// [colors]
+/// d73a49 = "b91c1c"
+/// "#0075ca" = "1d4ed8"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+pub struct ColorTheme {
+    #[serde(default)]
+    colors: HashMap<String, String>,
+}
+
+impl ColorTheme {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content = fs::read_to_string(path.as_ref()).map_err(|e| {
+            BiaoError::Io(std::io::Error::other(format!(
+                "Failed to read color theme: {}",
+                e
+            )))
+        })?;
+
+        content.parse()
+    }
+
+    /// Replace every label color that has a mapping in the theme
+    pub fn apply(&self, config: &mut LabelConfig) -> Result<()> {
+        let mapping = self
+            .colors
+            .iter()
+            .map(|(from, to)| Ok((normalize_color(from)?, normalize_color(to)?)))
+            .collect::<Result<HashMap<String, String>>>()?;
+
+        for label in &mut config.labels {
+            let Some(color) = &label.color else { continue };
+            if let Some(themed) = mapping.get(&normalize_color(color)?) {
+                label.color = Some(themed.clone());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for ColorTheme {
+    type Err = BiaoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        toml::from_str(s)
+            .map_err(|e| BiaoError::InvalidInput(format!("Failed to parse color theme: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan::Plan;
+
+    #[test]
+    fn test_theme_replaces_colors_in_plan() {
+        let theme: ColorTheme = r##"
+[colors]
+"#D73A49" = "b91c1c"
+"##
+        .parse()
+        .unwrap();
+
+        let mut config: LabelConfig = toml::from_str(
+            r##"
+[[labels]]
+name = "bug"
+color = "d73a49"
+
+[[labels]]
+name = "feature"
+color = "#a2eeef"
+"##,
+        )
+        .unwrap();
+
+        theme.apply(&mut config).unwrap();
+        let plan = Plan::build(&config, &[], false).unwrap();
+        assert_eq!(plan.operations[0].color, Some("b91c1c".to_string()));
+        assert_eq!(plan.operations[1].color, Some("a2eeef".to_string()));
+    }
+
+    #[test]
+    fn test_theme_rejects_invalid_colors() {
+        let theme: ColorTheme = "[colors]\nd73a49 = \"nope\"\n".parse().unwrap();
+        let mut config = LabelConfig::default();
+        assert!(theme.apply(&mut config).is_err());
+    }
+}
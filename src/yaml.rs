@@ -0,0 +1,424 @@
+//! Reader for the subset of YAML that label configs are written in.
+//!
+//! Supports block mappings and sequences, flow sequences of scalars
+//! (`[a, b]`), plain and quoted scalars, `|` and `>` block scalars and
+//! comments. Anchors, aliases, tags and flow mappings are rejected rather
+//! than misread. Plain scalars follow YAML 1.2 for `true`, `false`, `null`
+//! and `~`; everything else is a string, numbers included, since every
+//! config value they appear in is one (`color: 008000`, `name: 2024`).
+
+use serde_json::{Map, Value};
+
+/// Parse a YAML document into the JSON data model
+pub fn parse(source: &str) -> Result<Value, String> {
+    let mut parser = Parser::new(source)?;
+    if let Some(index) = parser.peek() {
+        if parser.lines[index].content == "---" && parser.lines[index].indent == 0 {
+            parser.pos = index + 1;
+        }
+    }
+
+    let value = match parser.peek() {
+        // An empty document is an empty config
+        None => Value::Object(Map::new()),
+        Some(index) => {
+            let indent = parser.lines[index].indent;
+            parser.block(indent)?
+        }
+    };
+
+    match parser.peek() {
+        Some(index) if parser.lines[index].content == "..." => Ok(value),
+        Some(index) => Err(parser.error(index, "unexpected indentation")),
+        None => Ok(value),
+    }
+}
+
+struct Line<'a> {
+    raw: &'a str,
+    indent: usize,
+    /// Text after the indentation, without comment or trailing whitespace
+    content: String,
+}
+
+struct Parser<'a> {
+    lines: Vec<Line<'a>>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> Result<Self, String> {
+        let lines = source
+            .lines()
+            .enumerate()
+            .map(|(number, raw)| {
+                let indent = raw.len() - raw.trim_start_matches(' ').len();
+                if raw[indent..].starts_with('\t') && !raw.trim().is_empty() {
+                    return Err(format!("line {}: tabs can't be used for indentation", number + 1));
+                }
+                Ok(Line {
+                    raw,
+                    indent,
+                    content: strip_comment(&raw[indent..]).trim_end().to_string(),
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Parser { lines, pos: 0 })
+    }
+
+    fn error(&self, index: usize, message: &str) -> String {
+        format!("line {}: {}", index + 1, message)
+    }
+
+    /// Index of the next line with content
+    fn peek(&self) -> Option<usize> {
+        (self.pos..self.lines.len()).find(|&index| !self.lines[index].content.is_empty())
+    }
+
+    /// The mapping or sequence starting at the next line, indented by `indent`
+    fn block(&mut self, indent: usize) -> Result<Value, String> {
+        match self.peek() {
+            Some(index) if is_item(&self.lines[index].content) => self.sequence(indent),
+            _ => self.mapping(indent),
+        }
+    }
+
+    /// Value of a key or item left empty on its own line: a block indented
+    /// deeper than `indent`, or null
+    fn nested(&mut self, indent: usize) -> Result<Value, String> {
+        match self.peek() {
+            Some(index) if self.lines[index].indent > indent => {
+                let indent = self.lines[index].indent;
+                self.block(indent)
+            }
+            _ => Ok(Value::Null),
+        }
+    }
+
+    fn sequence(&mut self, indent: usize) -> Result<Value, String> {
+        let mut items = Vec::new();
+        while let Some(index) = self.peek() {
+            let line = &self.lines[index];
+            if line.indent < indent || !is_item(&line.content) {
+                break;
+            }
+            if line.indent > indent {
+                return Err(self.error(index, "unexpected indentation"));
+            }
+
+            let after_dash = &line.content[1..];
+            let rest = after_dash.trim_start();
+            if rest.is_empty() {
+                self.pos = index + 1;
+                items.push(self.nested(indent)?);
+            } else if key_separator(rest).is_some() || is_item(rest) {
+                // `- name: bug` opens a mapping (or sequence) at the column of `name`
+                let column = indent + 1 + (after_dash.len() - rest.len());
+                let rest = rest.to_string();
+                self.lines[index].indent = column;
+                self.lines[index].content = rest;
+                self.pos = index;
+                items.push(self.block(column)?);
+            } else {
+                self.pos = index + 1;
+                items.push(self.inline(index, rest)?);
+            }
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn mapping(&mut self, indent: usize) -> Result<Value, String> {
+        let mut map = Map::new();
+        while let Some(index) = self.peek() {
+            let line = &self.lines[index];
+            if line.indent < indent || (line.indent == indent && is_item(&line.content)) {
+                break;
+            }
+            if line.indent > indent {
+                return Err(self.error(index, "unexpected indentation"));
+            }
+
+            let content = line.content.clone();
+            let separator = key_separator(&content)
+                .ok_or_else(|| self.error(index, "expected `key: value`"))?;
+            let key = match scalar(content[..separator].trim()) {
+                Ok(Value::String(key)) => key,
+                _ => content[..separator].trim().to_string(),
+            };
+            let value = content[separator + 1..].trim();
+            self.pos = index + 1;
+
+            let value = if value.is_empty() {
+                match self.peek() {
+                    // A sequence may sit at the same indentation as its key
+                    Some(next) if self.lines[next].indent == indent && is_item(&self.lines[next].content) => {
+                        self.sequence(indent)?
+                    }
+                    _ => self.nested(indent)?,
+                }
+            } else if value.starts_with('|') || value.starts_with('>') {
+                self.block_scalar(index, indent, value)?
+            } else {
+                self.inline(index, value)?
+            };
+
+            if map.insert(key.clone(), value).is_some() {
+                return Err(self.error(index, &format!("duplicate key `{}`", key)));
+            }
+        }
+        Ok(Value::Object(map))
+    }
+
+    /// `|` (literal) or `>` (folded) text on the lines indented deeper than `indent`
+    fn block_scalar(&mut self, index: usize, indent: usize, header: &str) -> Result<Value, String> {
+        let (style, chomping) = header.split_at(1);
+        if !matches!(chomping, "" | "-" | "+") {
+            return Err(self.error(index, "unsupported block scalar header"));
+        }
+
+        let mut lines = Vec::new();
+        let mut content_indent = None;
+        while self.pos < self.lines.len() {
+            let line = &self.lines[self.pos];
+            let blank = line.raw.trim().is_empty();
+            if !blank && line.indent <= indent {
+                break;
+            }
+            if !blank {
+                let column = *content_indent.get_or_insert(line.indent);
+                if line.indent < column {
+                    return Err(self.error(self.pos, "block scalar lines must be indented alike"));
+                }
+            }
+            lines.push(if blank { "" } else { &line.raw[content_indent.unwrap_or(0)..] });
+            self.pos += 1;
+        }
+
+        let trailing = lines.iter().rev().take_while(|line| line.is_empty()).count();
+        let body = &lines[..lines.len() - trailing];
+        let mut text = if style == "|" {
+            body.join("\n")
+        } else {
+            // Folded: line breaks become spaces, blank lines become breaks
+            let mut folded = String::new();
+            for (i, line) in body.iter().enumerate() {
+                if line.is_empty() {
+                    folded.push('\n');
+                } else {
+                    if i > 0 && !body[i - 1].is_empty() {
+                        folded.push(' ');
+                    }
+                    folded.push_str(line);
+                }
+            }
+            folded
+        };
+        match chomping {
+            "-" => {}
+            "+" => text.push_str(&"\n".repeat(trailing + 1)),
+            _ => text.push('\n'),
+        }
+        Ok(Value::String(text))
+    }
+
+    /// A value written on the same line as its key or dash
+    fn inline(&self, index: usize, text: &str) -> Result<Value, String> {
+        match text.chars().next() {
+            Some('[') => {
+                let inner = text
+                    .strip_suffix(']')
+                    .map(|t| t[1..].trim())
+                    .ok_or_else(|| self.error(index, "unterminated `[`"))?;
+                if inner.is_empty() {
+                    return Ok(Value::Array(Vec::new()));
+                }
+                split_flow(inner)
+                    .iter()
+                    .map(|item| match item.trim() {
+                        item if item.starts_with(['[', '{']) => {
+                            Err(self.error(index, "nested flow collections are not supported"))
+                        }
+                        item => scalar(item).map_err(|e| self.error(index, &e)),
+                    })
+                    .collect::<Result<_, _>>()
+                    .map(Value::Array)
+            }
+            Some('{') if text == "{}" => Ok(Value::Object(Map::new())),
+            Some('{') => Err(self.error(index, "flow mappings are not supported, use one `key: value` per line")),
+            Some('&' | '*' | '!') => Err(self.error(index, "anchors, aliases and tags are not supported")),
+            _ => scalar(text).map_err(|e| self.error(index, &e)),
+        }
+    }
+}
+
+fn is_item(content: &str) -> bool {
+    content == "-" || content.starts_with("- ")
+}
+
+/// Byte offset of the `:` ending a mapping key, outside quotes
+fn key_separator(text: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (None, '"' | '\'') if i == 0 => quote = Some(c),
+            (Some('"'), '\\') => {
+                chars.next();
+            }
+            (Some(q), c) if c == q => quote = None,
+            (None, ':') if chars.peek().is_none_or(|(_, next)| next.is_whitespace()) => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// `text` up to a `#` comment that isn't inside quotes
+fn strip_comment(text: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') if previous.is_whitespace() || matches!(previous, '[' | ',' | ':' | '-') => {
+                quote = Some(c)
+            }
+            (Some(q), c) if c == q => quote = None,
+            (None, '#') if previous.is_whitespace() => return &text[..i],
+            _ => {}
+        }
+        previous = c;
+    }
+    text
+}
+
+/// Items of a flow sequence, split on commas outside quotes
+fn split_flow(text: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut quote = None;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, ',') => {
+                items.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(&text[start..]);
+    items
+}
+
+fn scalar(text: &str) -> Result<Value, String> {
+    if let Some(inner) = text.strip_prefix('"') {
+        let inner = inner
+            .strip_suffix('"')
+            .ok_or_else(|| format!("unterminated string {}", text))?;
+        return unescape(inner).map(Value::String);
+    }
+    if let Some(inner) = text.strip_prefix('\'') {
+        let inner = inner
+            .strip_suffix('\'')
+            .ok_or_else(|| format!("unterminated string {}", text))?;
+        return Ok(Value::String(inner.replace("''", "'")));
+    }
+
+    Ok(match text {
+        "" | "~" | "null" | "Null" | "NULL" => Value::Null,
+        "true" | "True" | "TRUE" => Value::Bool(true),
+        "false" | "False" | "FALSE" => Value::Bool(false),
+        _ => Value::String(text.to_string()),
+    })
+}
+
+/// Resolve the escapes of a double-quoted string
+fn unescape(text: &str) -> Result<String, String> {
+    let mut out = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('0') => out.push('\0'),
+            Some(c @ ('"' | '\\' | '/' | ' ')) => out.push(c),
+            Some('u') => {
+                let code: String = chars.by_ref().take(4).collect();
+                let c = u32::from_str_radix(&code, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| format!("invalid escape \\u{}", code))?;
+                out.push(c);
+            }
+            other => return Err(format!("invalid escape \\{}", other.map(String::from).unwrap_or_default())),
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_config_shapes() {
+        let yaml = r#"
+# Team labels
+name: "Core labels"
+delete: [wontfix, 'duplicate']
+labels:
+  - name: bug  # the usual
+    color: d73a49
+    update_if_match:
+      - Bug
+      - "type: bug"
+  - name: docs
+    description: |
+      Documentation
+      changes
+    skip_if_exists: true
+label_group:
+- prefix: area/
+  names: [api, cli]
+"#;
+        assert_eq!(
+            parse(yaml).unwrap(),
+            json!({
+                "name": "Core labels",
+                "delete": ["wontfix", "duplicate"],
+                "labels": [
+                    {"name": "bug", "color": "d73a49", "update_if_match": ["Bug", "type: bug"]},
+                    {"name": "docs", "description": "Documentation\nchanges\n", "skip_if_exists": true}
+                ],
+                "label_group": [{"prefix": "area/", "names": ["api", "cli"]}]
+            })
+        );
+    }
+
+    #[test]
+    fn test_scalars() {
+        assert_eq!(scalar("~").unwrap(), Value::Null);
+        assert_eq!(scalar("False").unwrap(), json!(false));
+        assert_eq!(scalar("42").unwrap(), json!("42"));
+        assert_eq!(scalar("0075ca").unwrap(), json!("0075ca"));
+        assert_eq!(scalar(r#""say \"hi\"\n""#).unwrap(), json!("say \"hi\"\n"));
+        assert_eq!(scalar("'it''s # not a comment'").unwrap(), json!("it's # not a comment"));
+        assert_eq!(parse("description: >-\n  one\n  two\n\n  three\n").unwrap(), json!({"description": "one two\nthree"}));
+    }
+
+    #[test]
+    fn test_unsupported_yaml_is_an_error() {
+        assert!(parse("labels: {name: bug}").unwrap_err().contains("flow mappings"));
+        assert!(parse("base: &base\n  color: red").unwrap_err().contains("anchors"));
+        assert!(parse("a: 1\na: 2").unwrap_err().contains("duplicate key `a`"));
+        assert!(parse("a: 1\n   b: 2").unwrap_err().contains("line 2"));
+        assert!(parse("just text").unwrap_err().contains("expected `key: value`"));
+    }
+}